@@ -0,0 +1,50 @@
+//! Sends DSHOT600 frames to an ESC on PA8 (TIM1 CH1) using DMA2 stream 1
+
+#![no_main]
+#![no_std]
+
+// Halt on panic
+use panic_halt as _;
+
+use cortex_m_rt::entry;
+use stm32f4xx_hal::{
+    dma::{Stream1, StreamsTuple},
+    pac,
+    prelude::*,
+    timer::dshot::{Dshot, DshotSpeed, DSHOT_BUF_LEN},
+};
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+    let cp = cortex_m::Peripherals::take().unwrap();
+
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc.cfgr.sysclk(168.MHz()).freeze();
+
+    let gpioa = dp.GPIOA.split();
+    let ch1 = dp
+        .TIM1
+        .pwm_hz(
+            gpioa.pa8.into_alternate(),
+            DshotSpeed::Dshot600.bitrate(),
+            &clocks,
+        )
+        .split();
+
+    let buf = cortex_m::singleton!(: [u16; DSHOT_BUF_LEN] = [0; DSHOT_BUF_LEN]).unwrap();
+    let stream = StreamsTuple::new(dp.DMA2).1;
+    let mut dshot: Dshot<Stream1<pac::DMA2>, 6, pac::TIM1, 0, u16> = Dshot::new(stream, ch1, buf);
+
+    let mut delay = cp.SYST.delay(&clocks);
+
+    // Arm the ESC with zero throttle, then spin slowly
+    for _ in 0..1000 {
+        dshot.send(0, false).ok();
+        delay.delay_ms(1u32);
+    }
+    loop {
+        dshot.send(200, false).ok();
+        delay.delay_ms(1u32);
+    }
+}
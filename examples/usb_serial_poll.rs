@@ -27,14 +27,11 @@ fn main() -> ! {
 
     let gpioa = dp.GPIOA.split();
 
-    let usb = USB {
-        usb_global: dp.OTG_FS_GLOBAL,
-        usb_device: dp.OTG_FS_DEVICE,
-        usb_pwrclk: dp.OTG_FS_PWRCLK,
-        pin_dm: gpioa.pa11.into_alternate(),
-        pin_dp: gpioa.pa12.into_alternate(),
-        hclk: clocks.hclk(),
-    };
+    let usb = USB::new(
+        (dp.OTG_FS_GLOBAL, dp.OTG_FS_DEVICE, dp.OTG_FS_PWRCLK),
+        (gpioa.pa11, gpioa.pa12),
+        &clocks,
+    );
 
     let usb_bus = UsbBus::new(usb, unsafe { &mut EP_MEMORY });
 
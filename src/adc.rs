@@ -3,11 +3,6 @@
 
 #![deny(missing_docs)]
 
-/*
-    Currently unused but this is the formula for using temperature calibration:
-    Temperature in °C = (110-30) * (adc_sample - VtempCal30::get().read()) / (VtempCal110::get().read()-VtempCal30::get().read()) + 30
-*/
-
 use crate::dma::traits::{PeriAddress, SafePeripheralRead};
 use crate::rcc::{Enable, Reset};
 use crate::{
@@ -27,6 +22,29 @@ pub struct Vbat;
 /// Core temperature internal signal
 pub struct Temperature;
 
+/// ADC interrupt events
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Event {
+    /// End of conversion of a regular channel or sequence
+    Eoc,
+    /// End of conversion of the injected channels
+    Jeoc,
+    /// Analog watchdog
+    Awd,
+    /// Overrun, a regular result was not read before the next conversion completed
+    Overrun,
+}
+
+/// ADC error
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A regular result was overwritten before it was read
+    Overrun,
+}
+
 macro_rules! adc_pins {
     ($($pin:ty => ($adc:ident, $chan:expr)),+ $(,)*) => {
         $(
@@ -360,6 +378,16 @@ pub mod config {
         }
     }
 
+    /// Discontinuous mode enable/disable
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum Discontinuous {
+        /// Each trigger converts the whole sequence
+        Disabled,
+        /// Each trigger converts the next 1 - 8 channels of the sequence
+        Channels(u8),
+    }
+
     /// DMA mode
     #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -372,6 +400,58 @@ pub mod config {
         Continuous,
     }
 
+    /// Multi ADC mode of ADC1 and ADC2
+    #[cfg(feature = "adc2")]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum DualMode {
+        /// Regular simultaneous mode, both ADCs convert their sequence at the same time
+        RegularSimultaneous,
+        /// Interleaved mode, the ADCs convert the same channel alternately
+        Interleaved,
+        /// Combined regular simultaneous and injected simultaneous mode
+        RegularInjectedSimultaneous,
+        /// Combined regular simultaneous and alternate trigger mode
+        RegularSimultaneousAlternateTrigger,
+    }
+    #[cfg(feature = "adc2")]
+    impl From<DualMode> for u8 {
+        fn from(m: DualMode) -> u8 {
+            match m {
+                DualMode::RegularSimultaneous => 0b00110,
+                DualMode::Interleaved => 0b00111,
+                DualMode::RegularInjectedSimultaneous => 0b00001,
+                DualMode::RegularSimultaneousAlternateTrigger => 0b00010,
+            }
+        }
+    }
+
+    /// Multi ADC mode of ADC1, ADC2 and ADC3
+    #[cfg(feature = "adc3")]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum TripleMode {
+        /// Regular simultaneous mode, all ADCs convert their sequence at the same time
+        RegularSimultaneous,
+        /// Interleaved mode, the ADCs convert the same channel one after the other
+        Interleaved,
+        /// Combined regular simultaneous and injected simultaneous mode
+        RegularInjectedSimultaneous,
+        /// Combined regular simultaneous and alternate trigger mode
+        RegularSimultaneousAlternateTrigger,
+    }
+    #[cfg(feature = "adc3")]
+    impl From<TripleMode> for u8 {
+        fn from(m: TripleMode) -> u8 {
+            match m {
+                TripleMode::RegularSimultaneous => 0b10110,
+                TripleMode::Interleaved => 0b10111,
+                TripleMode::RegularInjectedSimultaneous => 0b10001,
+                TripleMode::RegularSimultaneousAlternateTrigger => 0b10010,
+            }
+        }
+    }
+
     /// End-of-conversion interrupt enabled/disabled
     #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -396,6 +476,7 @@ pub mod config {
         pub(crate) scan: Scan,
         pub(crate) external_trigger: (TriggerMode, ExternalTrigger),
         pub(crate) continuous: Continuous,
+        pub(crate) discontinuous: Discontinuous,
         pub(crate) dma: Dma,
         pub(crate) end_of_conversion_interrupt: Eoc,
         pub(crate) default_sample_time: SampleTime,
@@ -437,6 +518,11 @@ pub mod config {
             self.continuous = continuous;
             self
         }
+        /// change the discontinuous field
+        pub fn discontinuous(mut self, discontinuous: Discontinuous) -> Self {
+            self.discontinuous = discontinuous;
+            self
+        }
         /// change the dma field
         pub fn dma(mut self, dma: Dma) -> Self {
             self.dma = dma;
@@ -472,6 +558,7 @@ pub mod config {
                 scan: Scan::Disabled,
                 external_trigger: (TriggerMode::Disabled, ExternalTrigger::Tim_1_cc_1),
                 continuous: Continuous::Single,
+                discontinuous: Discontinuous::Disabled,
                 dma: Dma::Disabled,
                 end_of_conversion_interrupt: Eoc::Disabled,
                 default_sample_time: SampleTime::Cycles_480,
@@ -493,8 +580,6 @@ pub mod config {
 /// To fully support the right pins would require 10+ more features for the various variants.
 /// ## Todo
 /// * Injected conversions
-/// * Analog watchdog config
-/// * Discontinuous mode
 /// # Examples
 /// ## One-shot conversion
 /// ```
@@ -514,6 +599,19 @@ pub mod config {
 /// info!("pa3: {}mV", millivolts);
 /// ```
 ///
+/// ## embedded-hal `OneShot`
+/// Drivers written against embedded-hal take the ADC and the pin. The conversion uses the
+/// default sample time of the config, a disabled ADC is enabled for the conversion only.
+/// ```
+/// use embedded_hal::adc::OneShot;
+/// use stm32f4xx_hal::adc::{config::AdcConfig, config::SampleTime, Adc};
+///
+/// let config = AdcConfig::default().default_sample_time(SampleTime::Cycles_144);
+/// let mut adc = Adc::adc1(device.ADC1, true, config);
+/// let mut pa3 = gpioa.pa3.into_analog();
+/// let sample: u16 = nb::block!(adc.read(&mut pa3)).unwrap();
+/// ```
+///
 /// ## Sequence conversion
 /// ```
 /// use stm32f4xx_hal::{
@@ -548,6 +646,89 @@ pub mod config {
 /// adc.configure_channel(&pa3, Sequence::Two, SampleTime::Cycles_480);
 /// adc.configure_channel(&pa0, Sequence::Three, SampleTime::Cycles_112);
 /// adc.start_conversion();
+///
+/// // In the ADC interrupt, the samples arrive in sequence order, reading the data register
+/// // clears the end-of-conversion flag
+/// let sample = adc.current_sample();
+/// ```
+///
+/// Up to 16 conversions are scanned in order, a channel can appear several times.
+/// [`configure_channel`](Adc::configure_channel) only extends the sequence, start over with
+/// [`reset_sequence`](Adc::reset_sequence) to convert fewer channels. With DMA the buffer
+/// should hold a multiple of [`sequence_length`](Adc::sequence_length) samples.
+///
+/// ## Continuous conversion with DMA
+/// With [`Dma::Continuous`](config::Dma::Continuous) the ADC keeps requesting DMA transfers, so
+/// a sequence converted continuously or on every trigger is written into the memory buffer of
+/// a [`Transfer`](crate::dma::Transfer). The half transfer and transfer complete interrupts of
+/// the stream signal when each half of the buffer is filled, the buffer is swapped with
+/// `next_transfer` or the double buffer mode of the transfer. The latest sample is read with
+/// [`current_sample`](Adc::current_sample) through `transfer.peripheral()`.
+/// ```
+/// use stm32f4xx_hal::{
+///   adc::{config::{AdcConfig, Continuous, Dma, SampleTime, Scan, Sequence}, Adc},
+///   dma::{config::DmaConfig, StreamsTuple, Transfer},
+/// };
+///
+/// let config = AdcConfig::default()
+///     .dma(Dma::Continuous)
+///     .scan(Scan::Enabled)
+///     .continuous(Continuous::Continuous);
+/// let mut adc = Adc::adc1(device.ADC1, true, config);
+/// adc.configure_channel(&pa0, Sequence::One, SampleTime::Cycles_480);
+/// adc.configure_channel(&pa3, Sequence::Two, SampleTime::Cycles_480);
+///
+/// let buffer = cortex_m::singleton!(: [u16; 64] = [0; 64]).unwrap();
+/// let streams = StreamsTuple::new(device.DMA2);
+/// let dma_config = DmaConfig::default()
+///     .memory_increment(true)
+///     .half_transfer_interrupt(true)
+///     .transfer_complete_interrupt(true);
+/// let mut transfer =
+///     Transfer::init_peripheral_to_memory(streams.0, adc, buffer, None, dma_config);
+/// transfer.start(|adc| adc.start_conversion());
+/// ```
+///
+/// ## Double buffered streaming
+/// Gap free acquisition uses the double buffer mode of the DMA stream: the DMA fills one buffer
+/// while the other is processed. After each transfer complete interrupt
+/// [`next_transfer`](crate::dma::Transfer::next_transfer) hands out the filled buffer in
+/// exchange for the processed one, it fails with `NotReady` when called too early.
+/// ```
+/// let dma_config = DmaConfig::default()
+///     .memory_increment(true)
+///     .double_buffer(true)
+///     .transfer_complete_interrupt(true);
+/// let mut transfer =
+///     Transfer::init_peripheral_to_memory(streams.0, adc, first, Some(second), dma_config);
+/// transfer.start(|adc| adc.start_conversion());
+///
+/// // In the DMA interrupt, `processed` is the buffer handed out by the previous call
+/// let (filled, _) = transfer.next_transfer(processed).unwrap();
+/// ```
+///
+/// If the samples are not read in time, the ADC sets the overrun flag, see
+/// [`Event::Overrun`], and stops requesting DMA transfers. To restart, clear the flag,
+/// toggle the DMA mode of the ADC and restart the transfer and the conversion:
+/// ```
+/// transfer.pause(|adc| {
+///     adc.clear_flag(Event::Overrun);
+///     adc.set_dma(Dma::Disabled);
+///     adc.set_dma(Dma::Continuous);
+/// });
+/// transfer.start(|adc| adc.start_conversion());
+/// ```
+///
+/// ## Analog watchdog
+/// ```
+/// // Flag samples of pa3 outside of 1000 - 3000 while converting continuously
+/// adc.enable_analog_watchdog_channel(&pa3, 1000, 3000);
+/// adc.set_analog_watchdog_interrupt(true);
+///
+/// // In the ADC interrupt
+/// if adc.is_analog_watchdog_triggered() {
+///     adc.clear_analog_watchdog_flag();
+/// }
 /// ```
 ///
 /// ## External trigger
@@ -621,7 +802,7 @@ pub struct Adc<ADC> {
     adc_reg: ADC,
     /// VDDA in millivolts calculated from the factory calibration and vrefint
     calibrated_vdda: u32,
-    /// Exclusive limit for the sample value possible for the configured resolution.
+    /// Exclusive limit for the sample value possible for the configured resolution and alignment.
     max_sample: u32,
 }
 impl<ADC> fmt::Debug for Adc<ADC> {
@@ -647,10 +828,13 @@ macro_rules! adc {
                 self.enable_temperature_and_vref();
             }
 
-            let vref_cal = VrefCal::get().read();
-            let vref_samp = self.read(&mut Vref).unwrap(); //This can't actually fail, it's just in a result to satisfy hal trait
+            let vref_cal = VrefCal::get();
+            //VREFINT must be sampled for at least 10 µs
+            let vref_samp = self.convert(&Vref, config::SampleTime::Cycles_480);
 
-            self.calibrated_vdda = (VDDA_CALIB * u32::from(vref_cal)) / u32::from(vref_samp);
+            //The calibration value is a 12 bit sample
+            let vref_samp = (u32::from(vref_samp) << 12) / self.max_sample;
+            self.calibrated_vdda = vref_cal.vdda_millivolts(vref_samp as u16);
             if !vref_en {
                 self.disable_temperature_and_vref();
             }
@@ -672,14 +856,26 @@ macro_rules! adc {
             }
         }
 
+        /// Returns if the vbat internal channel is enabled
+        pub fn vbat_enabled(&self) -> bool {
+            unsafe {
+                let common = &(*pac::$common_type::ptr());
+                common.ccr.read().vbate().bit_is_set()
+            }
+        }
+
         /// Enables the temp and vref internal channels.
         /// They can't work while vbat is also enabled so this method also disables vbat.
+        /// Waits for the 10 µs startup time of the channels if they were disabled.
         pub fn enable_temperature_and_vref(&mut self) {
             //VBAT prevents TS and VREF from being sampled
             self.disable_vbat();
-            unsafe {
-                let common = &(*pac::$common_type::ptr());
-                common.ccr.modify(|_, w| w.tsvrefe().set_bit());
+            if !self.temperature_and_vref_enabled() {
+                unsafe {
+                    let common = &(*pac::$common_type::ptr());
+                    common.ccr.modify(|_, w| w.tsvrefe().set_bit());
+                }
+                cortex_m::asm::delay(crate::rcc::SYSCLK_MAX / 100_000);
             }
         }
 
@@ -698,6 +894,67 @@ macro_rules! adc {
                 common.ccr.read().tsvrefe().bit_is_set()
             }
         }
+
+        /// Converts a sample of the temperature sensor to degrees Celsius, using the factory
+        /// calibration at 30 °C and 110 °C corrected for the calibrated VDDA and the resolution.
+        pub fn sample_to_celsius(&self, sample: u16) -> f32 {
+            //The calibration values are 12 bit samples
+            let sample = (u32::from(sample) << 12) / self.max_sample;
+            crate::signature::temperature_celsius(sample as u16, self.calibrated_vdda)
+        }
+
+        /// Synchronously converts the temperature sensor to degrees Celsius
+        /// The sensor is sampled for the required 10 µs and left in the state it was found in.
+        pub fn read_temperature(&mut self) -> f32 {
+            let enabled = self.is_enabled();
+            let ts_en = self.temperature_and_vref_enabled();
+            if !ts_en {
+                self.enable_temperature_and_vref();
+            }
+            let sample = self.convert(&Temperature, config::SampleTime::Cycles_480);
+            if !ts_en {
+                self.disable_temperature_and_vref();
+            }
+            if !enabled {
+                self.disable();
+            }
+            self.sample_to_celsius(sample)
+        }
+
+        /// Synchronously converts VBAT to millivolts
+        /// The channel is left in the state it was found in, a channel enabled only for the
+        /// conversion is disabled again, so it doesn't drain the battery.
+        pub fn read_vbat(&mut self) -> u16 {
+            //VBAT is divided internally before the ADC
+            #[cfg(any(
+                feature = "stm32f405",
+                feature = "stm32f407",
+                feature = "stm32f415",
+                feature = "stm32f417"
+            ))]
+            const VBAT_DIV: u16 = 2;
+            #[cfg(not(any(
+                feature = "stm32f405",
+                feature = "stm32f407",
+                feature = "stm32f415",
+                feature = "stm32f417"
+            )))]
+            const VBAT_DIV: u16 = 4;
+
+            let enabled = self.is_enabled();
+            let vbat_en = self.vbat_enabled();
+            if !vbat_en {
+                self.enable_vbat();
+            }
+            let sample = self.convert(&Vbat, config::SampleTime::Cycles_480);
+            if !vbat_en {
+                self.disable_vbat();
+            }
+            if !enabled {
+                self.disable();
+            }
+            self.sample_to_millivolts(sample) * VBAT_DIV
+        }
     };
 
     // Provide a stub implementation for ADCs that do not have a means of sampling VREF.
@@ -761,6 +1018,7 @@ macro_rules! adc {
                     self.set_scan(config.scan);
                     self.set_external_trigger(config.external_trigger);
                     self.set_continuous(config.continuous);
+                    self.set_discontinuous(config.discontinuous);
                     self.set_dma(config.dma);
                     self.set_end_of_conversion_interrupt(config.end_of_conversion_interrupt);
                     self.set_default_sample_time(config.default_sample_time);
@@ -810,20 +1068,30 @@ macro_rules! adc {
                 }
 
                 /// Sets the sampling resolution
+                /// Lower resolutions take less ADC clock cycles per conversion, 15 for 12-bit down to 9 for 6-bit
+                /// with the shortest sample time.
                 pub fn set_resolution(&mut self, resolution: config::Resolution) {
-                    self.max_sample = match resolution {
-                        config::Resolution::Twelve => (1 << 12),
-                        config::Resolution::Ten => (1 << 10),
-                        config::Resolution::Eight => (1 << 8),
-                        config::Resolution::Six => (1 << 6),
-                    };
                     self.config.resolution = resolution;
+                    self.update_max_sample();
                     self.adc_reg.cr1.modify(|_, w| w.res().bits(resolution.into()));
                 }
 
+                fn update_max_sample(&mut self) {
+                    self.max_sample = match (self.config.align, self.config.resolution) {
+                        //Left aligned samples fill the upper bits of the half word, 6-bit samples of the byte
+                        (config::Align::Left, config::Resolution::Six) => (1 << 8),
+                        (config::Align::Left, _) => (1 << 16),
+                        (config::Align::Right, config::Resolution::Twelve) => (1 << 12),
+                        (config::Align::Right, config::Resolution::Ten) => (1 << 10),
+                        (config::Align::Right, config::Resolution::Eight) => (1 << 8),
+                        (config::Align::Right, config::Resolution::Six) => (1 << 6),
+                    };
+                }
+
                 /// Sets the DR register alignment to left or right
                 pub fn set_align(&mut self, align: config::Align) {
                     self.config.align = align;
+                    self.update_max_sample();
                     self.adc_reg.cr2.modify(|_, w| w.align().bit(align.into()));
                 }
 
@@ -862,6 +1130,22 @@ macro_rules! adc {
                     self.adc_reg.cr2.modify(|_, w| w.cont().bit(continuous.into()));
                 }
 
+                /// Enables discontinuous mode, each trigger converts the next channels of the sequence
+                /// in bursts, starting over after the last one. Scan mode must be enabled and continuous mode
+                /// disabled.
+                /// # Panics
+                /// Will panic if the number of channels is not in 1 - 8
+                pub fn set_discontinuous(&mut self, discontinuous: config::Discontinuous) {
+                    self.config.discontinuous = discontinuous;
+                    match discontinuous {
+                        config::Discontinuous::Disabled => self.adc_reg.cr1.modify(|_, w| w.discen().clear_bit()),
+                        config::Discontinuous::Channels(n) => {
+                            assert!((1..=8).contains(&n));
+                            self.adc_reg.cr1.modify(|_, w| w.discnum().bits(n - 1).discen().set_bit());
+                        }
+                    }
+                }
+
                 /// Sets DMA to disabled, single or continuous
                 pub fn set_dma(&mut self, dma: config::Dma) {
                     self.config.dma = dma;
@@ -897,6 +1181,115 @@ macro_rules! adc {
                     self.adc_reg.sr.modify(|_, w| w.eoc().clear_bit());
                 }
 
+                /// Starts listening for an interrupt event
+                /// `Event::Eoc` keeps the end-of-conversion behaviour of the config, or selects
+                /// one interrupt per conversion if it was disabled.
+                pub fn listen(&mut self, event: Event) {
+                    match event {
+                        Event::Eoc => if self.config.end_of_conversion_interrupt == config::Eoc::Disabled {
+                            self.set_end_of_conversion_interrupt(config::Eoc::Conversion);
+                        },
+                        Event::Jeoc => self.adc_reg.cr1.modify(|_, w| w.jeocie().set_bit()),
+                        Event::Awd => self.adc_reg.cr1.modify(|_, w| w.awdie().set_bit()),
+                        Event::Overrun => self.adc_reg.cr1.modify(|_, w| w.ovrie().set_bit()),
+                    }
+                }
+
+                /// Stops listening for an interrupt event
+                pub fn unlisten(&mut self, event: Event) {
+                    match event {
+                        Event::Eoc => self.set_end_of_conversion_interrupt(config::Eoc::Disabled),
+                        Event::Jeoc => self.adc_reg.cr1.modify(|_, w| w.jeocie().clear_bit()),
+                        Event::Awd => self.adc_reg.cr1.modify(|_, w| w.awdie().clear_bit()),
+                        Event::Overrun => self.adc_reg.cr1.modify(|_, w| w.ovrie().clear_bit()),
+                    }
+                }
+
+                /// Returns true if the flag of the event is set
+                pub fn is_flag_set(&self, event: Event) -> bool {
+                    let sr = self.adc_reg.sr.read();
+                    match event {
+                        Event::Eoc => sr.eoc().bit_is_set(),
+                        Event::Jeoc => sr.jeoc().bit_is_set(),
+                        Event::Awd => sr.awd().bit_is_set(),
+                        Event::Overrun => sr.ovr().bit_is_set(),
+                    }
+                }
+
+                /// Resets the flag of the event
+                pub fn clear_flag(&mut self, event: Event) {
+                    self.adc_reg.sr.modify(|_, w| match event {
+                        Event::Eoc => w.eoc().clear_bit(),
+                        Event::Jeoc => w.jeoc().clear_bit(),
+                        Event::Awd => w.awd().clear_bit(),
+                        Event::Overrun => w.ovr().clear_bit(),
+                    });
+                }
+
+                /// Reads the result of a completed regular conversion
+                /// Returns `WouldBlock` while no conversion is completed, reading clears the end-of-conversion flag.
+                /// After an overrun the flag is cleared and the conversion must be restarted.
+                pub fn read_sample(&mut self) -> nb::Result<u16, Error> {
+                    let sr = self.adc_reg.sr.read();
+                    if sr.ovr().bit_is_set() {
+                        self.clear_flag(Event::Overrun);
+                        Err(nb::Error::Other(Error::Overrun))
+                    } else if sr.eoc().bit_is_set() {
+                        Ok(self.current_sample())
+                    } else {
+                        Err(nb::Error::WouldBlock)
+                    }
+                }
+
+                /// Enables the analog watchdog on all regular channels
+                ///
+                /// The watchdog flag is set when a conversion result is below `low` or above `high`.
+                /// The thresholds are 12 bit values, independent of the resolution and alignment.
+                pub fn enable_analog_watchdog(&mut self, low: u16, high: u16) {
+                    self.set_analog_watchdog_thresholds(low, high);
+                    self.adc_reg.cr1.modify(|_, w| w.awdsgl().clear_bit().awden().set_bit());
+                }
+
+                /// Enables the analog watchdog on a single regular channel
+                ///
+                /// See [enable_analog_watchdog](#method.enable_analog_watchdog) for the thresholds.
+                pub fn enable_analog_watchdog_channel<CHANNEL>(&mut self, _channel: &CHANNEL, low: u16, high: u16)
+                where
+                    CHANNEL: embedded_hal::adc::Channel<pac::$adc_type, ID=u8>
+                {
+                    self.set_analog_watchdog_thresholds(low, high);
+                    self.adc_reg.cr1.modify(|_, w| unsafe {
+                        w.awdch().bits(CHANNEL::channel())
+                            .awdsgl().set_bit()
+                            .awden().set_bit()
+                    });
+                }
+
+                fn set_analog_watchdog_thresholds(&mut self, low: u16, high: u16) {
+                    self.adc_reg.ltr.write(|w| w.lt().bits(low & 0xfff));
+                    self.adc_reg.htr.write(|w| w.ht().bits(high & 0xfff));
+                }
+
+                /// Disables the analog watchdog
+                pub fn disable_analog_watchdog(&mut self) {
+                    self.adc_reg.cr1.modify(|_, w| w.awden().clear_bit());
+                }
+
+                /// Enables/disables the analog watchdog interrupt
+                pub fn set_analog_watchdog_interrupt(&mut self, enabled: bool) {
+                    self.adc_reg.cr1.modify(|_, w| w.awdie().bit(enabled));
+                }
+
+                /// Returns true if a conversion result was out of the watchdog thresholds
+                pub fn is_analog_watchdog_triggered(&self) -> bool {
+                    self.adc_reg.sr.read().awd().bit_is_set()
+                }
+
+                /// Resets the analog watchdog flag
+                pub fn clear_analog_watchdog_flag(&mut self) {
+                    self.adc_reg.sr.modify(|_, w| w.awd().clear_bit());
+                }
+
                 /// Sets the default sample time that is used for one-shot conversions.
                 /// [configure_channel](#method.configure_channel) and [start_conversion](#method.start_conversion) can be \
                 /// used for configurations where different sampling times are required per channel.
@@ -927,10 +1320,12 @@ macro_rules! adc {
                 /// * `sequence` - where in the sequence to sample the channel. Also called rank in some STM docs/code
                 /// * `sample_time` - how long to sample for. See datasheet and ref manual to work out how long you need\
                 /// to sample for at a given ADC clock frequency
-                pub fn configure_channel<CHANNEL>(&mut self, _channel: &CHANNEL, sequence: config::Sequence, sample_time: config::SampleTime)
+                pub fn configure_channel<CHANNEL>(&mut self, channel: &CHANNEL, sequence: config::Sequence, sample_time: config::SampleTime)
                 where
                     CHANNEL: embedded_hal::adc::Channel<pac::$adc_type, ID=u8>
                 {
+                    self.set_channel_sample_time(channel, sample_time);
+
                     //Check the sequence is long enough
                     self.adc_reg.sqr1.modify(|r, w| {
                         let prev: config::Sequence = r.l().bits().into();
@@ -962,7 +1357,15 @@ macro_rules! adc {
                         config::Sequence::Fifteen  => self.adc_reg.sqr1.modify(|_, w| unsafe {w.sq15().bits(channel) }),
                         config::Sequence::Sixteen  => self.adc_reg.sqr1.modify(|_, w| unsafe {w.sq16().bits(channel) }),
                     }
+                }
 
+                /// Sets the sample time of a channel without changing the sequence
+                /// The sample time applies to every position of the channel in the sequence.
+                /// High impedance sources need longer sample times, see "ADC characteristics" in the datasheet.
+                pub fn set_channel_sample_time<CHANNEL>(&mut self, _channel: &CHANNEL, sample_time: config::SampleTime)
+                where
+                    CHANNEL: embedded_hal::adc::Channel<pac::$adc_type, ID=u8>
+                {
                     fn replace_bits(mut v: u32, offset: u32, width: u32, value: u32) -> u32 {
                         let mask = !(((1 << width) -1) << (offset * width));
                         v &= mask;
@@ -970,6 +1373,8 @@ macro_rules! adc {
                         v
                     }
 
+                    let channel = CHANNEL::channel();
+
                     //Set the sample time for the channel
                     let st = u8::from(sample_time);
                     let st = u32::from(st);
@@ -981,6 +1386,20 @@ macro_rules! adc {
                     }
                 }
 
+                /// Returns the sample time of a channel
+                pub fn channel_sample_time<CHANNEL>(&self, _channel: &CHANNEL) -> config::SampleTime
+                where
+                    CHANNEL: embedded_hal::adc::Channel<pac::$adc_type, ID=u8>
+                {
+                    let ch = u32::from(CHANNEL::channel());
+                    let st = match ch {
+                        0..=9   => self.adc_reg.smpr2.read().bits() >> (ch * 3),
+                        10..=18 => self.adc_reg.smpr1.read().bits() >> ((ch - 10) * 3),
+                        _ => unimplemented!(),
+                    };
+                    ((st & 0b111) as u8).into()
+                }
+
                 /// Returns the current sample stored in the ADC data register
                 pub fn current_sample(&self) -> u16 {
                     self.adc_reg.dr.read().data().bits()
@@ -1104,6 +1523,235 @@ adc!(ADC2 => (adc2, ADC_COMMON, 9));
 #[cfg(feature = "adc3")]
 adc!(ADC3 => (adc3, ADC_COMMON, 10));
 
+/// Configures the multi ADC mode, `mode` 0 is independent mode
+#[cfg(feature = "adc2")]
+fn set_multi_mode(mode: u8, dma: config::Dma) {
+    // NOTE(unsafe) the common registers are only changed while the ADCs are owned by a
+    // multi ADC, or when it is released
+    let common = unsafe { &*pac::ADC_COMMON::ptr() };
+    common.ccr.modify(|_, w| {
+        // DMA mode 2, one 32 bit word per pair of conversions
+        w.dma()
+            .bits(if dma == config::Dma::Disabled { 0 } else { 2 });
+        w.dds().bit(dma == config::Dma::Continuous);
+        unsafe { w.multi().bits(mode) }
+    });
+}
+
+/// Sets the delay between two sampling phases in interleaved mode to `cycles` ADC clock cycles
+#[cfg(feature = "adc2")]
+fn set_multi_delay(cycles: u8) {
+    assert!((5..=20).contains(&cycles));
+    let common = unsafe { &*pac::ADC_COMMON::ptr() };
+    common.ccr.modify(|_, w| w.delay().bits(cycles - 5));
+}
+
+/// ADC1 and ADC2 in dual mode
+///
+/// ADC1 is the master, its external trigger or [`start_conversion`](Self::start_conversion)
+/// starts both ADCs. The sequences, sample times and resolution are configured on the single
+/// ADCs before they are combined. The results are read from the common data register, with DMA
+/// one 32 bit word holds the ADC1 result in the lower and the ADC2 result in the upper half.
+///
+/// ```
+/// // Sample two phase currents at the same time
+/// adc1.configure_channel(&pa0, Sequence::One, SampleTime::Cycles_15);
+/// adc2.configure_channel(&pa1, Sequence::One, SampleTime::Cycles_15);
+/// let mut adc = DualAdc::new(adc1, adc2, DualMode::RegularSimultaneous, Dma::Disabled);
+/// adc.enable();
+/// adc.start_conversion();
+/// adc.wait_for_conversion_sequence();
+/// let (ia, ib) = adc.current_sample();
+/// ```
+#[cfg(feature = "adc2")]
+pub struct DualAdc {
+    master: Adc<pac::ADC1>,
+    slave: Adc<pac::ADC2>,
+}
+
+#[cfg(feature = "adc2")]
+impl DualAdc {
+    /// Combines ADC1 and ADC2 in `mode`, the DMA requests of the single ADCs are disabled
+    pub fn new(
+        mut master: Adc<pac::ADC1>,
+        mut slave: Adc<pac::ADC2>,
+        mode: config::DualMode,
+        dma: config::Dma,
+    ) -> Self {
+        master.set_dma(config::Dma::Disabled);
+        slave.set_dma(config::Dma::Disabled);
+        set_multi_mode(mode.into(), dma);
+        Self { master, slave }
+    }
+
+    /// Sets the delay between the sampling phases in interleaved mode, 5 - 20 ADC clock cycles
+    pub fn set_delay(&mut self, cycles: u8) {
+        set_multi_delay(cycles);
+    }
+
+    /// Enables both ADCs
+    pub fn enable(&mut self) {
+        self.master.enable();
+        self.slave.enable();
+    }
+
+    /// Disables both ADCs
+    pub fn disable(&mut self) {
+        self.master.disable();
+        self.slave.disable();
+    }
+
+    /// Starts conversion of both ADCs
+    pub fn start_conversion(&mut self) {
+        self.master.start_conversion();
+    }
+
+    /// Blocks until the conversion of the master is completed
+    pub fn wait_for_conversion_sequence(&self) {
+        self.master.wait_for_conversion_sequence();
+    }
+
+    /// Returns the latest ADC1 and ADC2 results from the common data register
+    pub fn current_sample(&self) -> (u16, u16) {
+        let cdr = unsafe { &*pac::ADC_COMMON::ptr() }.cdr.read();
+        (cdr.data1().bits(), cdr.data2().bits())
+    }
+
+    /// ADC1, e.g. to convert samples to millivolts
+    pub fn master(&self) -> &Adc<pac::ADC1> {
+        &self.master
+    }
+
+    /// ADC2
+    pub fn slave(&self) -> &Adc<pac::ADC2> {
+        &self.slave
+    }
+
+    /// Returns to independent mode and releases the ADCs
+    pub fn release(self) -> (Adc<pac::ADC1>, Adc<pac::ADC2>) {
+        set_multi_mode(0, config::Dma::Disabled);
+        (self.master, self.slave)
+    }
+}
+
+#[cfg(feature = "adc2")]
+impl SafePeripheralRead for DualAdc {}
+
+#[cfg(feature = "adc2")]
+unsafe impl PeriAddress for DualAdc {
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        unsafe { &(*pac::ADC_COMMON::ptr()).cdr as *const _ as u32 }
+    }
+
+    type MemSize = u32;
+}
+
+/// ADC1, ADC2 and ADC3 in triple mode
+///
+/// Like [`DualAdc`] with ADC2 and ADC3 as slaves. With DMA one 32 bit word holds two results,
+/// in the order ADC1 and ADC2, ADC3 and ADC1, ADC2 and ADC3.
+#[cfg(feature = "adc3")]
+pub struct TripleAdc {
+    master: Adc<pac::ADC1>,
+    slave1: Adc<pac::ADC2>,
+    slave2: Adc<pac::ADC3>,
+}
+
+#[cfg(feature = "adc3")]
+impl TripleAdc {
+    /// Combines ADC1, ADC2 and ADC3 in `mode`, the DMA requests of the single ADCs are disabled
+    pub fn new(
+        mut master: Adc<pac::ADC1>,
+        mut slave1: Adc<pac::ADC2>,
+        mut slave2: Adc<pac::ADC3>,
+        mode: config::TripleMode,
+        dma: config::Dma,
+    ) -> Self {
+        master.set_dma(config::Dma::Disabled);
+        slave1.set_dma(config::Dma::Disabled);
+        slave2.set_dma(config::Dma::Disabled);
+        set_multi_mode(mode.into(), dma);
+        Self {
+            master,
+            slave1,
+            slave2,
+        }
+    }
+
+    /// Sets the delay between the sampling phases in interleaved mode, 5 - 20 ADC clock cycles
+    pub fn set_delay(&mut self, cycles: u8) {
+        set_multi_delay(cycles);
+    }
+
+    /// Enables all ADCs
+    pub fn enable(&mut self) {
+        self.master.enable();
+        self.slave1.enable();
+        self.slave2.enable();
+    }
+
+    /// Disables all ADCs
+    pub fn disable(&mut self) {
+        self.master.disable();
+        self.slave1.disable();
+        self.slave2.disable();
+    }
+
+    /// Starts conversion of all ADCs
+    pub fn start_conversion(&mut self) {
+        self.master.start_conversion();
+    }
+
+    /// Blocks until the conversion of the master is completed
+    pub fn wait_for_conversion_sequence(&self) {
+        self.master.wait_for_conversion_sequence();
+    }
+
+    /// Returns the latest two results from the common data register
+    ///
+    /// The register holds one pair at a time in the same order as the DMA words, ADC1 and
+    /// ADC2, ADC3 and ADC1, ADC2 and ADC3.
+    pub fn current_sample(&self) -> (u16, u16) {
+        let cdr = unsafe { &*pac::ADC_COMMON::ptr() }.cdr.read();
+        (cdr.data1().bits(), cdr.data2().bits())
+    }
+
+    /// ADC1, e.g. to convert samples to millivolts
+    pub fn master(&self) -> &Adc<pac::ADC1> {
+        &self.master
+    }
+
+    /// ADC2
+    pub fn slave1(&self) -> &Adc<pac::ADC2> {
+        &self.slave1
+    }
+
+    /// ADC3
+    pub fn slave2(&self) -> &Adc<pac::ADC3> {
+        &self.slave2
+    }
+
+    /// Returns to independent mode and releases the ADCs
+    pub fn release(self) -> (Adc<pac::ADC1>, Adc<pac::ADC2>, Adc<pac::ADC3>) {
+        set_multi_mode(0, config::Dma::Disabled);
+        (self.master, self.slave1, self.slave2)
+    }
+}
+
+#[cfg(feature = "adc3")]
+impl SafePeripheralRead for TripleAdc {}
+
+#[cfg(feature = "adc3")]
+unsafe impl PeriAddress for TripleAdc {
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        unsafe { &(*pac::ADC_COMMON::ptr()).cdr as *const _ as u32 }
+    }
+
+    type MemSize = u32;
+}
+
 #[cfg(feature = "stm32f401")]
 adc_pins!(
     gpio::PA0<Analog> => (ADC1, 0),
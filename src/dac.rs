@@ -1,10 +1,83 @@
 //! # API for the Digital to Analog converter
 //!
-//! Currently only supports writing to the DR of the DAC,
-//! just a basic one-shot conversion.
+//! Supports writing to the data holding registers of the DAC, a basic one-shot
+//! conversion. Values can be written as 12-bit right aligned, 12-bit left aligned
+//! or 8-bit right aligned data, the output is updated one APB1 clock cycle later.
+//!
+//! ```ignore
+//! let mut dac = dp.DAC.constrain(gpioa.pa4.into_analog());
+//! dac.enable();
+//! // 12-bit right aligned
+//! dac.set_value(2048);
+//! // 8-bit, same output voltage
+//! dac.set_value_8bit(128);
+//! // 12-bit left aligned, e.g. 16-bit audio samples with the lower bits ignored
+//! dac.set_value_left_aligned(0x8000);
+//! ```
+//!
+//! The output buffer is enabled by default, it can be disabled for high impedance loads
+//! with [`DacExt::constrain_with_output_buffer`] or per channel later.
+//!
+//! ```ignore
+//! let mut vref = dp.DAC.constrain_with_output_buffer(gpioa.pa5.into_analog(), OutputBuffer::Disabled);
+//! ```
+//!
+//! ## Waveform playback with DMA
+//!
+//! With DMA the channel loads the next sample from a buffer on each trigger, a timer
+//! `TRGO` update event sets the sample rate. In circular mode the buffer is played
+//! repeatedly without any CPU involvement.
+//!
+//! ```ignore
+//! static mut SINE: [u16; 32] = [/* 12-bit right aligned samples */];
+//!
+//! let mut dac = dp.DAC.constrain(gpioa.pa4.into_analog());
+//! dac.enable_dma(Trigger::Tim6);
+//! dac.enable();
+//!
+//! let streams = StreamsTuple::new(dp.DMA1);
+//! let config = DmaConfig::default()
+//!     .memory_increment(true)
+//!     .circular_buffer(true);
+//! let mut transfer = Transfer::init_memory_to_peripheral(
+//!     streams.5,
+//!     dac,
+//!     unsafe { &SINE },
+//!     None,
+//!     config,
+//! );
+//! transfer.start(|_| {});
+//!
+//! // 32 kHz sample rate, 1 kHz sine
+//! let mut tim6 = dp.TIM6.counter_hz(&clocks);
+//! tim6.set_master_mode(Mms::Update);
+//! tim6.start(32.kHz()).unwrap();
+//! ```
+//!
+//! If DMA can't keep up with the trigger, the channel stops requesting transfers and
+//! [`is_dma_underrun`](C1::is_dma_underrun) is set.
+//!
+//! ## Dual channel output
+//!
+//! [`DualDac`] writes both channels with one register access, so e.g. stereo or
+//! differential outputs change on the same clock edge.
+//! With a [`Trigger`] both outputs are only updated on the next trigger event.
+//!
+//! ```ignore
+//! let (c1, c2) = dp.DAC.constrain((gpioa.pa4.into_analog(), gpioa.pa5.into_analog()));
+//! let mut dac = DualDac::new(c1, c2);
+//! dac.enable();
+//! dac.set_values(1024, 3072);
+//!
+//! // prepare the next values, output them later
+//! dac.set_trigger(Trigger::Software);
+//! dac.set_values(3072, 1024);
+//! dac.trigger();
+//! ```
 #![deny(unused_imports)]
 
 use crate::{
+    dma::traits::PeriAddress,
     gpio::{Analog, PA4, PA5},
     pac::{DAC, RCC},
     rcc::{Enable, Reset},
@@ -13,6 +86,34 @@ use crate::{
 pub struct C1;
 pub struct C2;
 
+/// Conversion trigger, the data holding register is only loaded into the output on
+/// this event once a trigger is enabled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Trigger {
+    /// TIM6 TRGO
+    Tim6 = 0,
+    /// TIM8 TRGO
+    #[cfg(not(feature = "stm32f410"))]
+    Tim8 = 1,
+    /// TIM7 TRGO
+    #[cfg(not(feature = "stm32f410"))]
+    Tim7 = 2,
+    /// TIM5 TRGO
+    Tim5 = 3,
+    /// TIM2 TRGO
+    #[cfg(not(feature = "stm32f410"))]
+    Tim2 = 4,
+    /// TIM4 TRGO
+    #[cfg(not(feature = "stm32f410"))]
+    Tim4 = 5,
+    /// EXTI line 9, e.g. a GPIO pin 9 selected with
+    /// [`make_interrupt_source`](crate::gpio::ExtiPin::make_interrupt_source)
+    Exti9 = 6,
+    /// Software trigger with `trigger()`
+    Software = 7,
+}
+
 pub trait DacOut<V> {
     fn set_value(&mut self, val: V);
     fn get_value(&mut self) -> V;
@@ -22,34 +123,58 @@ pub trait DacPin {
     fn enable(&mut self);
 }
 
+/// Output buffer of a channel
+///
+/// The buffer reduces the output impedance to drive loads without an external op amp.
+/// Without the buffer the output can go closer to the supply rails, e.g. for a precision
+/// reference into a high impedance input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OutputBuffer {
+    Enabled,
+    Disabled,
+}
+
 pub trait Pins<DAC> {
     type Output;
     #[doc(hidden)]
-    fn init() -> Self::Output;
+    fn init(buffer: OutputBuffer) -> Self::Output;
 }
 
 impl Pins<DAC> for PA4<Analog> {
     type Output = C1;
-    fn init() -> Self::Output {
-        C1
+    fn init(buffer: OutputBuffer) -> Self::Output {
+        let mut c1 = C1;
+        c1.set_output_buffer(buffer);
+        c1
     }
 }
 
 impl Pins<DAC> for PA5<Analog> {
     type Output = C2;
-    fn init() -> Self::Output {
-        C2
+    fn init(buffer: OutputBuffer) -> Self::Output {
+        let mut c2 = C2;
+        c2.set_output_buffer(buffer);
+        c2
     }
 }
 
 impl Pins<DAC> for (PA4<Analog>, PA5<Analog>) {
     type Output = (C1, C2);
-    fn init() -> Self::Output {
-        (C1, C2)
+    fn init(buffer: OutputBuffer) -> Self::Output {
+        (PA4::<Analog>::init(buffer), PA5::<Analog>::init(buffer))
     }
 }
 
-pub fn dac<PINS>(_dac: DAC, _pins: PINS) -> PINS::Output
+pub fn dac<PINS>(dac: DAC, pins: PINS) -> PINS::Output
+where
+    PINS: Pins<DAC>,
+{
+    dac_with_output_buffer(dac, pins, OutputBuffer::Enabled)
+}
+
+/// Like [`dac`] with the output buffer of the channels enabled or disabled
+pub fn dac_with_output_buffer<PINS>(_dac: DAC, _pins: PINS, buffer: OutputBuffer) -> PINS::Output
 where
     PINS: Pins<DAC>,
 {
@@ -60,13 +185,13 @@ where
         // Enable and reset clock.
         DAC::enable(rcc);
         DAC::reset(rcc);
-
-        PINS::init()
     }
+
+    PINS::init(buffer)
 }
 
 macro_rules! dac {
-    ($CX:ident, $en:ident, $cen:ident, $cal_flag:ident, $trim:ident, $mode:ident, $dhrx:ident, $dac_dor:ident, $daccxdhr:ident) => {
+    ($CX:ident, $en:ident, $cen:ident, $cal_flag:ident, $trim:ident, $mode:ident, $dhrx:ident, $dhrl:ident, $dhr8:ident, $dac_dor:ident, $daccxdhr:ident, $tsel:ident, $ten:ident, $dmaen:ident, $dmaudr:ident, $boff:ident, $swtrig:ident) => {
         impl DacPin for $CX {
             fn enable(&mut self) {
                 let dac = unsafe { &(*DAC::ptr()) };
@@ -74,6 +199,107 @@ macro_rules! dac {
             }
         }
 
+        impl $CX {
+            /// Enables or disables the output buffer, takes effect when the channel is enabled
+            pub fn set_output_buffer(&mut self, buffer: OutputBuffer) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr
+                    .modify(|_, w| w.$boff().bit(buffer == OutputBuffer::Disabled));
+            }
+
+            /// Disables the channel, the output is high impedance
+            pub fn disable(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr.modify(|_, w| w.$en().clear_bit());
+            }
+
+            /// Writes an 8-bit right aligned value
+            pub fn set_value_8bit(&mut self, val: u8) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.$dhr8.write(|w| unsafe { w.bits(val as u32) });
+            }
+
+            /// Writes a 12-bit left aligned value, the lower 4 bits are ignored
+            pub fn set_value_left_aligned(&mut self, val: u16) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.$dhrl.write(|w| unsafe { w.bits(val as u32 & 0xfff0) });
+            }
+
+            /// Loads written values into the output only on `trigger` events
+            ///
+            /// The output is updated 3 APB1 clock cycles after a hardware trigger, or one
+            /// cycle after [`trigger`](Self::trigger) with `Trigger::Software`.
+            pub fn set_trigger(&mut self, trigger: Trigger) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                #[allow(unused_unsafe)]
+                dac.cr
+                    .modify(|_, w| unsafe { w.$tsel().bits(trigger as u8).$ten().set_bit() });
+            }
+
+            /// Loads written values into the output immediately again
+            pub fn disable_trigger(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr.modify(|_, w| w.$ten().clear_bit());
+            }
+
+            /// Software trigger, loads the last written value into the output
+            ///
+            /// Only takes effect with `Trigger::Software` selected, the bit is cleared by
+            /// hardware.
+            pub fn trigger(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.swtrigr.write(|w| w.$swtrig().set_bit());
+            }
+
+            /// Loads a sample from DMA into the output on each `trigger` event
+            ///
+            /// The DMA stream must write 12-bit right aligned samples to this channel.
+            pub fn enable_dma(&mut self, trigger: Trigger) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                #[allow(unused_unsafe)]
+                dac.cr.modify(|_, w| unsafe {
+                    w.$tsel()
+                        .bits(trigger as u8)
+                        .$ten()
+                        .set_bit()
+                        .$dmaen()
+                        .set_bit()
+                });
+            }
+
+            /// Stops DMA requests, values written are converted immediately again
+            pub fn disable_dma(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.cr
+                    .modify(|_, w| w.$dmaen().clear_bit().$ten().clear_bit());
+            }
+
+            /// A trigger occurred before DMA transferred the previous sample
+            ///
+            /// No further DMA requests are generated until the flag is cleared, either
+            /// the trigger frequency is too high or the DMA stream stopped.
+            pub fn is_dma_underrun(&self) -> bool {
+                let dac = unsafe { &(*DAC::ptr()) };
+                dac.sr.read().$dmaudr().bit_is_set()
+            }
+
+            /// Clears the DMA underrun flag
+            pub fn clear_dma_underrun(&mut self) {
+                let dac = unsafe { &(*DAC::ptr()) };
+                // the flag is cleared by writing 1, the other bits are ignored
+                dac.sr.write(|w| w.$dmaudr().set_bit());
+            }
+        }
+
+        unsafe impl PeriAddress for $CX {
+            #[inline(always)]
+            fn address(&self) -> u32 {
+                unsafe { &(*DAC::ptr()).$dhrx as *const _ as u32 }
+            }
+
+            type MemSize = u16;
+        }
+
         impl DacOut<u16> for $CX {
             fn set_value(&mut self, val: u16) {
                 let dac = unsafe { &(*DAC::ptr()) };
@@ -92,6 +318,11 @@ pub trait DacExt {
     fn constrain<PINS>(self, pins: PINS) -> PINS::Output
     where
         PINS: Pins<DAC>;
+
+    /// Like `constrain` with the output buffer of the channels enabled or disabled
+    fn constrain_with_output_buffer<PINS>(self, pins: PINS, buffer: OutputBuffer) -> PINS::Output
+    where
+        PINS: Pins<DAC>;
 }
 
 impl DacExt for DAC {
@@ -101,7 +332,130 @@ impl DacExt for DAC {
     {
         dac(self, pins)
     }
+
+    fn constrain_with_output_buffer<PINS>(self, pins: PINS, buffer: OutputBuffer) -> PINS::Output
+    where
+        PINS: Pins<DAC>,
+    {
+        dac_with_output_buffer(self, pins, buffer)
+    }
 }
 
-dac!(C1, en1, cen1, cal_flag1, otrim1, mode1, dhr12r1, dor1, dacc1dhr);
-dac!(C2, en2, cen2, cal_flag2, otrim2, mode2, dhr12r2, dor2, dacc2dhr);
+dac!(
+    C1, en1, cen1, cal_flag1, otrim1, mode1, dhr12r1, dhr12l1, dhr8r1, dor1, dacc1dhr, tsel1, ten1,
+    dmaen1, dmaudr1, boff1, swtrig1
+);
+dac!(
+    C2, en2, cen2, cal_flag2, otrim2, mode2, dhr12r2, dhr12l2, dhr8r2, dor2, dacc2dhr, tsel2, ten2,
+    dmaen2, dmaudr2, boff2, swtrig2
+);
+
+/// Both DAC channels written at once
+///
+/// The dual data holding registers hold the values of both channels, they are loaded
+/// into the outputs at the same time. With DMA one 32 bit word holds the samples of
+/// channel 1 in the lower and channel 2 in the upper half word, the requests of
+/// channel 1 are used.
+pub struct DualDac {
+    c1: C1,
+    c2: C2,
+}
+
+impl DualDac {
+    pub fn new(c1: C1, c2: C2) -> Self {
+        Self { c1, c2 }
+    }
+
+    /// Enables both channels
+    pub fn enable(&mut self) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.cr.modify(|_, w| w.en1().set_bit().en2().set_bit());
+    }
+
+    /// Disables both channels
+    pub fn disable(&mut self) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.cr.modify(|_, w| w.en1().clear_bit().en2().clear_bit());
+    }
+
+    /// Writes 12-bit right aligned values
+    pub fn set_values(&mut self, c1: u16, c2: u16) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.dhr12rd
+            .write(|w| unsafe { w.bits((c1 as u32 & 0xfff) | (c2 as u32 & 0xfff) << 16) });
+    }
+
+    /// Writes 12-bit left aligned values, the lower 4 bits are ignored
+    pub fn set_values_left_aligned(&mut self, c1: u16, c2: u16) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.dhr12ld
+            .write(|w| unsafe { w.bits((c1 as u32 & 0xfff0) | (c2 as u32 & 0xfff0) << 16) });
+    }
+
+    /// Writes 8-bit right aligned values
+    pub fn set_values_8bit(&mut self, c1: u8, c2: u8) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.dhr8rd
+            .write(|w| unsafe { w.bits(c1 as u32 | (c2 as u32) << 8) });
+    }
+
+    /// Loads written values into both outputs only on `trigger` events
+    pub fn set_trigger(&mut self, trigger: Trigger) {
+        self.c1.set_trigger(trigger);
+        self.c2.set_trigger(trigger);
+    }
+
+    /// Loads written values into both outputs immediately again
+    pub fn disable_trigger(&mut self) {
+        self.c1.disable_trigger();
+        self.c2.disable_trigger();
+    }
+
+    /// Software trigger of both channels at once
+    pub fn trigger(&mut self) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.swtrigr
+            .write(|w| w.swtrig1().set_bit().swtrig2().set_bit());
+    }
+
+    /// Loads samples from DMA into both outputs on each `trigger` event
+    ///
+    /// The DMA stream of channel 1 must write words with both 12-bit right aligned samples.
+    pub fn enable_dma(&mut self, trigger: Trigger) {
+        self.c2.disable_dma();
+        let dac = unsafe { &(*DAC::ptr()) };
+        #[allow(unused_unsafe)]
+        dac.cr.modify(|_, w| unsafe {
+            w.tsel2()
+                .bits(trigger as u8)
+                .ten2()
+                .set_bit()
+                .tsel1()
+                .bits(trigger as u8)
+                .ten1()
+                .set_bit()
+                .dmaen1()
+                .set_bit()
+        });
+    }
+
+    /// Stops DMA requests, values written are converted immediately again
+    pub fn disable_dma(&mut self) {
+        self.c1.disable_dma();
+        self.c2.disable_dma();
+    }
+
+    /// Releases the channels
+    pub fn release(self) -> (C1, C2) {
+        (self.c1, self.c2)
+    }
+}
+
+unsafe impl PeriAddress for DualDac {
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        unsafe { &(*DAC::ptr()).dhr12rd as *const _ as u32 }
+    }
+
+    type MemSize = u32;
+}
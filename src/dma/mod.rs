@@ -485,6 +485,13 @@ where
             .modify(|_, w| w.dbm().bit(double_buffer));
     }
 
+    #[inline(always)]
+    fn set_circular_buffer(&mut self, circular_buffer: bool) {
+        unsafe { Self::st() }
+            .cr
+            .modify(|_, w| w.circ().bit(circular_buffer));
+    }
+
     #[inline(always)]
     fn set_fifo_threshold(&mut self, fifo_threshold: config::FifoThreshold) {
         unsafe { Self::st() }
@@ -759,6 +766,7 @@ pub mod config {
         pub(crate) direct_mode_error_interrupt: bool,
         pub(crate) fifo_error_interrupt: bool,
         pub(crate) double_buffer: bool,
+        pub(crate) circular_buffer: bool,
         pub(crate) fifo_threshold: FifoThreshold,
         pub(crate) fifo_enable: bool,
         pub(crate) memory_burst: BurstMode,
@@ -777,6 +785,7 @@ pub mod config {
                 direct_mode_error_interrupt: false,
                 fifo_error_interrupt: false,
                 double_buffer: false,
+                circular_buffer: false,
                 fifo_threshold: FifoThreshold::QuarterFull,
                 fifo_enable: false,
                 memory_burst: BurstMode::NoBurst,
@@ -841,6 +850,15 @@ pub mod config {
             self.double_buffer = double_buffer;
             self
         }
+        /// Set the circular_buffer.
+        ///
+        /// The stream restarts from the beginning of the buffer after the last transfer, until
+        /// it is disabled, e.g. to play a waveform repeatedly. Always on with double buffering.
+        #[inline(always)]
+        pub fn circular_buffer(mut self, circular_buffer: bool) -> Self {
+            self.circular_buffer = circular_buffer;
+            self
+        }
         /// Set the fifo_threshold.
         #[inline(always)]
         pub fn fifo_threshold(mut self, fifo_threshold: FifoThreshold) -> Self {
@@ -1374,6 +1392,7 @@ where
         stream.set_direct_mode_error_interrupt_enable(config.direct_mode_error_interrupt);
         stream.set_fifo_error_interrupt_enable(config.fifo_error_interrupt);
         stream.set_double_buffer(config.double_buffer);
+        stream.set_circular_buffer(config.circular_buffer);
         stream.set_fifo_threshold(config.fifo_threshold);
         stream.set_fifo_enable(config.fifo_enable);
         stream.set_memory_burst(config.memory_burst);
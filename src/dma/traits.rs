@@ -166,6 +166,9 @@ pub trait Stream: StreamISR + Sealed {
     /// Enable/disable the double buffer (dbm) of the DMA stream.
     fn set_double_buffer(&mut self, double_buffer: bool);
 
+    /// Enable/disable the circular mode (circ) of the DMA stream.
+    fn set_circular_buffer(&mut self, circular_buffer: bool);
+
     /// Set the fifo threshold (fcr.fth) of the DMA stream.
     fn set_fifo_threshold(&mut self, fifo_threshold: config::FifoThreshold);
 
@@ -275,6 +278,16 @@ pub trait Channel {}
 /// Memory corruption might occur if this trait is implemented for an invalid combination.
 pub unsafe trait DMASet<STREAM, const CHANNEL: u8, DIRECTION> {}
 
+// 16 bit SPI frames use the same requests as 8 bit frames
+unsafe impl<SPI, STREAM, const CHANNEL: u8, DIR> DMASet<STREAM, CHANNEL, DIR> for spi::Tx<SPI, u16> where
+    spi::Tx<SPI>: DMASet<STREAM, CHANNEL, DIR>
+{
+}
+unsafe impl<SPI, STREAM, const CHANNEL: u8, DIR> DMASet<STREAM, CHANNEL, DIR> for spi::Rx<SPI, u16> where
+    spi::Rx<SPI>: DMASet<STREAM, CHANNEL, DIR>
+{
+}
+
 macro_rules! dma_map {
     ($(($Stream:ty, $C:literal, $Peripheral:ty, $Dir:ty)),+ $(,)*) => {
         $(
@@ -456,6 +469,7 @@ dma_map!(
     (Stream2<DMA2>, 5, pac::USART6, PeripheralToMemory), //USART6_RX
     (Stream2<DMA2>, 5, serial::Rx<pac::USART6>, PeripheralToMemory), //USART6_RX
     (Stream4<DMA2>, 0, pac::ADC1, PeripheralToMemory), //ADC1
+    (Stream4<DMA2>, 0, Adc<pac::ADC1>, PeripheralToMemory), //ADC1
     (Stream5<DMA2>, 4, pac::USART1, PeripheralToMemory), //USART1_RX
     (Stream5<DMA2>, 4, serial::Rx<pac::USART1>, PeripheralToMemory), //USART1_RX
     (Stream6<DMA2>, 5, pac::USART6, MemoryToPeripheral), //USART6_TX
@@ -637,7 +651,6 @@ dma_map!(
     (Stream0<DMA1>, 4, pac::UART5, PeripheralToMemory), //UART5_RX
     (Stream2<DMA1>, 4, pac::UART4, PeripheralToMemory), //UART4_RX
     (Stream4<DMA1>, 4, pac::UART4, MemoryToPeripheral), //UART4_TX
-    //(Stream6<DMA1>, 7, pac::DAC2, MemoryToPeripheral), //DAC2
 );
 
 #[cfg(any(
@@ -655,11 +668,7 @@ dma_map!(
     feature = "stm32f469",
     feature = "stm32f479",
 ))]
-address!(
-    (pac::UART4, dr, u8),
-    (pac::UART5, dr, u8),
-    //(pac::DAC, ??),
-);
+address!((pac::UART4, dr, u8), (pac::UART5, dr, u8),);
 
 #[cfg(any(
     feature = "stm32f417",
@@ -793,46 +802,12 @@ dma_map!(
 ))]
 address!((pac::HASH, din, u32), (pac::CRYP, din, u32),);
 
-/* Not sure how DAC works with DMA
-#[cfg(any(
-    feature = "stm32f417",
-    feature = "stm32f415",
-    feature = "stm32f405",
-    feature = "stm32f407",
-    feature = "stm32f410",
-    feature = "stm32f413",
-    feature = "stm32f423",
-    feature = "stm32f427",
-    feature = "stm32f439",
-    feature = "stm32f437",
-    feature = "stm32f429",
-    feature = "stm32f446",
-    feature = "stm32f469",
-    feature = "stm32f479",
-))]
+#[cfg(feature = "dac")]
 dma_map!(
-    (pac::DMA1, Stream5, 7, pac::DAC, MemoryToPeripheral), //DAC1
-);
-#[cfg(any(
-    feature = "stm32f417",
-    feature = "stm32f415",
-    feature = "stm32f405",
-    feature = "stm32f407",
-    feature = "stm32f410",
-    feature = "stm32f413",
-    feature = "stm32f423",
-    feature = "stm32f427",
-    feature = "stm32f439",
-    feature = "stm32f437",
-    feature = "stm32f429",
-    feature = "stm32f446",
-    feature = "stm32f469",
-    feature = "stm32f479",
-))]
-address!(
-    (pac::DAC, ??),
+    (Stream5<DMA1>, 7, crate::dac::C1, MemoryToPeripheral), //DAC1
+    (Stream6<DMA1>, 7, crate::dac::C2, MemoryToPeripheral), //DAC2
+    (Stream5<DMA1>, 7, crate::dac::DualDac, MemoryToPeripheral), //DAC1
 );
-*/
 
 #[cfg(any(
     feature = "stm32f417",
@@ -855,8 +830,8 @@ dma_map!(
     (Stream2<DMA2>, 1, pac::ADC2, PeripheralToMemory),  //ADC2
     (Stream3<DMA2>, 1, pac::ADC2, PeripheralToMemory),  //ADC2
     (Stream7<DMA2>, 1, pac::DCMI, PeripheralToMemory),  //DCMI
-    (Stream2<DMA2>, 1, Adc<pac::ADC3>, PeripheralToMemory), //ADC2
-    (Stream3<DMA2>, 1, Adc<pac::ADC3>, PeripheralToMemory), //ADC2
+    (Stream2<DMA2>, 1, Adc<pac::ADC2>, PeripheralToMemory), //ADC2
+    (Stream3<DMA2>, 1, Adc<pac::ADC2>, PeripheralToMemory), //ADC2
     (Stream0<DMA2>, 2, Adc<pac::ADC3>, PeripheralToMemory), //ADC3
     (Stream1<DMA2>, 2, Adc<pac::ADC3>, PeripheralToMemory), //ADC3
 );
@@ -879,6 +854,17 @@ address!(
     (pac::DCMI, dr, u32),
 );
 
+#[cfg(feature = "adc2")]
+dma_map!(
+    (Stream0<DMA2>, 0, crate::adc::DualAdc, PeripheralToMemory), //ADC1
+    (Stream4<DMA2>, 0, crate::adc::DualAdc, PeripheralToMemory), //ADC1
+);
+#[cfg(feature = "adc3")]
+dma_map!(
+    (Stream0<DMA2>, 0, crate::adc::TripleAdc, PeripheralToMemory), //ADC1
+    (Stream4<DMA2>, 0, crate::adc::TripleAdc, PeripheralToMemory), //ADC1
+);
+
 /* FMPI2C missing from peripheral crates (?)
 #[cfg(any(
     feature = "stm32f410",
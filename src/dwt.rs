@@ -43,6 +43,16 @@ impl Dwt {
     pub fn stopwatch<'i>(&self, times: &'i mut [u32]) -> StopWatch<'i> {
         StopWatch::new(times, self.clock)
     }
+    /// Returns the current value of the DWT cycle counter (CYCCNT)
+    ///
+    /// The counter runs at `HCLK` and wraps around to 0 on overflow.
+    pub fn cycle_count(&self) -> u32 {
+        DWT::cycle_count()
+    }
+    /// Returns the frequency at which the cycle counter is running
+    pub fn frequency(&self) -> Hertz {
+        self.clock
+    }
     /// Measure cycles it takes to execute closure `f`.
     ///
     /// Since DWT Cycle Counter is a 32-bit counter that wraps around to 0 on overflow,
@@ -66,6 +76,30 @@ impl Delay {
         let ticks = duration.ticks as u64;
         Delay::delay_ticks(DWT::cycle_count(), ticks);
     }
+    /// Delay for given time
+    ///
+    /// Accepts any `fugit` duration, e.g. `100.nanos()`, `10.micros()` or `5.millis()`.
+    /// The delay is rounded up to the next cycle boundary.
+    pub fn delay_for<const NOM: u32, const DENOM: u32>(
+        &mut self,
+        duration: fugit::Duration<u32, NOM, DENOM>,
+    ) {
+        let start = DWT::cycle_count();
+        // The product needs up to 96 bits, e.g. for minutes at 180 MHz
+        let den = DENOM as u128;
+        let ticks =
+            (duration.ticks() as u128 * NOM as u128 * self.clock.raw() as u128 + den - 1) / den;
+        Delay::delay_ticks(start, u64::try_from(ticks).unwrap_or(u64::MAX));
+    }
+    /// Delay for `ns` nanoseconds
+    ///
+    /// Resolution is one core clock cycle, so short delays are rounded up to the
+    /// next cycle boundary.
+    pub fn delay_ns(&mut self, ns: u32) {
+        let start = DWT::cycle_count();
+        let ticks = (ns as u64 * self.clock.raw() as u64 + 999_999_999) / 1_000_000_000;
+        Delay::delay_ticks(start, ticks);
+    }
     /// Delay ticks
     /// NOTE DCB and DWT need to be set up for this to work, so it is private
     fn delay_ticks(mut start: u32, ticks: u64) {
@@ -78,7 +112,7 @@ impl Delay {
             let mut ticks = ticks as u32;
             ticks -= core::u32::MAX / 2;
             while (DWT::cycle_count().wrapping_sub(start)) < core::u32::MAX / 2 {}
-            start -= core::u32::MAX / 2;
+            start = start.wrapping_add(core::u32::MAX / 2);
             while (DWT::cycle_count().wrapping_sub(start)) < ticks {}
         } else {
             // Delay for ticks, then delay for rest * u32::MAX
@@ -260,14 +294,19 @@ impl MonoTimer {
     pub fn now(self) -> Instant {
         Instant {
             now: DWT::cycle_count(),
+            frequency: self.frequency,
         }
     }
 }
 
 /// A measurement of a monotonically non-decreasing clock
+///
+/// The cycle counter wraps around after `2^32` cycles (~25 s at 168 MHz),
+/// so only shorter intervals can be measured.
 #[derive(Clone, Copy)]
 pub struct Instant {
     now: u32,
+    frequency: Hertz,
 }
 
 impl Instant {
@@ -275,4 +314,18 @@ impl Instant {
     pub fn elapsed(self) -> u32 {
         DWT::cycle_count().wrapping_sub(self.now)
     }
+
+    /// Time elapsed since the `Instant` was created
+    ///
+    /// ```ignore
+    /// let start = mono.now();
+    /// work();
+    /// let us = start.elapsed_time().as_micros();
+    /// ```
+    pub fn elapsed_time(self) -> ClockDuration {
+        ClockDuration {
+            ticks: self.elapsed(),
+            clock: self.frequency,
+        }
+    }
 }
@@ -0,0 +1,272 @@
+//! # External interrupt/event controller
+//!
+//! EXTI lines 0 - 15 are connected to GPIO pins and usually configured with
+//! [`ExtiPin`](crate::gpio::ExtiPin). The other lines are connected to internal
+//! peripherals. All lines can be configured through [`ExtiExt`] with a [`Line`]. Each line can
+//! generate an interrupt, an event (which wakes up a `WFE`) or both. Most lines have
+//! their own NVIC interrupt, see the vector table of the reference manual.
+//! [`ExtiExt::enable_wakeup`] prepares a line to wake the MCU up from Stop mode.
+//!
+//! ```ignore
+//! // RTC wakeup timer interrupt, also wakes the MCU up from Stop mode
+//! dp.EXTI.listen(Line::RtcWakeup, Edge::Rising);
+//! unsafe { cortex_m::peripheral::NVIC::unmask(pac::Interrupt::RTC_WKUP) };
+//!
+//! // in the RTC_WKUP handler
+//! dp.EXTI.clear(Line::RtcWakeup);
+//!
+//! // defer work to the EXTI1 handler from software
+//! let line = Line::gpio(1).unwrap();
+//! exti.listen(line, Edge::Rising);
+//! exti.trigger(line);
+//! ```
+
+use crate::gpio::Edge;
+use crate::pac::EXTI;
+
+/// EXTI line 0 - 15 of the GPIO pins with the same number
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GpioLine(pub(crate) u8);
+
+impl GpioLine {
+    /// GPIO line `n`, `None` if `n` is greater than 15
+    pub const fn new(n: u8) -> Option<Self> {
+        if n < 16 {
+            Some(Self(n))
+        } else {
+            None
+        }
+    }
+
+    pub const fn number(self) -> u8 {
+        self.0
+    }
+}
+
+/// EXTI lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Line {
+    /// GPIO line 0 - 15, the port is selected by
+    /// [`make_interrupt_source`](crate::gpio::ExtiPin::make_interrupt_source)
+    Gpio(GpioLine),
+    /// PVD output
+    Pvd,
+    /// RTC alarm event
+    RtcAlarm,
+    /// USB OTG FS wakeup event
+    #[cfg(feature = "otg-fs")]
+    OtgFsWakeup,
+    /// Ethernet wakeup event
+    #[cfg(any(
+        feature = "stm32f407",
+        feature = "stm32f417",
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    EthernetWakeup,
+    /// USB OTG HS wakeup event
+    #[cfg(feature = "otg-hs")]
+    OtgHsWakeup,
+    /// RTC tamper and timestamp events
+    RtcTamperTimestamp,
+    /// RTC wakeup timer event
+    RtcWakeup,
+    /// LPTIM1 asynchronous event
+    #[cfg(any(feature = "stm32f410", feature = "stm32f413", feature = "stm32f423"))]
+    Lptim1,
+}
+
+impl Line {
+    /// GPIO line `n`, `None` if `n` is greater than 15
+    pub const fn gpio(n: u8) -> Option<Self> {
+        match GpioLine::new(n) {
+            Some(line) => Some(Self::Gpio(line)),
+            None => None,
+        }
+    }
+
+    /// Line number, the bit in the EXTI registers
+    pub const fn number(self) -> u8 {
+        match self {
+            Self::Gpio(line) => line.number(),
+            Self::Pvd => 16,
+            Self::RtcAlarm => 17,
+            #[cfg(feature = "otg-fs")]
+            Self::OtgFsWakeup => 18,
+            #[cfg(any(
+                feature = "stm32f407",
+                feature = "stm32f417",
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            Self::EthernetWakeup => 19,
+            #[cfg(feature = "otg-hs")]
+            Self::OtgHsWakeup => 20,
+            Self::RtcTamperTimestamp => 21,
+            Self::RtcWakeup => 22,
+            #[cfg(any(feature = "stm32f410", feature = "stm32f413", feature = "stm32f423"))]
+            Self::Lptim1 => 23,
+        }
+    }
+}
+
+/// How a wakeup source wakes the MCU up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WakeupMode {
+    /// Interrupt, to wake up from `WFI`, the NVIC interrupt must be unmasked
+    Interrupt,
+    /// Event, to wake up from `WFE` without running an interrupt handler
+    Event,
+}
+
+/// EXTI line configured to wake the MCU up from Stop mode
+///
+/// Returned by [`ExtiExt::enable_wakeup`], so code entering Stop mode can require a
+/// wakeup source and check it with [`is_enabled`](Self::is_enabled).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WakeupSource {
+    line: Line,
+    mode: WakeupMode,
+}
+
+impl WakeupSource {
+    pub fn line(&self) -> Line {
+        self.line
+    }
+
+    pub fn mode(&self) -> WakeupMode {
+        self.mode
+    }
+
+    /// Checks that the line is still unmasked in the selected mode
+    pub fn is_enabled(&self, exti: &EXTI) -> bool {
+        let mask = match self.mode {
+            WakeupMode::Interrupt => exti.imr.read().bits(),
+            WakeupMode::Event => exti.emr.read().bits(),
+        };
+        mask & (1 << self.line.number()) != 0
+    }
+}
+
+/// Configuration of EXTI lines
+pub trait ExtiExt {
+    /// Generates an interrupt on `edge` of `line`
+    fn listen(&mut self, line: Line, edge: Edge);
+
+    /// Stops generating interrupts, the edge selection is kept for events
+    fn unlisten(&mut self, line: Line);
+
+    /// Generates an event on `edge` of `line`
+    fn listen_event(&mut self, line: Line, edge: Edge);
+
+    /// Stops generating events, the edge selection is kept for interrupts
+    fn unlisten_event(&mut self, line: Line);
+
+    /// Reads the pending bit of `line`
+    fn is_pending(&self, line: Line) -> bool;
+
+    /// Clears the pending bit of `line`
+    fn clear(&mut self, line: Line);
+
+    /// Sets the pending bit of `line` from software (`SWIER`)
+    ///
+    /// Generates an interrupt or event if enabled with `listen` or `listen_event`,
+    /// e.g. to defer work to a lower priority interrupt handler of an otherwise unused
+    /// line. The request is removed by [`clear`](Self::clear).
+    fn trigger(&mut self, line: Line);
+
+    /// Configures `line` to wake the MCU up from Stop mode on `edge`
+    ///
+    /// Enables either the interrupt or the event of the line and clears its pending bit,
+    /// a pending line would prevent entering Stop mode. GPIO pins must be selected with
+    /// [`make_interrupt_source`](crate::gpio::ExtiPin::make_interrupt_source) first, their
+    /// line is [`ExtiPin::line`](crate::gpio::ExtiPin::line).
+    fn enable_wakeup(&mut self, line: Line, edge: Edge, mode: WakeupMode) -> WakeupSource;
+}
+
+impl ExtiExt for EXTI {
+    fn listen(&mut self, line: Line, edge: Edge) {
+        set_edge(self, line.number(), edge);
+        self.imr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << line.number())) });
+    }
+
+    fn unlisten(&mut self, line: Line) {
+        self.imr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << line.number())) });
+    }
+
+    fn listen_event(&mut self, line: Line, edge: Edge) {
+        set_edge(self, line.number(), edge);
+        self.emr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << line.number())) });
+    }
+
+    fn unlisten_event(&mut self, line: Line) {
+        self.emr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << line.number())) });
+    }
+
+    fn is_pending(&self, line: Line) -> bool {
+        self.pr.read().bits() & (1 << line.number()) != 0
+    }
+
+    fn clear(&mut self, line: Line) {
+        // NOTE(unsafe) the pending bit is cleared by writing 1
+        self.pr.write(|w| unsafe { w.bits(1 << line.number()) });
+    }
+
+    fn trigger(&mut self, line: Line) {
+        // NOTE(unsafe) writing 1 triggers the line, 0 has no effect
+        self.swier.write(|w| unsafe { w.bits(1 << line.number()) });
+    }
+
+    fn enable_wakeup(&mut self, line: Line, edge: Edge, mode: WakeupMode) -> WakeupSource {
+        match mode {
+            WakeupMode::Interrupt => {
+                self.unlisten_event(line);
+                self.listen(line, edge);
+            }
+            WakeupMode::Event => {
+                self.unlisten(line);
+                self.listen_event(line, edge);
+            }
+        }
+        self.clear(line);
+        WakeupSource { line, mode }
+    }
+}
+
+fn set_edge(exti: &EXTI, i: u8, edge: Edge) {
+    let (rising, falling) = match edge {
+        Edge::Rising => (true, false),
+        Edge::Falling => (false, true),
+        Edge::RisingFalling => (true, true),
+    };
+    exti.rtsr.modify(|r, w| unsafe {
+        w.bits(if rising {
+            r.bits() | (1 << i)
+        } else {
+            r.bits() & !(1 << i)
+        })
+    });
+    exti.ftsr.modify(|r, w| unsafe {
+        w.bits(if falling {
+            r.bits() | (1 << i)
+        } else {
+            r.bits() & !(1 << i)
+        })
+    });
+}
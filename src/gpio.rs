@@ -53,6 +53,35 @@
 //!
 //! To make a pin dynamic, use the `into_dynamic` function, and then use the `make_<mode>` functions to
 //! change the mode
+//!
+//! ## Speed, pull and initial state
+//!
+//! Output and alternate function pins select the slew rate (`OSPEEDR`) with `speed`/`set_speed`,
+//! [`Speed::Low`] after reset. High speed interfaces like SDIO, FSMC or Ethernet RMII need
+//! [`Speed::High`] or [`Speed::VeryHigh`]. Pull-up and pull-down resistors are selected with
+//! `internal_resistor`/`set_internal_resistor` and outputs can start at a defined level with
+//! the `into_<mode>_output_in_state` functions, so no glitch is seen on the pin.
+//!
+//! ```rust
+//! let clk = gpioc.pc12.into_alternate::<12>().speed(Speed::VeryHigh);
+//! let cmd = gpiod.pd2.into_alternate::<12>().internal_pull_up(true).speed(Speed::VeryHigh);
+//! let enable = gpiob.pb0.into_push_pull_output_in_state(PinState::Low);
+//! ```
+//!
+//! ## Pin types
+//!
+//! All pins are the same type [`Pin<P, N, MODE>`](Pin) with the port letter `P` and pin number `N`
+//! as const generics, `PA5` is an alias for `Pin<'A', 5>`. Drivers can be generic over any pin in
+//! a mode without listing pins:
+//!
+//! ```rust
+//! fn blink<const P: char, const N: u8>(led: &mut Pin<P, N, Output>) {
+//!     led.toggle();
+//! }
+//! ```
+//!
+//! To store pins of different numbers or ports together, erase them into [`PartiallyErasedPin`]
+//! with `erase_number` or into [`ErasedPin`] with `erase`.
 
 use core::marker::PhantomData;
 
@@ -68,6 +97,10 @@ mod exti;
 pub use exti::ExtiPin;
 mod dynamic;
 pub use dynamic::{Dynamic, DynamicPin};
+mod port;
+pub use port::Port;
+mod lock;
+pub use lock::LockedPin;
 mod hal_02;
 mod hal_1;
 
@@ -228,6 +261,20 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
     const fn new() -> Self {
         Self { _mode: PhantomData }
     }
+
+    /// Creates the pin without the `Parts` of its port
+    ///
+    /// E.g. to use a pin in an interrupt handler after it was moved into another driver.
+    /// The pin configuration is not changed, use [`into_mode`](Self::into_mode) to switch
+    /// the mode.
+    ///
+    /// # Safety
+    ///
+    /// The port clock must be enabled, the pin must already be in `MODE` and must
+    /// not be used by other code at the same time.
+    pub const unsafe fn steal() -> Self {
+        Self::new()
+    }
 }
 
 impl<const P: char, const N: u8, MODE> fmt::Debug for Pin<P, N, MODE> {
@@ -438,13 +485,19 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, Output<MODE>> {
     }
 
     /// Toggle pin output
+    ///
+    /// Writes `BSRR` once with the set or reset bit computed from `ODR`, other pins of the
+    /// port changed meanwhile, e.g. from interrupts, are not affected.
     #[inline(always)]
     pub fn toggle(&mut self) {
-        if self.is_set_low() {
-            self.set_high()
-        } else {
-            self.set_low()
-        }
+        let gpio = unsafe { &*Gpio::<P>::ptr() };
+        let mask = 1 << N;
+        let odr = gpio.odr.read().bits();
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            gpio.bsrr
+                .write(|w| w.bits(((odr & mask) << 16) | (!odr & mask)))
+        };
     }
 }
 
@@ -132,6 +132,25 @@ impl<const P: char, const N: u8, MODE: PinMode> Pin<P, N, MODE> {
         self.into_mode()
     }
 
+    /// Configures the pin to operate in alternate open drain mode with the internal pull-up,
+    /// e.g. for I2C or 1-Wire
+    ///
+    /// The pull-up is enabled before the mode change, so the line doesn't float meanwhile.
+    pub fn into_alternate_open_drain_pull_up<const A: u8>(
+        self,
+    ) -> Pin<P, N, Alternate<A, OpenDrain>>
+    where
+        Self: marker::IntoAf<A>,
+    {
+        let offset = 2 * { N };
+        unsafe {
+            (*Gpio::<P>::ptr()).pupdr.modify(|r, w| {
+                w.bits((r.bits() & !(0b11 << offset)) | ((Pull::Up as u32) << offset))
+            });
+        }
+        self.into_mode()
+    }
+
     /// Configures the pin to operate as a input pin
     pub fn into_input(self) -> Pin<P, N, Input> {
         self.into_mode()
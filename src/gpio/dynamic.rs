@@ -10,6 +10,7 @@ pub struct DynamicPin<const P: char, const N: u8> {
 }
 
 /// Tracks the current pin state for dynamic pins
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Dynamic {
     /// Floating input mode
     InputFloating,
@@ -21,6 +22,8 @@ pub enum Dynamic {
     OutputPushPull,
     /// Open-drain output mode
     OutputOpenDrain,
+    /// Analog mode, neither readable nor writable
+    Analog,
 }
 
 /// Error for [DynamicPin]
@@ -36,7 +39,7 @@ impl Dynamic {
         use Dynamic::*;
         match self {
             InputFloating | InputPullUp | InputPullDown | OutputOpenDrain => true,
-            OutputPushPull => false,
+            OutputPushPull | Analog => false,
         }
     }
 
@@ -44,7 +47,7 @@ impl Dynamic {
     pub fn is_output(&self) -> bool {
         use Dynamic::*;
         match self {
-            InputFloating | InputPullUp | InputPullDown => false,
+            InputFloating | InputPullUp | InputPullDown | Analog => false,
             OutputPushPull | OutputOpenDrain => true,
         }
     }
@@ -61,6 +64,12 @@ impl<const P: char, const N: u8> DynamicPin<P, N> {
         Self { mode }
     }
 
+    /// Current pin mode
+    #[inline]
+    pub fn mode(&self) -> Dynamic {
+        self.mode
+    }
+
     /// Switch pin into pull-up input
     #[inline]
     pub fn make_pull_up_input(&mut self) {
@@ -110,6 +119,13 @@ impl<const P: char, const N: u8> DynamicPin<P, N> {
         Pin::<P, N, Unknown>::new().into_open_drain_output_in_state(state);
         self.mode = Dynamic::OutputOpenDrain;
     }
+    /// Switch pin into analog mode, e.g. to sample it with the ADC or to reduce consumption
+    #[inline]
+    pub fn make_analog(&mut self) {
+        // NOTE(unsafe), we have a mutable reference to the current pin
+        Pin::<P, N, Unknown>::new().into_analog();
+        self.mode = Dynamic::Analog;
+    }
 
     /// Drives the pin high
     pub fn set_high(&mut self) -> Result<(), PinModeError> {
@@ -128,13 +128,19 @@ impl<MODE> ErasedPin<Output<MODE>> {
     }
 
     /// Toggle pin output
+    ///
+    /// Writes `BSRR` once with the set or reset bit computed from `ODR`, other pins of the
+    /// port changed meanwhile, e.g. from interrupts, are not affected.
     #[inline(always)]
     pub fn toggle(&mut self) {
-        if self.is_set_low() {
-            self.set_high()
-        } else {
-            self.set_low()
-        }
+        let gpio = self.block();
+        let mask = 1 << self.pin_id();
+        let odr = gpio.odr.read().bits();
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            gpio.bsrr
+                .write(|w| w.bits(((odr & mask) << 16) | (!odr & mask)))
+        };
     }
 }
 
@@ -154,3 +160,29 @@ where
         self.block().idr.read().bits() & (1 << self.pin_id()) == 0
     }
 }
+
+impl<const P: char, const N: u8, MODE> TryFrom<ErasedPin<MODE>> for Pin<P, N, MODE> {
+    type Error = ErasedPin<MODE>;
+
+    /// Restores the pin type if the port is `P` and the pin number `N`, returns the pin otherwise
+    fn try_from(p: ErasedPin<MODE>) -> Result<Self, Self::Error> {
+        if p.port_id() == P as u8 - b'A' && p.pin_id() == N {
+            Ok(Self::new())
+        } else {
+            Err(p)
+        }
+    }
+}
+
+impl<const P: char, MODE> TryFrom<ErasedPin<MODE>> for PartiallyErasedPin<P, MODE> {
+    type Error = ErasedPin<MODE>;
+
+    /// Restores the port in the pin type if the port is `P`, returns the pin otherwise
+    fn try_from(p: ErasedPin<MODE>) -> Result<Self, Self::Error> {
+        if p.port_id() == P as u8 - b'A' {
+            Ok(Self::new(p.pin_id()))
+        } else {
+            Err(p)
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use super::{marker, Edge, Pin, PinExt};
 use crate::{
+    exti::{GpioLine, Line},
     pac::{Interrupt, EXTI},
     syscfg::SysCfg,
 };
@@ -24,6 +25,24 @@ impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
 }
 
 /// External Interrupt Pin
+///
+/// Implemented for pins in input and output modes. Each EXTI line is
+/// shared by the pins with the same number on all ports, `make_interrupt_source`
+/// selects the port through `SYSCFG_EXTICRx`.
+///
+/// ```ignore
+/// let mut syscfg = dp.SYSCFG.constrain();
+/// let mut button = gpioa.pa0.into_pull_down_input();
+/// button.make_interrupt_source(&mut syscfg);
+/// button.trigger_on_edge(&mut dp.EXTI, Edge::Rising);
+/// button.enable_interrupt(&mut dp.EXTI);
+/// unsafe { cortex_m::peripheral::NVIC::unmask(button.interrupt()) };
+///
+/// // in the EXTI0 handler
+/// if button.check_interrupt() {
+///     button.clear_interrupt_pending_bit();
+/// }
+/// ```
 pub trait ExtiPin {
     /// Make corresponding EXTI line sensitive to this pin
     fn make_interrupt_source(&mut self, syscfg: &mut SysCfg);
@@ -42,6 +61,9 @@ pub trait ExtiPin {
 
     /// Reads the interrupt pending bit for this pin
     fn check_interrupt(&self) -> bool;
+
+    /// EXTI line of this pin, for [`ExtiExt`](crate::exti::ExtiExt)
+    fn line(&self) -> Line;
 }
 
 impl<PIN> ExtiPin for PIN
@@ -125,4 +147,9 @@ where
     fn check_interrupt(&self) -> bool {
         unsafe { ((*EXTI::ptr()).pr.read().bits() & (1 << self.pin_id())) != 0 }
     }
+
+    #[inline(always)]
+    fn line(&self) -> Line {
+        Line::Gpio(GpioLine(self.pin_id()))
+    }
 }
@@ -1,7 +1,7 @@
 use core::convert::Infallible;
 
 use super::{
-    dynamic::PinModeError, marker, DynamicPin, ErasedPin, Input, OpenDrain, Output,
+    dynamic::PinModeError, marker, DynamicPin, ErasedPin, Input, LockedPin, OpenDrain, Output,
     PartiallyErasedPin, Pin, PinMode, PinState,
 };
 
@@ -105,6 +105,62 @@ where
     }
 }
 
+// Implementations for `LockedPin`
+impl<const P: char, const N: u8, MODE> OutputPin for LockedPin<P, N, Output<MODE>> {
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_high();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_low();
+        Ok(())
+    }
+}
+
+impl<const P: char, const N: u8, MODE> StatefulOutputPin for LockedPin<P, N, Output<MODE>> {
+    #[inline(always)]
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.pin.is_set_high())
+    }
+
+    #[inline(always)]
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.pin.is_set_low())
+    }
+}
+
+impl<const P: char, const N: u8, MODE> ToggleableOutputPin for LockedPin<P, N, Output<MODE>> {
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        self.toggle();
+        Ok(())
+    }
+}
+
+impl<const P: char, const N: u8, MODE> InputPin for LockedPin<P, N, MODE>
+where
+    MODE: marker::Readable,
+{
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.pin.is_high())
+    }
+
+    #[inline(always)]
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.pin.is_low())
+    }
+}
+
 // Implementations for `ErasedPin`
 
 impl<MODE> OutputPin for ErasedPin<Output<MODE>> {
@@ -240,3 +296,26 @@ impl<const P: char, const N: u8> InputPin for DynamicPin<P, N> {
         self.is_low()
     }
 }
+
+impl<const P: char, const N: u8> IoPin<Self, Self> for DynamicPin<P, N> {
+    type Error = PinModeError;
+    /// Keeps readable modes (inputs and open-drain output), otherwise switches to floating input
+    fn into_input_pin(mut self) -> Result<Self, Self::Error> {
+        if !self.mode.is_input() {
+            self.make_floating_input();
+        }
+        Ok(self)
+    }
+    /// Keeps output modes, otherwise switches to push-pull output
+    fn into_output_pin(mut self, state: PinState) -> Result<Self, Self::Error> {
+        if self.mode.is_output() {
+            match state {
+                PinState::High => self.set_high()?,
+                PinState::Low => self.set_low()?,
+            }
+        } else {
+            self.make_push_pull_output_in_state(state);
+        }
+        Ok(self)
+    }
+}
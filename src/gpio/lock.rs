@@ -0,0 +1,68 @@
+use super::*;
+use core::ops::Deref;
+
+/// Pin with configuration frozen until the next reset
+///
+/// Created by [`Pin::lock`]. Inputs can still be read and outputs driven high or low,
+/// but the pin can't be converted into another mode.
+pub struct LockedPin<const P: char, const N: u8, MODE> {
+    pub(super) pin: Pin<P, N, MODE>,
+}
+
+impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
+    /// Locks the pin configuration with the `GPIOx_LCKR` lock sequence
+    ///
+    /// Mode, output type, speed, pull and alternate function of the pin are ignored
+    /// by the hardware on write until the next reset, e.g. for gate driver enables
+    /// or break inputs.
+    pub fn lock(self) -> LockedPin<P, N, MODE> {
+        const LCKK: u32 = 1 << 16;
+        // NOTE(unsafe) the sequence is done in a critical section
+        let gpio = unsafe { &*Gpio::<P>::ptr() };
+        cortex_m::interrupt::free(|_| {
+            // Already locked pins of the port must stay set during the sequence
+            let bits = (gpio.lckr.read().bits() & 0xffff) | (1 << N);
+            unsafe {
+                gpio.lckr.write(|w| w.bits(LCKK | bits));
+                gpio.lckr.write(|w| w.bits(bits));
+                gpio.lckr.write(|w| w.bits(LCKK | bits));
+            }
+            gpio.lckr.read();
+            debug_assert!(gpio.lckr.read().bits() & LCKK != 0);
+        });
+        LockedPin { pin: self }
+    }
+}
+
+impl<const P: char, const N: u8, MODE> Deref for LockedPin<P, N, MODE> {
+    type Target = Pin<P, N, MODE>;
+    fn deref(&self) -> &Self::Target {
+        &self.pin
+    }
+}
+
+impl<const P: char, const N: u8, MODE> LockedPin<P, N, Output<MODE>> {
+    /// Drives the pin high
+    #[inline(always)]
+    pub fn set_high(&mut self) {
+        self.pin.set_high()
+    }
+
+    /// Drives the pin low
+    #[inline(always)]
+    pub fn set_low(&mut self) {
+        self.pin.set_low()
+    }
+
+    /// Drives the pin high or low depending on the provided value
+    #[inline(always)]
+    pub fn set_state(&mut self, state: PinState) {
+        self.pin.set_state(state)
+    }
+
+    /// Toggle pin output
+    #[inline(always)]
+    pub fn toggle(&mut self) {
+        self.pin.toggle()
+    }
+}
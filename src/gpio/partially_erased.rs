@@ -109,13 +109,19 @@ impl<const P: char, MODE> PartiallyErasedPin<P, Output<MODE>> {
     }
 
     /// Toggle pin output
+    ///
+    /// Writes `BSRR` once with the set or reset bit computed from `ODR`, other pins of the
+    /// port changed meanwhile, e.g. from interrupts, are not affected.
     #[inline(always)]
     pub fn toggle(&mut self) {
-        if self.is_set_low() {
-            self.set_high()
-        } else {
-            self.set_low()
-        }
+        let gpio = unsafe { &*Gpio::<P>::ptr() };
+        let mask = 1 << self.i;
+        let odr = gpio.odr.read().bits();
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            gpio.bsrr
+                .write(|w| w.bits(((odr & mask) << 16) | (!odr & mask)))
+        };
     }
 }
 
@@ -145,3 +151,16 @@ impl<const P: char, MODE> From<PartiallyErasedPin<P, MODE>> for ErasedPin<MODE>
         ErasedPin::new(P as u8 - b'A', p.i)
     }
 }
+
+impl<const P: char, const N: u8, MODE> TryFrom<PartiallyErasedPin<P, MODE>> for Pin<P, N, MODE> {
+    type Error = PartiallyErasedPin<P, MODE>;
+
+    /// Restores the pin type if the pin number is `N`, returns the pin otherwise
+    fn try_from(p: PartiallyErasedPin<P, MODE>) -> Result<Self, Self::Error> {
+        if p.i == N {
+            Ok(Self::new())
+        } else {
+            Err(p)
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use super::*;
+
+/// Pins of one port read and written at once
+///
+/// Values are in port bit positions, e.g. bit 3 is the state of pin 3. Only the bits of
+/// the grouped pins are read or changed, writes go through `BSRR` in one access, so
+/// the outputs never show intermediate states and other pins of the port are not affected.
+///
+/// ```ignore
+/// let gpiod = dp.GPIOD.split();
+/// let mut bus = Port::new([
+///     gpiod.pd0.into_push_pull_output().erase_number(),
+///     gpiod.pd1.into_push_pull_output().erase_number(),
+///     // ...
+///     gpiod.pd7.into_push_pull_output().erase_number(),
+/// ]);
+/// bus.write(0xa5);
+/// ```
+pub struct Port<const P: char, const N: usize, MODE> {
+    pins: [PartiallyErasedPin<P, MODE>; N],
+    mask: u16,
+}
+
+impl<const P: char, const N: usize, MODE> Port<P, N, MODE> {
+    /// Groups `pins` of port `P`
+    pub fn new(pins: [PartiallyErasedPin<P, MODE>; N]) -> Self {
+        let mask = pins.iter().fold(0, |mask, pin| mask | (1 << pin.pin_id()));
+        Self { pins, mask }
+    }
+
+    /// Bits of the grouped pins
+    #[inline(always)]
+    pub fn mask(&self) -> u16 {
+        self.mask
+    }
+
+    /// Releases the pins
+    pub fn release(self) -> [PartiallyErasedPin<P, MODE>; N] {
+        self.pins
+    }
+}
+
+impl<const P: char, const N: usize, MODE> Port<P, N, Output<MODE>> {
+    /// Drives the pins with bits set in `value` high and the others low
+    #[inline(always)]
+    pub fn write(&mut self, value: u16) {
+        let set = value & self.mask;
+        let reset = !value & self.mask;
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            (*Gpio::<P>::ptr())
+                .bsrr
+                .write(|w| w.bits(set as u32 | (reset as u32) << 16))
+        };
+    }
+
+    /// Drives the pins with bits set in `bits` high, leaves the others unchanged
+    #[inline(always)]
+    pub fn set_high(&mut self, bits: u16) {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            (*Gpio::<P>::ptr())
+                .bsrr
+                .write(|w| w.bits((bits & self.mask) as u32))
+        };
+    }
+
+    /// Drives the pins with bits set in `bits` low, leaves the others unchanged
+    #[inline(always)]
+    pub fn set_low(&mut self, bits: u16) {
+        // NOTE(unsafe) atomic write to a stateless register
+        unsafe {
+            (*Gpio::<P>::ptr())
+                .bsrr
+                .write(|w| w.bits(((bits & self.mask) as u32) << 16))
+        };
+    }
+
+    /// Output levels last written to the pins
+    #[inline(always)]
+    pub fn read_output(&self) -> u16 {
+        // NOTE(unsafe) atomic read with no side effects
+        unsafe { (*Gpio::<P>::ptr()).odr.read().bits() as u16 & self.mask }
+    }
+}
+
+impl<const P: char, const N: usize, MODE> Port<P, N, MODE>
+where
+    MODE: marker::Readable,
+{
+    /// Input levels of the pins sampled at the same time
+    #[inline(always)]
+    pub fn read(&self) -> u16 {
+        // NOTE(unsafe) atomic read with no side effects
+        unsafe { (*Gpio::<P>::ptr()).idr.read().bits() as u16 & self.mask }
+    }
+}
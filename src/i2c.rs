@@ -1,13 +1,62 @@
+//! Inter-Integrated Circuit (I2C) bus
+//!
+//! # Bus recovery
+//!
+//! A device reset in the middle of a read can hold SDA low until it gets the remaining
+//! clock pulses, the next transfer then fails or never starts. [`recover_bus`](I2c::recover_bus)
+//! clocks the device free and generates a STOP.
+//!
+//! ```ignore
+//! if let Err(_) = i2c.write(ADDR, &[REG]) {
+//!     i2c.recover_bus(&clocks)?;
+//! }
+//! ```
+//!
+//! # Timeout
+//!
+//! By default a transfer waits forever for the bus. With [`set_timeout`](I2c::set_timeout)
+//! it returns [`Error::Timeout`] instead, the bus can then be freed with `recover_bus`.
+//! The time is measured with the DWT cycle counter.
+//!
+//! ```ignore
+//! let dwt = cp.DWT.constrain(cp.DCB, &clocks);
+//! i2c.set_timeout(10.millis(), &dwt);
+//! if let Err(Error::Timeout) = i2c.read(ADDR, &mut buffer) {
+//!     i2c.recover_bus(&clocks)?;
+//! }
+//! ```
+//!
+//! # SMBus
+//!
+//! [`set_smbus_host`](I2c::set_smbus_host) enables the SMBus timeouts and alert detection.
+//! Transfers with a packet error code (PEC) use the hardware CRC, e.g. for battery gauges.
+//!
+//! ```ignore
+//! let mut i2c = dp.I2C1.i2c((scl, sda), 100.kHz(), &clocks);
+//! i2c.set_smbus_host(true);
+//!
+//! // read word 0x0d (relative state of charge) of a smart battery
+//! let mut soc = [0; 2];
+//! i2c.write_read_pec(0x0b, &[0x0d], &mut soc)?;
+//!
+//! if i2c.is_smbus_alert() {
+//!     i2c.clear_smbus_alert();
+//!     let device = i2c.read_alert_response()?;
+//! }
+//! ```
+
 use core::ops::Deref;
 
 use crate::pac::{self, i2c1};
 use crate::rcc::{Enable, Reset};
 
-use crate::gpio::{Const, OpenDrain, PinA, SetAlternate};
+use crate::gpio::{Const, OpenDrain, Pin, PinA, PinMode, PinState, SetAlternate};
 use crate::pac::RCC;
 
+use crate::dwt::Dwt;
 use crate::rcc::Clocks;
-use fugit::{HertzU32 as Hertz, RateExtU32};
+use cortex_m::peripheral::DWT;
+use fugit::{HertzU32 as Hertz, MicrosDurationU32, RateExtU32};
 
 mod hal_02;
 mod hal_1;
@@ -67,6 +116,7 @@ impl From<Hertz> for Mode {
 pub struct I2c<I2C: Instance, PINS> {
     i2c: I2C,
     pins: PINS,
+    timeout: u32,
 }
 
 pub struct Scl;
@@ -102,7 +152,7 @@ pub enum Error {
     Overrun,
     NoAcknowledge(NoAcknowledgeSource),
     Timeout,
-    // Note: The Bus error type is not currently returned, but is maintained for compatibility.
+    /// SDA still held low after [`I2c::recover_bus`]
     Bus,
     Crc,
     ArbitrationLoss,
@@ -181,7 +231,11 @@ where
 
         pins.set_alt_mode();
 
-        let i2c = I2c { i2c, pins };
+        let i2c = I2c {
+            i2c,
+            pins,
+            timeout: 0,
+        };
         i2c.i2c_init(mode, clocks.pclk1());
         i2c
     }
@@ -193,6 +247,82 @@ where
     }
 }
 
+impl<I2C, const P1: char, const N1: u8, M1, const P2: char, const N2: u8, M2>
+    I2c<I2C, (Pin<P1, N1, M1>, Pin<P2, N2, M2>)>
+where
+    I2C: Instance,
+    M1: PinMode,
+    M2: PinMode,
+    (Pin<P1, N1, M1>, Pin<P2, N2, M2>): Pins<I2C>,
+{
+    /// Frees a bus with SDA held low by a device, e.g. after a reset in the middle of a
+    /// transfer
+    ///
+    /// Takes the pins over as GPIOs and clocks SCL up to 9 times at about 100 kHz until
+    /// the device releases SDA, then generates a STOP condition. The peripheral is reset
+    /// afterwards with the same configuration. Returns [`Error::Bus`] if SDA is still low.
+    pub fn recover_bus(&mut self, clocks: &Clocks) -> Result<(), Error> {
+        let half_period = clocks.sysclk().raw() / 200_000;
+
+        // Release the lines
+        self.i2c.cr1.modify(|_, w| w.pe().clear_bit());
+
+        let (scl, sda) = &mut self.pins;
+        let released = scl.with_open_drain_output_in_state(PinState::High, |scl| {
+            sda.with_open_drain_output_in_state(PinState::High, |sda| {
+                cortex_m::asm::delay(half_period);
+                for _ in 0..9 {
+                    if sda.is_high() {
+                        break;
+                    }
+                    scl.set_low();
+                    cortex_m::asm::delay(half_period);
+                    scl.set_high();
+                    cortex_m::asm::delay(half_period);
+                }
+
+                // STOP condition, SDA rising while SCL is high
+                scl.set_low();
+                cortex_m::asm::delay(half_period);
+                sda.set_low();
+                cortex_m::asm::delay(half_period);
+                scl.set_high();
+                cortex_m::asm::delay(half_period);
+                sda.set_high();
+                cortex_m::asm::delay(half_period);
+
+                sda.is_high()
+            })
+        });
+        self.pins.set_alt_mode();
+
+        // Clears the BUSY flag, which stays set after a stuck bus
+        // SMBUS, SMBTYPE, ENARP, ENPEC, ENGC and NOSTRETCH
+        let cr1 = self.i2c.cr1.read().bits() & 0xfa;
+        let cr2 = self.i2c.cr2.read().bits();
+        let ccr = self.i2c.ccr.read().bits();
+        let trise = self.i2c.trise.read().bits();
+        let oar1 = self.i2c.oar1.read().bits();
+        self.i2c.cr1.write(|w| w.swrst().set_bit());
+        self.i2c.cr1.write(|w| w.swrst().clear_bit());
+        unsafe {
+            self.i2c.cr2.write(|w| w.bits(cr2));
+            self.i2c.ccr.write(|w| w.bits(ccr));
+            self.i2c.trise.write(|w| w.bits(trise));
+            self.i2c.oar1.write(|w| w.bits(oar1));
+        }
+        self.i2c
+            .cr1
+            .write(|w| unsafe { w.bits(cr1) }.pe().set_bit());
+
+        if released {
+            Ok(())
+        } else {
+            Err(Error::Bus)
+        }
+    }
+}
+
 impl<I2C: Instance, PINS> I2c<I2C, PINS> {
     fn i2c_init(&self, mode: impl Into<Mode>, pclk: Hertz) {
         let mode = mode.into();
@@ -303,17 +433,15 @@ impl<I2C: Instance, PINS> I2c<I2C, PINS> {
         self.i2c.cr1.modify(|_, w| w.start().set_bit());
 
         // Wait until START condition was generated
-        while self.check_and_clear_error_flags()?.sb().bit_is_clear() {}
+        self.wait_for(|| Ok(self.check_and_clear_error_flags()?.sb().bit_is_set()))?;
 
         // Also wait until signalled we're master and everything is waiting for us
-        loop {
+        self.wait_for(|| {
             self.check_and_clear_error_flags()?;
 
             let sr2 = self.i2c.sr2.read();
-            if !(sr2.msl().bit_is_clear() && sr2.busy().bit_is_clear()) {
-                break;
-            }
-        }
+            Ok(!(sr2.msl().bit_is_clear() && sr2.busy().bit_is_clear()))
+        })?;
 
         // Set up current address, we're trying to talk to
         self.i2c
@@ -321,17 +449,15 @@ impl<I2C: Instance, PINS> I2c<I2C, PINS> {
             .write(|w| unsafe { w.bits(u32::from(addr) << 1) });
 
         // Wait until address was sent
-        loop {
+        self.wait_for(|| {
             // Check for any I2C errors. If a NACK occurs, the ADDR bit will never be set.
             let sr1 = self
                 .check_and_clear_error_flags()
                 .map_err(Error::nack_addr)?;
 
             // Wait for the address to be acknowledged
-            if sr1.addr().bit_is_set() {
-                break;
-            }
-        }
+            Ok(sr1.addr().bit_is_set())
+        })?;
 
         // Clear condition by reading SR2
         self.i2c.sr2.read();
@@ -348,58 +474,63 @@ impl<I2C: Instance, PINS> I2c<I2C, PINS> {
     fn send_byte(&self, byte: u8) -> Result<(), Error> {
         // Wait until we're ready for sending
         // Check for any I2C errors. If a NACK occurs, the ADDR bit will never be set.
-        while self
-            .check_and_clear_error_flags()
-            .map_err(Error::nack_addr)?
-            .tx_e()
-            .bit_is_clear()
-        {}
+        self.wait_for(|| {
+            Ok(self
+                .check_and_clear_error_flags()
+                .map_err(Error::nack_addr)?
+                .tx_e()
+                .bit_is_set())
+        })?;
 
         // Push out a byte of data
         self.i2c.dr.write(|w| unsafe { w.bits(u32::from(byte)) });
 
         // Wait until byte is transferred
         // Check for any potential error conditions.
-        while self
-            .check_and_clear_error_flags()
-            .map_err(Error::nack_data)?
-            .btf()
-            .bit_is_clear()
-        {}
+        self.wait_for(|| {
+            Ok(self
+                .check_and_clear_error_flags()
+                .map_err(Error::nack_data)?
+                .btf()
+                .bit_is_set())
+        })?;
 
         Ok(())
     }
 
     fn recv_byte(&self) -> Result<u8, Error> {
-        loop {
+        self.wait_for(|| {
             // Check for any potential error conditions.
             self.check_and_clear_error_flags()
                 .map_err(Error::nack_data)?;
 
-            if self.i2c.sr1.read().rx_ne().bit_is_set() {
-                break;
-            }
-        }
+            Ok(self.i2c.sr1.read().rx_ne().bit_is_set())
+        })?;
 
         let value = self.i2c.dr.read().bits() as u8;
         Ok(value)
     }
 
     pub fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
-        if let Some((last, buffer)) = buffer.split_last_mut() {
+        self.read_bytes(addr, buffer, false)
+    }
+
+    /// Reads `buffer` and the PEC byte, [`Error::Crc`] if it doesn't match
+    fn read_bytes(&mut self, addr: u8, buffer: &mut [u8], pec: bool) -> Result<(), Error> {
+        if pec || !buffer.is_empty() {
             // Send a START condition and set ACK bit
             self.i2c
                 .cr1
                 .modify(|_, w| w.start().set_bit().ack().set_bit());
 
             // Wait until START condition was generated
-            while self.i2c.sr1.read().sb().bit_is_clear() {}
+            self.wait_for(|| Ok(self.i2c.sr1.read().sb().bit_is_set()))?;
 
             // Also wait until signalled we're master and everything is waiting for us
-            while {
+            self.wait_for(|| {
                 let sr2 = self.i2c.sr2.read();
-                sr2.msl().bit_is_clear() && sr2.busy().bit_is_clear()
-            } {}
+                Ok(!(sr2.msl().bit_is_clear() && sr2.busy().bit_is_clear()))
+            })?;
 
             // Set up current address, we're trying to talk to
             self.i2c
@@ -407,32 +538,43 @@ impl<I2C: Instance, PINS> I2c<I2C, PINS> {
                 .write(|w| unsafe { w.bits((u32::from(addr) << 1) + 1) });
 
             // Wait until address was sent
-            loop {
+            self.wait_for(|| {
                 self.check_and_clear_error_flags()
                     .map_err(Error::nack_addr)?;
-                if self.i2c.sr1.read().addr().bit_is_set() {
-                    break;
-                }
-            }
+                Ok(self.i2c.sr1.read().addr().bit_is_set())
+            })?;
 
             // Clear condition by reading SR2
             self.i2c.sr2.read();
 
+            // The PEC byte is received last
+            let (buffer, last) = match buffer.split_last_mut() {
+                Some((last, buffer)) if !pec => (buffer, Some(last)),
+                _ => (buffer, None),
+            };
+
             // Receive bytes into buffer
             for c in buffer {
                 *c = self.recv_byte()?;
             }
 
-            // Prepare to send NACK then STOP after next byte
+            // Prepare to send NACK then STOP after next byte, compare it with the PEC
             self.i2c
                 .cr1
-                .modify(|_, w| w.ack().clear_bit().stop().set_bit());
+                .modify(|_, w| w.ack().clear_bit().stop().set_bit().pec().bit(pec));
 
             // Receive last byte
-            *last = self.recv_byte()?;
+            let byte = self.recv_byte()?;
+            if let Some(last) = last {
+                *last = byte;
+            }
 
             // Wait for the STOP to be sent.
-            while self.i2c.cr1.read().stop().bit_is_set() {}
+            self.wait_for(|| Ok(self.i2c.cr1.read().stop().bit_is_clear()))?;
+
+            if pec {
+                self.check_and_clear_error_flags()?;
+            }
 
             // Fallthrough is success
             Ok(())
@@ -443,12 +585,7 @@ impl<I2C: Instance, PINS> I2c<I2C, PINS> {
 
     pub fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
         self.write_bytes(addr, bytes.iter().cloned())?;
-
-        // Send a STOP condition
-        self.i2c.cr1.modify(|_, w| w.stop().set_bit());
-
-        // Wait for STOP condition to transmit.
-        while self.i2c.cr1.read().stop().bit_is_set() {}
+        self.stop()?;
 
         // Fallthrough is success
         Ok(())
@@ -459,12 +596,7 @@ impl<I2C: Instance, PINS> I2c<I2C, PINS> {
         B: IntoIterator<Item = u8>,
     {
         self.write_bytes(addr, bytes.into_iter())?;
-
-        // Send a STOP condition
-        self.i2c.cr1.modify(|_, w| w.stop().set_bit());
-
-        // Wait for STOP condition to transmit.
-        while self.i2c.cr1.read().stop().bit_is_set() {}
+        self.stop()?;
 
         // Fallthrough is success
         Ok(())
@@ -482,4 +614,127 @@ impl<I2C: Instance, PINS> I2c<I2C, PINS> {
         self.write_bytes(addr, bytes.into_iter())?;
         self.read(addr, buffer)
     }
+
+    /// Limits every wait for the bus to `timeout`, counted in cycles of `dwt`
+    ///
+    /// A transfer waiting longer, e.g. because a device holds SCL or SDA low, is aborted
+    /// with [`Error::Timeout`]. The cycle counter wraps after `u32::MAX` cycles, longer
+    /// timeouts are limited to that. A zero `timeout`, the default, waits forever.
+    pub fn set_timeout(&mut self, timeout: MicrosDurationU32, dwt: &Dwt) {
+        let cycles = timeout.to_micros() as u64 * dwt.frequency().raw() as u64 / 1_000_000;
+        self.timeout = cycles.min(u32::MAX as u64) as u32;
+    }
+
+    /// Configures the peripheral as SMBus host
+    ///
+    /// A device holding SCL low for more than 25 ms is reported as [`Error::Timeout`].
+    /// The SMBA pin must be in its alternate function mode to detect alerts. Disabling
+    /// returns to I2C mode.
+    pub fn set_smbus_host(&mut self, enable: bool) {
+        self.i2c.cr1.modify(|_, w| {
+            w.pe()
+                .clear_bit()
+                .smbus()
+                .bit(enable)
+                .smbtype()
+                .bit(enable)
+                .enarp()
+                .bit(enable)
+        });
+        self.i2c.cr1.modify(|_, w| w.pe().set_bit());
+    }
+
+    /// Writes `bytes` followed by the packet error code calculated by the hardware
+    pub fn write_pec(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.with_pec(|i2c| {
+            i2c.write_bytes(addr, bytes.iter().cloned())?;
+            i2c.send_pec()?;
+            i2c.stop()?;
+            Ok(())
+        })
+    }
+
+    /// Reads `buffer` and checks the packet error code sent by the device
+    ///
+    /// Returns [`Error::Crc`] if the packet error code doesn't match.
+    pub fn read_pec(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.with_pec(|i2c| i2c.read_bytes(addr, buffer, true))
+    }
+
+    /// Writes `bytes`, then reads `buffer` with a repeated START and checks the packet
+    /// error code of the whole transaction, e.g. the SMBus read word command
+    pub fn write_read_pec(
+        &mut self,
+        addr: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        self.with_pec(|i2c| {
+            i2c.write_bytes(addr, bytes.iter().cloned())?;
+            i2c.read_bytes(addr, buffer, true)
+        })
+    }
+
+    /// Reads the address of a device signalling an SMBus alert (alert response address)
+    ///
+    /// If several devices signal an alert the one with the lowest address responds, it
+    /// releases SMBA afterwards.
+    pub fn read_alert_response(&mut self) -> Result<u8, Error> {
+        const ARA: u8 = 0x0c;
+        let mut buffer = [0];
+        self.read(ARA, &mut buffer)?;
+        Ok(buffer[0] >> 1)
+    }
+
+    /// Returns `true` if a device pulled the SMBA pin low
+    pub fn is_smbus_alert(&self) -> bool {
+        self.i2c.sr1.read().smbalert().bit_is_set()
+    }
+
+    /// Clears the SMBus alert flag
+    pub fn clear_smbus_alert(&mut self) {
+        self.i2c.sr1.modify(|_, w| w.smbalert().clear_bit());
+    }
+
+    fn with_pec<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        self.i2c.cr1.modify(|_, w| w.enpec().set_bit());
+        let result = f(self);
+        // Also resets the PEC
+        self.i2c.cr1.modify(|_, w| w.enpec().clear_bit());
+        result
+    }
+
+    fn send_pec(&self) -> Result<(), Error> {
+        self.i2c.cr1.modify(|_, w| w.pec().set_bit());
+
+        // Wait until the PEC is transferred
+        self.wait_for(|| {
+            Ok(self
+                .check_and_clear_error_flags()
+                .map_err(Error::nack_data)?
+                .btf()
+                .bit_is_set())
+        })?;
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), Error> {
+        // Send a STOP condition
+        self.i2c.cr1.modify(|_, w| w.stop().set_bit());
+
+        // Wait for STOP condition to transmit.
+        self.wait_for(|| Ok(self.i2c.cr1.read().stop().bit_is_clear()))
+    }
+
+    /// Polls `done` until it returns `true` or the [`set_timeout`](Self::set_timeout) expires
+    fn wait_for(&self, mut done: impl FnMut() -> Result<bool, Error>) -> Result<(), Error> {
+        let start = DWT::cycle_count();
+        while !done()? {
+            if self.timeout != 0 && DWT::cycle_count().wrapping_sub(start) >= self.timeout {
+                return Err(Error::Timeout);
+            }
+        }
+        Ok(())
+    }
 }
@@ -117,6 +117,8 @@ pub mod crc32;
 #[cfg(all(feature = "device-selected", feature = "dac"))]
 pub mod dac;
 #[cfg(feature = "device-selected")]
+pub mod exti;
+#[cfg(feature = "device-selected")]
 #[cfg(feature = "fmpi2c1")]
 pub mod fmpi2c;
 #[cfg(feature = "device-selected")]
@@ -149,6 +151,11 @@ pub mod flash;
     any(feature = "fmc", feature = "fsmc")
 ))]
 pub mod fsmc_lcd;
+#[cfg(all(
+    feature = "device-selected",
+    any(feature = "stm32f410", feature = "stm32f413", feature = "stm32f423")
+))]
+pub mod lptim;
 #[cfg(feature = "device-selected")]
 pub mod prelude;
 #[cfg(feature = "device-selected")]
@@ -0,0 +1,260 @@
+//! # Low-power timer (LPTIM1)
+//!
+//! 16-bit timer available on STM32F410, STM32F413 and STM32F423. Clocked from LSI or LSE
+//! it keeps running in Stop mode and its interrupts (routed to EXTI line 23) can wake the
+//! MCU up, see [`LpTimer::enable_wakeup`].
+//!
+//! The PWM output is on the `LPTIM1_OUT` pin, which has to be put into the matching
+//! alternate function mode by the user.
+//!
+//! ```ignore
+//! let mut lptim = LpTimer::new(dp.LPTIM1, ClockSource::Lsi, Prescaler::Div32, &clocks);
+//! lptim.listen(Event::AutoReloadMatch);
+//! lptim.enable_wakeup(&mut dp.EXTI);
+//! // ~1 s period at 32 kHz / 32
+//! lptim.start_counter(1000);
+//! ```
+
+#![allow(non_upper_case_globals)]
+
+use crate::exti::{ExtiExt, Line};
+use crate::gpio::Edge;
+use crate::pac::{EXTI, RCC};
+use crate::rcc::{Clocks, Enable, Reset};
+use crate::time::Hertz;
+
+#[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+pub use crate::pac::LPTIM as LPTIM1;
+#[cfg(feature = "stm32f410")]
+pub use crate::pac::LPTIM1;
+
+use crate::pac::rcc::dckcfgr2::LPTIM1SEL_A;
+
+/// Nominal LSI frequency
+const LSI: u32 = 32_000;
+/// LSE crystal frequency
+const LSE: u32 = 32_768;
+
+/// LPTIM1 kernel clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockSource {
+    /// APB1 clock (PCLK1), stopped in Stop mode
+    Apb1,
+    /// Internal low-speed oscillator (~32 kHz), enabled if not running yet
+    Lsi,
+    /// High-speed internal oscillator, stopped in Stop mode
+    Hsi,
+    /// External low-speed oscillator (32.768 kHz), must already be running
+    Lse,
+}
+
+/// Counter clock prescaler
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Prescaler {
+    Div1 = 0,
+    Div2 = 1,
+    Div4 = 2,
+    Div8 = 3,
+    Div16 = 4,
+    Div32 = 5,
+    Div64 = 6,
+    Div128 = 7,
+}
+
+bitflags::bitflags! {
+    /// Low-power timer interrupt events and flags of `IER`/`ISR`
+    pub struct Event: u32 {
+        /// Counter matched the compare value
+        const CompareMatch = 1 << 0;
+        /// Counter matched the auto-reload value
+        const AutoReloadMatch = 1 << 1;
+    }
+}
+
+/// Low-power timer
+pub struct LpTimer {
+    lptim: LPTIM1,
+    clk: Hertz,
+}
+
+impl LpTimer {
+    /// Enables LPTIM1 clocked from `source` divided by `prescaler`
+    pub fn new(lptim: LPTIM1, source: ClockSource, prescaler: Prescaler, clocks: &Clocks) -> Self {
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+            // Enable and reset clock.
+            LPTIM1::enable(rcc);
+            LPTIM1::reset(rcc);
+
+            let (sel, clk) = match source {
+                ClockSource::Apb1 => (LPTIM1SEL_A::Apb1, clocks.pclk1().raw()),
+                ClockSource::Lsi => {
+                    if rcc.csr.read().lsirdy().bit_is_clear() {
+                        rcc.csr.modify(|_, w| w.lsion().set_bit());
+                        while rcc.csr.read().lsirdy().bit_is_clear() {}
+                    }
+                    (LPTIM1SEL_A::Lsi, LSI)
+                }
+                ClockSource::Hsi => (LPTIM1SEL_A::Hsi, crate::rcc::HSI),
+                ClockSource::Lse => {
+                    assert!(rcc.bdcr.read().lserdy().bit_is_set(), "LSE is not running");
+                    (LPTIM1SEL_A::Lse, LSE)
+                }
+            };
+            rcc.dckcfgr2.modify(|_, w| w.lptim1sel().variant(sel));
+
+            // Prescaler can be changed only while the timer is disabled
+            lptim.cfgr.write(|w| w.presc().bits(prescaler as u8));
+
+            Self {
+                lptim,
+                clk: Hertz::from_raw(clk >> prescaler as u8),
+            }
+        }
+    }
+
+    /// Counter clock frequency
+    pub fn clock(&self) -> Hertz {
+        self.clk
+    }
+
+    /// Counts from 0 to `arr` continuously, the counter period is `arr + 1` ticks
+    pub fn start_counter(&mut self, arr: u16) {
+        self.load(arr, None);
+        self.lptim.cr.modify(|_, w| w.cntstrt().set_bit());
+    }
+
+    /// Counts from 0 to `arr` once and stops
+    ///
+    /// Can be restarted by calling it again, i.e. from the `AutoReloadMatch` interrupt.
+    pub fn start_one_shot(&mut self, arr: u16) {
+        self.load(arr, None);
+        self.lptim.cr.modify(|_, w| w.sngstrt().set_bit());
+    }
+
+    /// Starts PWM output on `LPTIM1_OUT` with a period of `arr + 1` ticks
+    ///
+    /// The output is high for `duty` ticks of the period, `duty` must be in `1..=arr`.
+    /// A running counter is stopped and restarted.
+    pub fn start_pwm(&mut self, arr: u16, duty: u16) {
+        assert!(duty > 0 && duty <= arr);
+        // CFGR can be written only while the timer is disabled
+        self.stop();
+        // Output is set on compare match and reset on auto-reload match
+        self.lptim
+            .cfgr
+            .modify(|_, w| w.wave().clear_bit().wavpol().clear_bit());
+        self.load(arr, Some(arr - duty));
+        self.lptim.cr.modify(|_, w| w.cntstrt().set_bit());
+    }
+
+    /// Changes the PWM duty cycle, `duty` must be in `1..=arr`
+    pub fn set_duty(&mut self, duty: u16) {
+        let arr = self.lptim.arr.read().arr().bits();
+        assert!(duty > 0 && duty <= arr);
+        self.write_cmp(arr - duty);
+    }
+
+    /// Changes the compare value, can be used as a second event in counter modes
+    ///
+    /// `cmp` must be less than the auto-reload value.
+    pub fn set_compare(&mut self, cmp: u16) {
+        self.write_cmp(cmp);
+    }
+
+    /// Current counter value
+    ///
+    /// With an asynchronous clock source the value is only reliable if two consecutive
+    /// reads return the same value, this is done here.
+    pub fn count(&self) -> u16 {
+        loop {
+            let a = self.lptim.cnt.read().cnt().bits();
+            let b = self.lptim.cnt.read().cnt().bits();
+            if a == b {
+                return a;
+            }
+        }
+    }
+
+    /// Stops and disables the timer
+    pub fn stop(&mut self) {
+        self.lptim.cr.write(|w| w.enable().clear_bit());
+    }
+
+    /// Starts listening for an `event`
+    ///
+    /// Interrupts can be enabled only while the timer is disabled, so this stops the timer.
+    pub fn listen(&mut self, event: Event) {
+        self.stop();
+        self.lptim
+            .ier
+            .modify(|r, w| unsafe { w.bits(r.bits() | event.bits()) });
+    }
+
+    /// Stops listening for an `event`
+    ///
+    /// Interrupts can be disabled only while the timer is disabled, so this stops the timer.
+    pub fn unlisten(&mut self, event: Event) {
+        self.stop();
+        self.lptim
+            .ier
+            .modify(|r, w| unsafe { w.bits(r.bits() & !event.bits()) });
+    }
+
+    /// Returns pending events
+    pub fn get_interrupt_flag(&self) -> Event {
+        Event::from_bits_truncate(self.lptim.isr.read().bits())
+    }
+
+    /// Clears interrupt flags and the EXTI line 23 pending bit
+    pub fn clear_interrupt_flag(&mut self, event: Event) {
+        self.lptim.icr.write(|w| unsafe { w.bits(event.bits()) });
+        unsafe {
+            (*EXTI::ptr())
+                .pr
+                .write(|w| w.bits(1 << Line::Lptim1.number()))
+        };
+    }
+
+    /// Lets LPTIM1 interrupts wake the MCU up from Stop mode
+    ///
+    /// Enables the rising edge interrupt of EXTI line 23. The timer has to be clocked
+    /// from LSI or LSE to keep counting in Stop mode. The EXTI pending bit is cleared
+    /// together with the timer flags by [`clear_interrupt_flag`](Self::clear_interrupt_flag).
+    pub fn enable_wakeup(&mut self, exti: &mut EXTI) {
+        exti.listen(Line::Lptim1, Edge::Rising);
+    }
+
+    /// Disables wakeup from Stop mode
+    pub fn disable_wakeup(&mut self, exti: &mut EXTI) {
+        exti.unlisten(Line::Lptim1);
+    }
+
+    /// Stops the timer and releases the peripheral
+    pub fn release(mut self) -> LPTIM1 {
+        self.stop();
+        self.lptim
+    }
+
+    /// Enables the timer and loads `ARR` and optionally `CMP`
+    fn load(&mut self, arr: u16, cmp: Option<u16>) {
+        // ARR and CMP can be written only while the timer is enabled
+        self.lptim.cr.write(|w| w.enable().set_bit());
+        self.lptim.icr.write(|w| w.arrokcf().set_bit());
+        self.lptim.arr.write(|w| unsafe { w.arr().bits(arr) });
+        while self.lptim.isr.read().arrok().bit_is_clear() {}
+        if let Some(cmp) = cmp {
+            self.write_cmp(cmp);
+        }
+    }
+
+    fn write_cmp(&mut self, cmp: u16) {
+        self.lptim.icr.write(|w| w.cmpokcf().set_bit());
+        self.lptim.cmp.write(|w| unsafe { w.cmp().bits(cmp) });
+        while self.lptim.isr.read().cmpok().bit_is_clear() {}
+    }
+}
@@ -6,7 +6,7 @@
 use crate::pac;
 
 use crate::gpio::{Alternate, PushPull, PA11, PA12};
-use crate::rcc::{Enable, Reset};
+use crate::rcc::{Clocks, Enable, Reset};
 use fugit::HertzU32 as Hertz;
 
 pub use synopsys_usb_otg::UsbBus;
@@ -21,6 +21,37 @@ pub struct USB {
     pub hclk: Hertz,
 }
 
+impl USB {
+    /// Takes the peripherals and pins of the USB OTG FS controller
+    ///
+    /// # Panics
+    ///
+    /// If the PLL48 clock is not within the USB specification, see
+    /// [`Clocks::is_pll48clk_valid`] and
+    /// [`require_pll48clk`](crate::rcc::CFGR::require_pll48clk).
+    pub fn new(
+        periphs: (pac::OTG_FS_GLOBAL, pac::OTG_FS_DEVICE, pac::OTG_FS_PWRCLK),
+        pins: (
+            impl Into<PA11<Alternate<10, PushPull>>>,
+            impl Into<PA12<Alternate<10, PushPull>>>,
+        ),
+        clocks: &Clocks,
+    ) -> Self {
+        assert!(
+            clocks.is_pll48clk_valid(),
+            "USB requires a valid 48 MHz clock"
+        );
+        Self {
+            usb_global: periphs.0,
+            usb_device: periphs.1,
+            usb_pwrclk: periphs.2,
+            pin_dm: pins.0.into(),
+            pin_dp: pins.1.into(),
+            hclk: clocks.hclk(),
+        }
+    }
+}
+
 unsafe impl Sync for USB {}
 
 unsafe impl UsbPeripheral for USB {
@@ -9,7 +9,7 @@
 use crate::pac;
 
 use crate::gpio::{Alternate, PushPull, PB14, PB15};
-use crate::rcc::{Enable, Reset};
+use crate::rcc::{Clocks, Enable, Reset};
 use fugit::HertzU32 as Hertz;
 
 pub use synopsys_usb_otg::UsbBus;
@@ -24,6 +24,37 @@ pub struct USB {
     pub hclk: Hertz,
 }
 
+impl USB {
+    /// Takes the peripherals and pins of the USB OTG HS controller
+    ///
+    /// # Panics
+    ///
+    /// If the PLL48 clock is not within the USB specification, see
+    /// [`Clocks::is_pll48clk_valid`] and
+    /// [`require_pll48clk`](crate::rcc::CFGR::require_pll48clk).
+    pub fn new(
+        periphs: (pac::OTG_HS_GLOBAL, pac::OTG_HS_DEVICE, pac::OTG_HS_PWRCLK),
+        pins: (
+            impl Into<PB14<Alternate<12, PushPull>>>,
+            impl Into<PB15<Alternate<12, PushPull>>>,
+        ),
+        clocks: &Clocks,
+    ) -> Self {
+        assert!(
+            clocks.is_pll48clk_valid(),
+            "USB requires a valid 48 MHz clock"
+        );
+        Self {
+            usb_global: periphs.0,
+            usb_device: periphs.1,
+            usb_pwrclk: periphs.2,
+            pin_dm: pins.0.into(),
+            pin_dp: pins.1.into(),
+            hclk: clocks.hclk(),
+        }
+    }
+}
+
 unsafe impl Sync for USB {}
 
 unsafe impl UsbPeripheral for USB {
@@ -55,6 +55,21 @@ where
     }
 }
 
+impl<TIM: Instance, PINS> Qei<TIM, PINS> {
+    /// Inverts the counting direction by inverting TI1 polarity
+    pub fn invert_direction(&mut self, invert: bool) {
+        self.tim.set_ti1_inverted(invert);
+    }
+
+    /// Sets the digital filter of both encoder inputs
+    ///
+    /// `filter` is the `ICxF` value (0 - 15), 0 disables the filter.
+    /// Higher values reject longer glitches on noisy encoder signals.
+    pub fn set_filter(&mut self, filter: u8) {
+        self.tim.set_input_filter(filter & 0xf);
+    }
+}
+
 impl<TIM: Instance, PINS> embedded_hal::Qei for Qei<TIM, PINS> {
     type Count = TIM::Width;
 
@@ -75,6 +90,10 @@ pub trait Instance: crate::Sealed + rcc::Enable + rcc::Reset + General {
     fn setup_qei(&mut self);
 
     fn read_direction(&self) -> bool;
+
+    fn set_ti1_inverted(&mut self, invert: bool);
+
+    fn set_input_filter(&mut self, filter: u8);
 }
 
 macro_rules! hal {
@@ -107,6 +126,16 @@ macro_rules! hal {
                 fn read_direction(&self) -> bool {
                     self.cr1.read().dir().bit_is_clear()
                 }
+
+                fn set_ti1_inverted(&mut self, invert: bool) {
+                    self.ccer.modify(|_, w| w.cc1p().bit(invert));
+                }
+
+                #[allow(unused_unsafe)]
+                fn set_input_filter(&mut self, filter: u8) {
+                    self.ccmr1_input()
+                        .modify(|_, w| unsafe { w.ic1f().bits(filter).ic2f().bits(filter) });
+                }
             }
         )+
     }
@@ -38,6 +38,10 @@ macro_rules! bus_lpenable {
                     bb::clear(Self::Bus::lpenr(rcc), $bit);
                 }
             }
+            #[inline(always)]
+            fn is_low_power_enabled(rcc: &RccRB) -> bool {
+                Self::Bus::lpenr(rcc).read().bits() & (1 << $bit) != 0
+            }
         }
     };
 }
@@ -124,7 +128,23 @@ bus! {
     FMC => (AHB3, 0),
 }
 
-// TODO: fix absent ahb3lpenr
+#[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+bus! {
+    QUADSPI => (AHB3, 1),
+}
+#[cfg(any(feature = "stm32f412", feature = "stm32f413", feature = "stm32f423"))]
+impl crate::Sealed for crate::pac::QUADSPI {}
+#[cfg(any(feature = "stm32f412", feature = "stm32f413", feature = "stm32f423"))]
+impl RccBus for crate::pac::QUADSPI {
+    type Bus = AHB3;
+}
+#[cfg(any(feature = "stm32f412", feature = "stm32f413", feature = "stm32f423"))]
+bus_enable!(QUADSPI => 1);
+#[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+bus_lpenable!(QUADSPI => 1);
+#[cfg(any(feature = "stm32f412", feature = "stm32f413", feature = "stm32f423"))]
+bus_reset!(QUADSPI => 1);
+
 #[cfg(feature = "fsmc")]
 impl crate::Sealed for crate::pac::FSMC {}
 #[cfg(feature = "fsmc")]
@@ -133,6 +153,8 @@ impl RccBus for crate::pac::FSMC {
 }
 #[cfg(feature = "fsmc")]
 bus_enable!(FSMC => 0);
+#[cfg(all(feature = "fsmc", not(feature = "stm32f412")))]
+bus_lpenable!(FSMC => 0);
 #[cfg(feature = "fsmc")]
 bus_reset!(FSMC => 0);
 
@@ -140,6 +162,67 @@ bus! {
     PWR => (APB1, 28),
 }
 
+bus! {
+    WWDG => (APB1, 11),
+}
+
+#[cfg(any(
+    feature = "stm32f407",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+bus! {
+    DCMI => (AHB2, 0),
+}
+
+#[cfg(any(
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+bus! {
+    DMA2D => (AHB1, 23),
+}
+
+#[cfg(any(
+    feature = "stm32f429",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+bus! {
+    LTDC => (APB2, 26),
+}
+
+#[cfg(any(feature = "stm32f427", feature = "stm32f437", feature = "stm32f446"))]
+bus! {
+    SAI1 => (APB2, 22),
+}
+#[cfg(feature = "stm32f446")]
+bus! {
+    SAI2 => (APB2, 23),
+}
+#[cfg(any(
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f429",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+bus! {
+    SAI => (APB2, 22),
+}
+
 bus! {
     SPI1 => (APB2, 12),
     SPI2 => (APB1, 14),
@@ -335,3 +418,13 @@ bus! {
     TIM13 => (APB1, 7),
     TIM14 => (APB1, 8),
 }
+
+#[cfg(feature = "stm32f410")]
+bus! {
+    LPTIM1 => (APB1, 9),
+}
+
+#[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+bus! {
+    LPTIM => (APB1, 9),
+}
@@ -0,0 +1,100 @@
+use crate::gpio::{Alternate, Pin, Speed};
+use crate::pac::RCC;
+
+/// Clock source of MCO1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Mco1Source {
+    Hsi = 0,
+    Lse = 1,
+    Hse = 2,
+    Pll = 3,
+}
+
+/// Clock source of MCO2
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Mco2Source {
+    Sysclk = 0,
+    #[cfg(not(feature = "stm32f410"))]
+    Plli2s = 1,
+    Hse = 2,
+    Pll = 3,
+}
+
+/// Division of the MCO clock source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum McoPrescaler {
+    Div1 = 0,
+    Div2 = 4,
+    Div3 = 5,
+    Div4 = 6,
+    Div5 = 7,
+}
+
+/// Microcontroller clock output 1 on PA8
+///
+/// ```ignore
+/// // 8 MHz HSE to clock an Ethernet PHY
+/// let mco1 = Mco1::new(gpioa.pa8, Mco1Source::Hse, McoPrescaler::Div1);
+/// ```
+pub struct Mco1 {
+    pin: Pin<'A', 8, Alternate<0>>,
+}
+
+impl Mco1 {
+    /// Outputs `source` divided by `prescaler` on the pin
+    pub fn new(
+        pin: impl Into<Pin<'A', 8, Alternate<0>>>,
+        source: Mco1Source,
+        prescaler: McoPrescaler,
+    ) -> Self {
+        let pin = pin.into().speed(Speed::VeryHigh);
+        // NOTE(unsafe) only the MCO1 bits are modified
+        let rcc = unsafe { &*RCC::ptr() };
+        rcc.cfgr
+            .modify(|_, w| unsafe { w.mco1().bits(source as u8).mco1pre().bits(prescaler as u8) });
+        #[cfg(feature = "stm32f410")]
+        rcc.cfgr.modify(|_, w| w.mco1en().set_bit());
+        Self { pin }
+    }
+
+    /// Releases the pin, the clock keeps running until the pin mode is changed
+    pub fn release(self) -> Pin<'A', 8, Alternate<0>> {
+        self.pin
+    }
+}
+
+/// Microcontroller clock output 2 on PC9
+///
+/// ```ignore
+/// // 12.5 MHz from the main PLL at 50 MHz for a camera
+/// let mco2 = Mco2::new(gpioc.pc9, Mco2Source::Pll, McoPrescaler::Div4);
+/// ```
+pub struct Mco2 {
+    pin: Pin<'C', 9, Alternate<0>>,
+}
+
+impl Mco2 {
+    /// Outputs `source` divided by `prescaler` on the pin
+    pub fn new(
+        pin: impl Into<Pin<'C', 9, Alternate<0>>>,
+        source: Mco2Source,
+        prescaler: McoPrescaler,
+    ) -> Self {
+        let pin = pin.into().speed(Speed::VeryHigh);
+        // NOTE(unsafe) only the MCO2 bits are modified
+        let rcc = unsafe { &*RCC::ptr() };
+        rcc.cfgr
+            .modify(|_, w| unsafe { w.mco2().bits(source as u8).mco2pre().bits(prescaler as u8) });
+        #[cfg(feature = "stm32f410")]
+        rcc.cfgr.modify(|_, w| w.mco2en().set_bit());
+        Self { pin }
+    }
+
+    /// Releases the pin, the clock keeps running until the pin mode is changed
+    pub fn release(self) -> Pin<'C', 9, Alternate<0>> {
+        self.pin
+    }
+}
@@ -19,6 +19,41 @@
 //!     assert!(clocks.i2s_clk().unwrap() == 48.MHz().into());
 //! ```
 //!
+//! # PLL configuration
+//!
+//! The PLL dividers are not selected by the caller. [`CFGR::freeze`] derives M, N, P and Q from
+//! the PLL source clock (the frequency passed to [`CFGR::use_hse`] or the 16 MHz HSI) and the
+//! requested [`sysclk`](CFGR::sysclk), keeping the VCO input between 1 and 2 MHz and the VCO
+//! output between 100 and 432 MHz. With [`require_pll48clk`](CFGR::require_pll48clk) the
+//! dividers giving the most accurate 48 MHz clock are preferred over the most accurate system
+//! clock. The frequencies actually generated are returned by [`Clocks::sysclk`] and
+//! [`Clocks::pll48clk`], and [`Clocks::is_pll48clk_valid`] tells if the 48 MHz clock is precise
+//! enough for USB.
+//!
+//! On STM32F446 and STM32F469/479 the R output of the main PLL is enabled with `pllr_clk`, its
+//! frequency is returned by `Clocks::pllr_clk`. It clocks SYSCLK on STM32F446 with
+//! `sysclk_from_pllr` and the DSI byte lanes on STM32F469/479 with `dsi_clk_from_pllr`.
+//!
+//! # I2S clock
+//!
+//! The I2S PLL (PLLI2S) is configured by [`CFGR::freeze`] from the frequency requested with
+//! `i2s_clk` (or `i2s_apb1_clk` and `i2s_apb2_clk` on MCUs with two I2S clocks). The M, N and R
+//! dividers are searched for the output closest to the requested frequency, the PLL is started and
+//! selected as I2S clock source. The Q output is not used. The frequency actually generated is
+//! returned by `Clocks::i2s_clk` (or `Clocks::i2s_apb1_clk` and `Clocks::i2s_apb2_clk`).
+//!
+//! Audio sample rates are generated by dividing the I2S clock by the I2S prescaler, so request a
+//! frequency which can be divided down to the sample rate with little error. With a 1 MHz PLL input
+//! (e.g. an 8 MHz HSE), the reference manual tables use 86 MHz (N = 258, R = 3) for 48 kHz and
+//! 135.5 MHz (N = 271, R = 2) for 44.1 kHz with master clock output enabled. On MCUs where the
+//! I2S PLL shares the M divider with the main PLL, M is chosen for the system clock and these
+//! frequencies may not be reachable exactly, so check the result:
+//!
+//! ```
+//! let clocks = rcc.cfgr.use_hse(8.MHz()).sysclk(168.MHz()).i2s_clk(86.MHz()).freeze();
+//! let i2s_clk = clocks.i2s_clk().unwrap();
+//! ```
+//!
 //! # Limitations
 //!
 //! Unlike the clock configuration tool provided by ST, the code does not extensively search all
@@ -32,8 +67,9 @@
 //! sourced from multiple PLLs, the code implements a fixed mapping between PLLs and clocks. The 48
 //! MHz clock is always generated by the main PLL, the I2S clocks are always generated by the I2S
 //! PLL (unless a matching external clock input is provided), and similarly the SAI clocks are
-//! always generated by the SAI PLL. It is therefore not possible to, for example, specify two
-//! different I2S frequencies unless you also provide a matching I2S_CKIN signal for one of them.
+//! always generated by the SAI PLL, which also generates the LCD-TFT clock with its R output. It
+//! is therefore not possible to, for example, specify two different I2S frequencies unless you
+//! also provide a matching I2S_CKIN signal for one of them.
 //!
 //! Some MCUs have limited clock generation hardware and do not provide either I2S or SAI PLLs even
 //! though I2S or SAI are available. On the STM32F410, the I2S clock is generated by the main PLL,
@@ -41,7 +77,7 @@
 //! frequencies may substantially deviate from the requested frequencies.
 
 use crate::pac::rcc::cfgr::{HPRE_A, SW_A};
-use crate::pac::{rcc, RCC};
+use crate::pac::{rcc, PWR, RCC};
 
 use fugit::HertzU32 as Hertz;
 use fugit::RateExtU32;
@@ -62,6 +98,9 @@ use pll::SaiPll;
 
 mod pll;
 
+mod mco;
+pub use mco::*;
+
 mod enable;
 use crate::pac::rcc::RegisterBlock as RccRB;
 
@@ -72,15 +111,60 @@ pub trait RccBus: crate::Sealed {
 }
 
 /// Enable/disable peripheral
+///
+/// Implemented for the PAC peripherals, so drivers outside of this crate can enable their
+/// clocks generically:
+///
+/// ```ignore
+/// fn init<SPI: Enable + Reset>() {
+///     let rcc = unsafe { &*pac::RCC::ptr() };
+///     SPI::enable(rcc);
+///     SPI::reset(rcc);
+/// }
+/// ```
 pub trait Enable: RccBus {
     fn enable(rcc: &RccRB);
     fn disable(rcc: &RccRB);
 }
 
 /// Low power enable/disable peripheral
+///
+/// Selects whether the clock of the peripheral keeps running while the core is in Sleep
+/// mode. All clocks are enabled in Sleep mode after reset, disabling the unused ones with
+/// [`low_power_disable_all`] and enabling those that wake the MCU up or do transfers
+/// meanwhile lowers the sleep current:
+///
+/// ```ignore
+/// let rcc = unsafe { &*pac::RCC::ptr() };
+/// rcc::low_power_disable_all(rcc);
+/// pac::USART2::low_power_enable(rcc);
+/// pac::DMA1::low_power_enable(rcc);
+/// cortex_m::asm::wfi();
+/// ```
 pub trait LPEnable: RccBus {
     fn low_power_enable(rcc: &RccRB);
     fn low_power_disable(rcc: &RccRB);
+    /// Returns true if the clock is enabled in Sleep mode
+    fn is_low_power_enabled(rcc: &RccRB) -> bool;
+}
+
+/// Disables the clocks of all peripherals in Sleep mode
+///
+/// The Flash interface and SRAM clocks are kept enabled, so DMA transfers from and to
+/// memory continue. The peripherals still used in Sleep mode must be enabled again with
+/// [`LPEnable::low_power_enable`]. Clocks only gate Sleep mode, in Stop and Standby modes
+/// all peripheral clocks are stopped anyway.
+pub fn low_power_disable_all(rcc: &RccRB) {
+    // FLITFLPEN, SRAM1LPEN, SRAM2LPEN, BKPSRAMLPEN and SRAM3LPEN
+    const MEMORY_MASK: u32 = 0x000f_8000;
+    rcc.ahb1lpenr
+        .modify(|r, w| unsafe { w.bits(r.bits() & MEMORY_MASK) });
+    #[cfg(not(feature = "stm32f410"))]
+    rcc.ahb2lpenr.write(|w| unsafe { w.bits(0) });
+    #[cfg(all(any(feature = "fsmc", feature = "fmc"), not(feature = "stm32f412")))]
+    rcc.ahb3lpenr.write(|w| unsafe { w.bits(0) });
+    rcc.apb1lpenr.write(|w| unsafe { w.bits(0) });
+    rcc.apb2lpenr.write(|w| unsafe { w.bits(0) });
 }
 
 /// Reset peripheral
@@ -180,7 +264,7 @@ impl AHB3 {
     fn enr(rcc: &RccRB) -> &rcc::AHB3ENR {
         &rcc.ahb3enr
     }
-    #[cfg(feature = "fmc")]
+    #[cfg(not(feature = "stm32f412"))]
     #[inline(always)]
     fn lpenr(rcc: &RccRB) -> &rcc::AHB3LPENR {
         &rcc.ahb3lpenr
@@ -265,89 +349,49 @@ impl BusClock for APB2 {
 
 impl BusTimerClock for APB1 {
     fn timer_clock(clocks: &Clocks) -> Hertz {
-        let pclk_mul = if clocks.ppre1 == 1 { 1 } else { 2 };
-        Hertz::from_raw(clocks.pclk1.raw() * pclk_mul)
+        clocks.timclk1()
     }
 }
 
 impl BusTimerClock for APB2 {
     fn timer_clock(clocks: &Clocks) -> Hertz {
-        let pclk_mul = if clocks.ppre2 == 1 { 1 } else { 2 };
-        Hertz::from_raw(clocks.pclk2.raw() * pclk_mul)
+        clocks.timclk2()
     }
 }
 
 impl RccExt for RCC {
     fn constrain(self) -> Rcc {
-        Rcc {
-            cfgr: CFGR {
-                hse: None,
-                hse_bypass: false,
-                hclk: None,
-                pclk1: None,
-                pclk2: None,
-                sysclk: None,
-                pll48clk: false,
-                i2s_ckin: None,
-                #[cfg(any(
-                    feature = "stm32f401",
-                    feature = "stm32f405",
-                    feature = "stm32f407",
-                    feature = "stm32f410",
-                    feature = "stm32f411",
-                    feature = "stm32f415",
-                    feature = "stm32f417",
-                    feature = "stm32f427",
-                    feature = "stm32f429",
-                    feature = "stm32f437",
-                    feature = "stm32f439",
-                    feature = "stm32f469",
-                    feature = "stm32f479"
-                ))]
-                i2s_clk: None,
-                #[cfg(any(
-                    feature = "stm32f412",
-                    feature = "stm32f413",
-                    feature = "stm32f423",
-                    feature = "stm32f446",
-                ))]
-                i2s_apb1_clk: None,
-                #[cfg(any(
-                    feature = "stm32f412",
-                    feature = "stm32f413",
-                    feature = "stm32f423",
-                    feature = "stm32f446",
-                ))]
-                i2s_apb2_clk: None,
-                #[cfg(any(
-                    feature = "stm32f413",
-                    feature = "stm32f423",
-                    feature = "stm32f427",
-                    feature = "stm32f429",
-                    feature = "stm32f437",
-                    feature = "stm32f439",
-                    feature = "stm32f446",
-                    feature = "stm32f469",
-                    feature = "stm32f479",
-                ))]
-                sai1_clk: None,
-                #[cfg(any(
-                    feature = "stm32f413",
-                    feature = "stm32f423",
-                    feature = "stm32f427",
-                    feature = "stm32f429",
-                    feature = "stm32f437",
-                    feature = "stm32f439",
-                    feature = "stm32f446",
-                    feature = "stm32f469",
-                    feature = "stm32f479",
-                ))]
-                sai2_clk: None,
-            },
-        }
+        Rcc { cfgr: CFGR::new() }
     }
 }
 
+/// Returns true if the clock security system detected a failure of the HSE
+///
+/// A failure raises the non-maskable interrupt, which can't be disabled, so the `NMI` handler
+/// must clear the flag with [`clear_css_failure`] or it is entered again.
+pub fn is_css_failure() -> bool {
+    // NOTE(unsafe) atomic read with no side effects
+    unsafe { &*RCC::ptr() }.cir.read().cssf().bit_is_set()
+}
+
+/// Clears the clock security system failure flag and the non-maskable interrupt
+///
+/// ```ignore
+/// #[exception]
+/// fn NMI() {
+///     if rcc::is_css_failure() {
+///         rcc::clear_css_failure();
+///         // Running on the HSI now, e.g. signal the failure to the main loop
+///     }
+/// }
+/// ```
+pub fn clear_css_failure() {
+    // NOTE(unsafe) the clear bits of CIR read as zero, the interrupt enables are kept
+    unsafe { &*RCC::ptr() }
+        .cir
+        .modify(|_, w| w.cssc().set_bit());
+}
+
 /// Constrained RCC peripheral
 pub struct Rcc {
     pub cfgr: CFGR,
@@ -356,6 +400,12 @@ pub struct Rcc {
 /// Built-in high speed clock frequency
 pub const HSI: u32 = 16_000_000; // Hz
 
+/// Low speed external clock frequency
+pub const LSE: u32 = 32_768; // Hz
+
+/// Built-in low speed clock frequency
+pub const LSI: u32 = 32_000; // Hz
+
 #[cfg(any(
     feature = "stm32f401",
     feature = "stm32f405",
@@ -441,9 +491,116 @@ pub const PCLK2_MAX: u32 = SYSCLK_MAX / 2;
 /// Maximum APB1 peripheral clock frequency
 pub const PCLK1_MAX: u32 = PCLK2_MAX / 2;
 
+/// LSE clock mode.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LSEClockMode {
+    /// Enable LSE oscillator to use external crystal or ceramic resonator.
+    Oscillator,
+    /// Bypass LSE oscillator to use external clock source.
+    /// Use this if an external oscillator is used which is not connected to `OSC32_IN` such as a MEMS resonator.
+    Bypass,
+}
+
+/// LSE oscillator drive capability.
+#[cfg(any(
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f446"
+))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LseDrive {
+    /// Low power mode, the reset value.
+    LowPower,
+    /// High drive mode for crystals with higher load capacitance.
+    HighDrive,
+}
+
+/// Clock configuration error
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The PLL source clock can't be divided down to the 1 - 2 MHz VCO input range
+    PllInput,
+    /// The system clock is outside of `SYSCLK_MIN` - `SYSCLK_MAX`
+    Sysclk,
+    /// The 48 MHz clock is required but not within the USB specification
+    Pll48clk,
+    /// The APB1 clock is above `PCLK1_MAX`
+    Pclk1,
+    /// The APB2 clock is above `PCLK2_MAX`
+    Pclk2,
+    /// The main PLL can't generate the requested SYSCLK, 48 MHz or R output clock
+    Pll,
+    /// The I2S PLL can't generate the requested I2S clock or different I2S clocks are requested
+    PllI2s,
+    /// The SAI PLL can't generate the requested SAI or LCD-TFT clock or different SAI clocks
+    /// are requested
+    #[cfg(any(
+        feature = "stm32f413",
+        feature = "stm32f423",
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f446",
+        feature = "stm32f469",
+        feature = "stm32f479",
+    ))]
+    PllSai,
+    /// The spread spectrum modulation can't be generated with the main PLL dividers
+    SpreadSpectrum,
+    /// The HSE is selected as RTC clock, but not enabled with `use_hse`
+    Hse,
+    /// A different RTC clock source is already selected, it can only be changed with
+    /// `reset_backup_domain`
+    RtcClock,
+}
+
+/// Spread spectrum modulation of the main PLL.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpreadMode {
+    /// Frequency modulated around the nominal frequency.
+    Center,
+    /// Frequency modulated below the nominal frequency.
+    Down,
+}
+
+/// Clock source of the RTC.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RtcClockSource {
+    /// 32.768 kHz low-speed external oscillator.
+    Lse,
+    /// 32 kHz low-speed internal RC oscillator, also used by the independent watchdog.
+    Lsi,
+    /// HSE divided down to 1 MHz or less with the RTC prescaler.
+    Hse,
+}
+
 pub struct CFGR {
     hse: Option<u32>,
     hse_bypass: bool,
+    css: bool,
+    spread_spectrum: Option<(SpreadMode, u32, u16)>,
+    lse: Option<LSEClockMode>,
+    #[cfg(any(
+        feature = "stm32f410",
+        feature = "stm32f411",
+        feature = "stm32f412",
+        feature = "stm32f413",
+        feature = "stm32f423",
+        feature = "stm32f446"
+    ))]
+    lse_drive: LseDrive,
+    lsi: bool,
+    rtc_clock: Option<RtcClockSource>,
+    reset_backup_domain: bool,
     hclk: Option<u32>,
     pclk1: Option<u32>,
     pclk2: Option<u32>,
@@ -505,9 +662,117 @@ pub struct CFGR {
         feature = "stm32f479",
     ))]
     sai2_clk: Option<u32>,
+    #[cfg(any(
+        feature = "stm32f429",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    lcd_clk: Option<u32>,
+    #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+    pllr_clk: Option<u32>,
+    #[cfg(feature = "stm32f446")]
+    sysclk_pllr: bool,
+    #[cfg(any(feature = "stm32f469", feature = "stm32f479"))]
+    dsi_pllr: bool,
 }
 
 impl CFGR {
+    fn new() -> Self {
+        CFGR {
+            hse: None,
+            hse_bypass: false,
+            css: false,
+            spread_spectrum: None,
+            lse: None,
+            #[cfg(any(
+                feature = "stm32f410",
+                feature = "stm32f411",
+                feature = "stm32f412",
+                feature = "stm32f413",
+                feature = "stm32f423",
+                feature = "stm32f446"
+            ))]
+            lse_drive: LseDrive::LowPower,
+            lsi: false,
+            rtc_clock: None,
+            reset_backup_domain: false,
+            hclk: None,
+            pclk1: None,
+            pclk2: None,
+            sysclk: None,
+            pll48clk: false,
+            i2s_ckin: None,
+            #[cfg(any(
+                feature = "stm32f401",
+                feature = "stm32f405",
+                feature = "stm32f407",
+                feature = "stm32f410",
+                feature = "stm32f411",
+                feature = "stm32f415",
+                feature = "stm32f417",
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            i2s_clk: None,
+            #[cfg(any(
+                feature = "stm32f412",
+                feature = "stm32f413",
+                feature = "stm32f423",
+                feature = "stm32f446",
+            ))]
+            i2s_apb1_clk: None,
+            #[cfg(any(
+                feature = "stm32f412",
+                feature = "stm32f413",
+                feature = "stm32f423",
+                feature = "stm32f446",
+            ))]
+            i2s_apb2_clk: None,
+            #[cfg(any(
+                feature = "stm32f413",
+                feature = "stm32f423",
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479",
+            ))]
+            sai1_clk: None,
+            #[cfg(any(
+                feature = "stm32f413",
+                feature = "stm32f423",
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479",
+            ))]
+            sai2_clk: None,
+            #[cfg(any(
+                feature = "stm32f429",
+                feature = "stm32f439",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            lcd_clk: None,
+            #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+            pllr_clk: None,
+            #[cfg(feature = "stm32f446")]
+            sysclk_pllr: false,
+            #[cfg(any(feature = "stm32f469", feature = "stm32f479"))]
+            dsi_pllr: false,
+        }
+    }
+
     /// Uses HSE (external oscillator) instead of HSI (internal RC oscillator) as the clock source.
     /// Will result in a hang if an external oscillator is not connected or it fails to start.
     pub fn use_hse(mut self, freq: Hertz) -> Self {
@@ -522,6 +787,12 @@ impl CFGR {
     /// frequency specified in the call to use_hse(), and the OSC_OUT pin should not be connected.
     ///
     /// This function has no effect unless use_hse() is also called.
+    ///
+    /// E.g. on Nucleo boards the 8 MHz MCO output of the ST-LINK is connected to OSC_IN:
+    ///
+    /// ```ignore
+    /// let clocks = rcc.cfgr.use_hse(8.MHz()).bypass_hse_oscillator().sysclk(84.MHz()).freeze();
+    /// ```
     pub fn bypass_hse_oscillator(self) -> Self {
         CFGR {
             hse_bypass: true,
@@ -529,6 +800,89 @@ impl CFGR {
         }
     }
 
+    /// Enables the clock security system (CSS) on the HSE.
+    ///
+    /// On a failure of the HSE, the HSE and the main PLL (if sourced from the HSE) are stopped,
+    /// the system clock is switched to the HSI and a non-maskable interrupt is raised, see
+    /// [`is_css_failure`] and [`clear_css_failure`]. The frequencies of the returned [`Clocks`]
+    /// are no longer valid after such a failure.
+    ///
+    /// This function has no effect unless use_hse() is also called.
+    pub fn enable_css(mut self) -> Self {
+        self.css = true;
+        self
+    }
+
+    /// Enables spread spectrum clock generation in the main PLL to reduce EMI.
+    ///
+    /// The PLL output is modulated with a triangle wave of `modulation` frequency (up to 10 kHz)
+    /// and a peak deviation of `depth_permille` (1 to 20, i.e. 0.1% to 2%). All clocks generated
+    /// by the main PLL are modulated, including the 48 MHz clock, so USB is not usable with
+    /// spread spectrum.
+    ///
+    /// This function has no effect if the main PLL is not used.
+    pub fn spread_spectrum(
+        mut self,
+        mode: SpreadMode,
+        modulation: Hertz,
+        depth_permille: u16,
+    ) -> Self {
+        self.spread_spectrum = Some((mode, modulation.raw(), depth_permille));
+        self
+    }
+
+    /// Enables the low-speed external oscillator (32.768 kHz).
+    ///
+    /// Access to the backup domain is enabled by [`freeze`](Self::freeze). If the LSE is already
+    /// running, e.g. after a reset with the backup domain powered, it is kept as is.
+    pub fn lse(mut self, mode: LSEClockMode) -> Self {
+        self.lse = Some(mode);
+        self
+    }
+
+    /// Selects the drive capability of the LSE oscillator.
+    ///
+    /// Has no effect unless [`lse`](Self::lse) is also called.
+    #[cfg(any(
+        feature = "stm32f410",
+        feature = "stm32f411",
+        feature = "stm32f412",
+        feature = "stm32f413",
+        feature = "stm32f423",
+        feature = "stm32f446"
+    ))]
+    pub fn lse_drive(mut self, drive: LseDrive) -> Self {
+        self.lse_drive = drive;
+        self
+    }
+
+    /// Enables the low-speed internal RC oscillator (32 kHz).
+    pub fn lsi(mut self) -> Self {
+        self.lsi = true;
+        self
+    }
+
+    /// Selects the clock source of the RTC and enables the RTC clock.
+    ///
+    /// The LSE (in oscillator mode unless configured with [`lse`](Self::lse)) or the LSI is
+    /// enabled if selected. The source can only be changed by a reset of the backup domain, if a
+    /// different source is already selected `freeze` fails with [`Error::RtcClock`] unless
+    /// [`reset_backup_domain`](Self::reset_backup_domain) allows it.
+    pub fn rtc_clock(mut self, source: RtcClockSource) -> Self {
+        self.rtc_clock = Some(source);
+        self
+    }
+
+    /// Allows a reset of the backup domain to change the RTC clock source selected with
+    /// [`rtc_clock`](Self::rtc_clock).
+    ///
+    /// The reset is only done if a different source is already selected. It stops the RTC,
+    /// clears the calendar and the backup registers and disables the LSE.
+    pub fn reset_backup_domain(mut self, reset: bool) -> Self {
+        self.reset_backup_domain = reset;
+        self
+    }
+
     pub fn hclk(mut self, freq: Hertz) -> Self {
         self.hclk = Some(freq.raw());
         self
@@ -653,10 +1007,58 @@ impl CFGR {
         self.sai2_clk = Some(freq.raw());
         self
     }
+
+    /// Selects an LCD-TFT clock frequency and enables the LCD-TFT clock.
+    ///
+    /// The clock is generated by the R output of the SAI PLL and the LCD-TFT divider. If SAI
+    /// clocks are requested as well, the SAI PLL is configured for them first and only the R
+    /// output and LCD-TFT dividers are selected for this clock.
+    #[cfg(any(
+        feature = "stm32f429",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    pub fn lcd_clk(mut self, freq: Hertz) -> Self {
+        self.lcd_clk = Some(freq.raw());
+        self
+    }
+
+    /// Selects a frequency for the R output of the main PLL and enables it.
+    ///
+    /// If the main PLL is used for SYSCLK or the 48 MHz clock, only the R divider is selected
+    /// for this clock, so the frequency can be off by a lot. Otherwise the PLL is configured for
+    /// the R output alone.
+    #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+    pub fn pllr_clk(mut self, freq: Hertz) -> Self {
+        self.pllr_clk = Some(freq.raw());
+        self
+    }
+
+    /// Generates SYSCLK with the R output of the main PLL instead of the P output.
+    ///
+    /// The R divider can also be odd, so more SYSCLK frequencies can be generated exactly. The
+    /// frequency is still selected with [`sysclk`](Self::sysclk), a frequency selected with
+    /// `pllr_clk` is ignored.
+    #[cfg(feature = "stm32f446")]
+    pub fn sysclk_from_pllr(mut self) -> Self {
+        self.sysclk_pllr = true;
+        self
+    }
+
+    /// Clocks the DSI byte lanes with the R output of the main PLL instead of the DSI-PHY.
+    ///
+    /// Used in low power mode when the DSI PLL and the DSI-PHY are off. The frequency must be
+    /// selected with [`pllr_clk`](Self::pllr_clk).
+    #[cfg(any(feature = "stm32f469", feature = "stm32f479"))]
+    pub fn dsi_clk_from_pllr(mut self) -> Self {
+        self.dsi_pllr = true;
+        self
+    }
     #[cfg(feature = "stm32f410")]
     #[inline(always)]
-    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> PllSetup {
-        let i2s_clocks = self.i2s_clocks();
+    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> Result<PllSetup, Error> {
+        let i2s_clocks = self.i2s_clocks()?;
 
         let main_pll = if let Some(i2s_clk) = i2s_clocks.pll_i2s_clk {
             // The I2S frequency is generated by the main PLL. The frequency needs to be accurate,
@@ -670,95 +1072,118 @@ impl CFGR {
             )
         } else {
             MainPll::fast_setup(pllsrcclk, self.hse.is_some(), pllsysclk, self.pll48clk)
-        };
+        }
+        .ok_or(Error::Pll)?;
 
-        PllSetup {
-            use_pll: main_pll.use_pll,
+        Ok(PllSetup {
             pllsysclk: main_pll.pllsysclk,
             pll48clk: main_pll.pll48clk,
             i2s: i2s_clocks.real(main_pll.plli2sclk, self.i2s_ckin),
-        }
+            main_pll,
+        })
     }
 
     #[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
     #[inline(always)]
-    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> PllSetup {
-        let rcc = unsafe { &*RCC::ptr() };
+    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> Result<PllSetup, Error> {
+        let i2s_clocks = self.i2s_clocks()?;
+        let sai_clocks = self.sai_clocks()?;
 
-        let i2s_clocks = self.i2s_clocks();
-        let sai_clocks = self.sai_clocks();
+        let main_pll = MainPll::fast_setup(pllsrcclk, self.hse.is_some(), pllsysclk, self.pll48clk)
+            .ok_or(Error::Pll)?;
 
-        let main_pll = MainPll::fast_setup(pllsrcclk, self.hse.is_some(), pllsysclk, self.pll48clk);
-
-        let (i2s_pll, real_sai_clk) = if let Some(i2s_clk) = i2s_clocks.pll_i2s_clk {
+        let (i2s_pll, real_sai_clk, plli2sdivr) = if let Some(i2s_clk) = i2s_clocks.pll_i2s_clk {
             // Currently, we only support generating SAI/PLL clocks with the I2S PLL. This is only
             // really usable when the frequencies are identical or the I2S frequency is a multiple of
             // the SAI frequency. Therefore, we just optimize the PLL for the I2S frequency and then
             // derive the SAI frequency from the I2S frequency.
-            let i2s_pll = I2sPll::setup(pllsrcclk, Some(i2s_clk));
+            let i2s_pll = I2sPll::setup(pllsrcclk, Some(i2s_clk)).ok_or(Error::PllI2s)?;
 
             if let Some(sai_clk) = sai_clocks.pll_sai_clk {
                 let div = u32::min(
                     u32::max((i2s_pll.plli2sclk.unwrap() + (sai_clk >> 1)) / sai_clk, 1),
                     31,
                 );
-                rcc.dckcfgr.modify(|_, w| w.plli2sdivr().bits(div as u8));
                 let real_sai_clk = sai_clk / div;
-                (i2s_pll, Some(real_sai_clk))
+                (i2s_pll, Some(real_sai_clk), Some(div as u8))
             } else {
-                (i2s_pll, None)
+                (i2s_pll, None, None)
             }
         } else if let Some(pll_sai_clk) = sai_clocks.pll_sai_clk {
             // We try all divider values to get the best approximation of the requested frequency.
             // NOTE: STM32F413/423 have a different divider range than other models!
             let (i2s_pll, real_sai_clk, div) = (1..31)
-                .map(|div| {
-                    let i2s_pll = I2sPll::setup(pllsrcclk, Some(pll_sai_clk * div));
+                .filter_map(|div| {
+                    let i2s_pll = I2sPll::setup(pllsrcclk, Some(pll_sai_clk * div))?;
                     let real_clk = i2s_pll.plli2sclk.unwrap() / div;
-                    (i2s_pll, real_clk, div)
+                    Some((i2s_pll, real_clk, div))
                 })
                 .min_by_key(|(_, real_clk, _)| (*real_clk as i32 - pll_sai_clk as i32).abs())
-                .unwrap();
-            rcc.dckcfgr.modify(|_, w| w.plli2sdivr().bits(div as u8));
-            (i2s_pll, Some(real_sai_clk))
+                .ok_or(Error::PllSai)?;
+            (i2s_pll, Some(real_sai_clk), Some(div as u8))
         } else {
-            (I2sPll::unused(), None)
+            (I2sPll::unused(), None, None)
         };
 
-        PllSetup {
-            use_pll: main_pll.use_pll,
-            use_i2spll: i2s_pll.use_pll,
+        Ok(PllSetup {
             pllsysclk: main_pll.pllsysclk,
             pll48clk: main_pll.pll48clk,
             i2s: i2s_clocks.real(i2s_pll.plli2sclk, self.i2s_ckin),
             sai: sai_clocks.real(real_sai_clk, self.i2s_ckin),
-        }
+            main_pll,
+            i2s_pll,
+            plli2sdivr,
+        })
     }
 
     #[cfg(any(feature = "stm32f411", feature = "stm32f412", feature = "stm32f446"))]
     #[inline(always)]
-    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> PllSetup {
-        let i2s_clocks = self.i2s_clocks();
+    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> Result<PllSetup, Error> {
+        let i2s_clocks = self.i2s_clocks()?;
         #[cfg(feature = "stm32f446")]
-        let sai_clocks = self.sai_clocks();
+        let sai_clocks = self.sai_clocks()?;
+
+        // SYSCLK on the "R" output leaves the "P" output unused.
+        #[cfg(feature = "stm32f446")]
+        let (pllsysclk, pllr_clk) = if self.sysclk_pllr {
+            (None, pllsysclk)
+        } else {
+            (pllsysclk, self.pllr_clk)
+        };
 
         // All PLLs are completely independent.
-        let main_pll = MainPll::fast_setup(pllsrcclk, self.hse.is_some(), pllsysclk, self.pll48clk);
-        let i2s_pll = I2sPll::setup(pllsrcclk, i2s_clocks.pll_i2s_clk);
+        let main_pll = MainPll::fast_setup(pllsrcclk, self.hse.is_some(), pllsysclk, self.pll48clk)
+            .ok_or(Error::Pll)?;
+        #[cfg(feature = "stm32f446")]
+        let mut main_pll = main_pll;
         #[cfg(feature = "stm32f446")]
-        let sai_pll = SaiPll::setup(pllsrcclk, sai_clocks.pll_sai_clk);
+        let pllr_clk = pllr_clk
+            .map(|clk| main_pll.setup_r(pllsrcclk, clk).ok_or(Error::Pll))
+            .transpose()?;
+        let i2s_pll = I2sPll::setup(pllsrcclk, i2s_clocks.pll_i2s_clk).ok_or(Error::PllI2s)?;
+        #[cfg(feature = "stm32f446")]
+        let sai_pll = SaiPll::setup(pllsrcclk, sai_clocks.pll_sai_clk).ok_or(Error::PllSai)?;
 
-        PllSetup {
-            use_pll: main_pll.use_pll,
-            use_i2spll: i2s_pll.use_pll,
-            #[cfg(feature = "stm32f446")]
-            use_saipll: sai_pll.use_pll,
+        Ok(PllSetup {
+            #[cfg(not(feature = "stm32f446"))]
             pllsysclk: main_pll.pllsysclk,
+            #[cfg(feature = "stm32f446")]
+            pllsysclk: if self.sysclk_pllr {
+                pllr_clk
+            } else {
+                main_pll.pllsysclk
+            },
             pll48clk: main_pll.pll48clk,
             i2s: i2s_clocks.real(i2s_pll.plli2sclk, self.i2s_ckin),
             #[cfg(feature = "stm32f446")]
             sai: sai_clocks.real(sai_pll.sai_clk, self.i2s_ckin),
-        }
+            #[cfg(feature = "stm32f446")]
+            pllr_clk,
+            main_pll,
+            i2s_pll,
+            #[cfg(feature = "stm32f446")]
+            sai_pll,
+        })
     }
 
     #[cfg(any(
@@ -775,8 +1200,8 @@ impl CFGR {
         feature = "stm32f479"
     ))]
     #[inline(always)]
-    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> PllSetup {
-        let i2s_clocks = self.i2s_clocks();
+    fn pll_setup(&self, pllsrcclk: u32, pllsysclk: Option<u32>) -> Result<PllSetup, Error> {
+        let i2s_clocks = self.i2s_clocks()?;
         #[cfg(any(
             feature = "stm32f427",
             feature = "stm32f429",
@@ -785,11 +1210,20 @@ impl CFGR {
             feature = "stm32f469",
             feature = "stm32f479"
         ))]
-        let sai_clocks = self.sai_clocks();
+        let sai_clocks = self.sai_clocks()?;
 
         // We have separate PLLs, but they share the "M" divider.
-        let main_pll = MainPll::fast_setup(pllsrcclk, self.hse.is_some(), pllsysclk, self.pll48clk);
-        let i2s_pll = I2sPll::setup_shared_m(pllsrcclk, main_pll.m, i2s_clocks.pll_i2s_clk);
+        let main_pll = MainPll::fast_setup(pllsrcclk, self.hse.is_some(), pllsysclk, self.pll48clk)
+            .ok_or(Error::Pll)?;
+        #[cfg(any(feature = "stm32f469", feature = "stm32f479"))]
+        let mut main_pll = main_pll;
+        #[cfg(any(feature = "stm32f469", feature = "stm32f479"))]
+        let pllr_clk = self
+            .pllr_clk
+            .map(|clk| main_pll.setup_r(pllsrcclk, clk).ok_or(Error::Pll))
+            .transpose()?;
+        let i2s_pll = I2sPll::setup_shared_m(pllsrcclk, main_pll.m, i2s_clocks.pll_i2s_clk)
+            .ok_or(Error::PllI2s)?;
         #[cfg(any(
             feature = "stm32f427",
             feature = "stm32f429",
@@ -799,11 +1233,34 @@ impl CFGR {
             feature = "stm32f479"
         ))]
         let sai_pll =
-            SaiPll::setup_shared_m(pllsrcclk, main_pll.m.or(i2s_pll.m), sai_clocks.pll_sai_clk);
+            SaiPll::setup_shared_m(pllsrcclk, main_pll.m.or(i2s_pll.m), sai_clocks.pll_sai_clk)
+                .ok_or(Error::PllSai)?;
+        #[cfg(any(
+            feature = "stm32f429",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        let mut sai_pll = sai_pll;
+        #[cfg(any(
+            feature = "stm32f429",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        let lcd_clk = self
+            .lcd_clk
+            .map(|clk| {
+                sai_pll
+                    .setup_lcd(pllsrcclk, main_pll.m.or(i2s_pll.m), clk)
+                    .ok_or(Error::PllSai)
+            })
+            .transpose()?;
 
-        PllSetup {
-            use_pll: main_pll.use_pll,
-            use_i2spll: i2s_pll.use_pll,
+        Ok(PllSetup {
+            pllsysclk: main_pll.pllsysclk,
+            pll48clk: main_pll.pll48clk,
+            i2s: i2s_clocks.real(i2s_pll.plli2sclk, self.i2s_ckin),
             #[cfg(any(
                 feature = "stm32f427",
                 feature = "stm32f429",
@@ -812,10 +1269,18 @@ impl CFGR {
                 feature = "stm32f469",
                 feature = "stm32f479"
             ))]
-            use_saipll: sai_pll.use_pll,
-            pllsysclk: main_pll.pllsysclk,
-            pll48clk: main_pll.pll48clk,
-            i2s: i2s_clocks.real(i2s_pll.plli2sclk, self.i2s_ckin),
+            sai: sai_clocks.real(sai_pll.sai_clk, self.i2s_ckin),
+            #[cfg(any(
+                feature = "stm32f429",
+                feature = "stm32f439",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            lcd_clk,
+            #[cfg(any(feature = "stm32f469", feature = "stm32f479"))]
+            pllr_clk,
+            main_pll,
+            i2s_pll,
             #[cfg(any(
                 feature = "stm32f427",
                 feature = "stm32f429",
@@ -824,8 +1289,8 @@ impl CFGR {
                 feature = "stm32f469",
                 feature = "stm32f479"
             ))]
-            sai: sai_clocks.real(sai_pll.sai_clk, self.i2s_ckin),
-        }
+            sai_pll,
+        })
     }
 
     #[cfg(any(
@@ -839,7 +1304,7 @@ impl CFGR {
         feature = "stm32f469",
         feature = "stm32f479",
     ))]
-    fn sai_clocks(&self) -> SaiClocks {
+    fn sai_clocks(&self) -> Result<SaiClocks, Error> {
         let sai1_ext = self.sai1_clk.is_some() && self.sai1_clk == self.i2s_ckin;
         #[cfg(not(feature = "stm32f446"))]
         let sai2_ext = self.sai2_clk.is_some() && self.sai2_clk == self.i2s_ckin;
@@ -850,15 +1315,16 @@ impl CFGR {
         let pll_sai_clk2 = self.sai2_clk;
         #[cfg(not(feature = "stm32f446"))]
         let pll_sai_clk2 = if sai2_ext { None } else { self.sai2_clk };
+        // Only one SAI PLL frequency is implemented
         if pll_sai_clk.is_some() && pll_sai_clk2.is_some() && pll_sai_clk != pll_sai_clk2 {
-            panic!("only one SAI PLL frequency implemented");
+            return Err(Error::PllSai);
         }
-        SaiClocks {
+        Ok(SaiClocks {
             sai1_ext,
             #[cfg(not(feature = "stm32f446"))]
             sai2_ext,
             pll_sai_clk,
-        }
+        })
     }
 
     #[cfg(any(
@@ -867,7 +1333,7 @@ impl CFGR {
         feature = "stm32f423",
         feature = "stm32f446",
     ))]
-    fn i2s_clocks(&self) -> I2sClocks {
+    fn i2s_clocks(&self) -> Result<I2sClocks, Error> {
         let i2s_apb1_ext = self.i2s_apb1_clk.is_some() && self.i2s_apb1_clk == self.i2s_ckin;
         let i2s_apb2_ext = self.i2s_apb2_clk.is_some() && self.i2s_apb2_clk == self.i2s_ckin;
         let pll_i2s_clk = if i2s_apb1_ext {
@@ -880,14 +1346,15 @@ impl CFGR {
         } else {
             self.i2s_apb2_clk
         };
+        // Only one I2S PLL frequency is implemented
         if pll_i2s_clk.is_some() && pll_i2s_clk2.is_some() && pll_i2s_clk != pll_i2s_clk2 {
-            panic!("only one I2S PLL frequency implemented");
+            return Err(Error::PllI2s);
         }
-        I2sClocks {
+        Ok(I2sClocks {
             i2s_apb1_ext,
             i2s_apb2_ext,
             pll_i2s_clk,
-        }
+        })
     }
 
     #[cfg(not(any(
@@ -896,13 +1363,13 @@ impl CFGR {
         feature = "stm32f423",
         feature = "stm32f446",
     )))]
-    fn i2s_clocks(&self) -> I2sClocks {
+    fn i2s_clocks(&self) -> Result<I2sClocks, Error> {
         let i2s_ext = self.i2s_clk.is_some() && self.i2s_clk == self.i2s_ckin;
         let pll_i2s_clk = if i2s_ext { None } else { self.i2s_clk };
-        I2sClocks {
+        Ok(I2sClocks {
             i2s_ext,
             pll_i2s_clk,
-        }
+        })
     }
 
     fn flash_setup(sysclk: u32) {
@@ -942,9 +1409,191 @@ impl CFGR {
         }
     }
 
+    /// Selects the lowest regulator voltage scale supporting `hclk`, the PLL must be disabled.
+    ///
+    /// STM32F405/407/415/417 always run in scale 1 after reset, which supports all frequencies.
+    #[allow(unused_variables)]
+    fn voltage_scaling_setup(rcc: &RccRB, hclk: u32) {
+        #[cfg(feature = "stm32f401")]
+        let vos = if hclk <= 60_000_000 { 0b01 } else { 0b10 };
+        #[cfg(any(
+            feature = "stm32f410",
+            feature = "stm32f411",
+            feature = "stm32f412",
+            feature = "stm32f413",
+            feature = "stm32f423"
+        ))]
+        let vos = match hclk {
+            0..=64_000_000 => 0b01,
+            64_000_001..=84_000_000 => 0b10,
+            _ => 0b11,
+        };
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f446",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        let vos = match hclk {
+            0..=120_000_000 => 0b01,
+            120_000_001..=144_000_000 => 0b10,
+            _ => 0b11,
+        };
+        #[cfg(not(any(
+            feature = "stm32f405",
+            feature = "stm32f407",
+            feature = "stm32f415",
+            feature = "stm32f417"
+        )))]
+        {
+            PWR::enable(rcc);
+            unsafe { &*PWR::ptr() }
+                .cr
+                .modify(|_, w| unsafe { w.vos().bits(vos) });
+        }
+    }
+
+    /// Calculates `MODPER` and `INCSTEP` from the main PLL dividers
+    fn spread_spectrum_config(
+        pllsrcclk: u32,
+        main_pll: &MainPll,
+        modulation: u32,
+        depth_permille: u16,
+    ) -> Option<(u32, u32)> {
+        if !(1..=10_000).contains(&modulation) || !(1..=20).contains(&depth_permille) {
+            return None;
+        }
+
+        let vco_in = pllsrcclk / main_pll.m?;
+        let plln = main_pll.n;
+
+        // MODPER = round(fPLL_IN / (4 * fMod))
+        let modper = (vco_in + 2 * modulation) / (4 * modulation);
+        // INCSTEP = round((2^15 - 1) * md * PLLN / (100 * 5 * MODPER)), md in percent
+        let incstep = ((1 << 15) - 1) * depth_permille as u32 * plln;
+        let incstep = (incstep + 2500 * modper) / (5000 * modper);
+        if modper < (1 << 13) && modper * incstep < (1 << 15) {
+            Some((modper, incstep))
+        } else {
+            None
+        }
+    }
+
+    /// Configures `SSCGR`, the PLL must be disabled.
+    fn spread_spectrum_setup(rcc: &RccRB, mode: SpreadMode, modper: u32, incstep: u32) {
+        #[allow(unused_unsafe)]
+        rcc.sscgr.write(|w| unsafe {
+            w.modper()
+                .bits(modper as u16)
+                .incstep()
+                .bits(incstep as u16)
+                .spreadsel()
+                .bit(mode == SpreadMode::Down)
+                .sscgen()
+                .set_bit()
+        });
+    }
+
+    /// Returns `true` if a different RTC clock source than the requested one is selected.
+    fn rtc_clock_differs(&self, rcc: &RccRB) -> bool {
+        let rtcsel = match self.rtc_clock {
+            Some(RtcClockSource::Lse) => 0b01,
+            Some(RtcClockSource::Lsi) => 0b10,
+            Some(RtcClockSource::Hse) => 0b11,
+            None => return false,
+        };
+        let current = rcc.bdcr.read().rtcsel().bits();
+        current != 0 && current != rtcsel
+    }
+
+    fn low_speed_setup(&self, rcc: &RccRB) -> (Option<u32>, Option<u32>, Option<u32>) {
+        let lse = match self.rtc_clock {
+            Some(RtcClockSource::Lse) => Some(self.lse.unwrap_or(LSEClockMode::Oscillator)),
+            _ => self.lse,
+        };
+        let lsi = self.lsi || self.rtc_clock == Some(RtcClockSource::Lsi);
+
+        if lse.is_some() || self.rtc_clock.is_some() {
+            // Enable write access to the backup domain
+            PWR::enable(rcc);
+            unsafe { &*PWR::ptr() }.cr.modify(|_, w| w.dbp().set_bit());
+        }
+
+        if self.rtc_clock_differs(rcc) {
+            // The RTC clock source can only be changed after a backup domain reset, allowed
+            // with `reset_backup_domain`
+            rcc.bdcr.modify(|_, w| w.bdrst().set_bit());
+            rcc.bdcr.modify(|_, w| w.bdrst().clear_bit());
+        }
+
+        if let Some(mode) = lse {
+            if rcc.bdcr.read().lserdy().bit_is_clear() {
+                // LSEBYP and LSEMOD can only be written while the LSE is disabled
+                rcc.bdcr.modify(|_, w| {
+                    #[cfg(any(
+                        feature = "stm32f410",
+                        feature = "stm32f411",
+                        feature = "stm32f412",
+                        feature = "stm32f413",
+                        feature = "stm32f423",
+                        feature = "stm32f446"
+                    ))]
+                    w.lsemod().bit(self.lse_drive == LseDrive::HighDrive);
+                    w.lsebyp().bit(mode == LSEClockMode::Bypass)
+                });
+                rcc.bdcr.modify(|_, w| w.lseon().set_bit());
+                while rcc.bdcr.read().lserdy().bit_is_clear() {}
+            }
+        }
+
+        if lsi {
+            rcc.csr.modify(|_, w| w.lsion().set_bit());
+            while rcc.csr.read().lsirdy().bit_is_clear() {}
+        }
+
+        let rtcclk = self.rtc_clock.map(|source| match source {
+            RtcClockSource::Lse => {
+                rcc.bdcr.modify(|_, w| w.rtcsel().lse());
+                LSE
+            }
+            RtcClockSource::Lsi => {
+                rcc.bdcr.modify(|_, w| w.rtcsel().lsi());
+                LSI
+            }
+            RtcClockSource::Hse => {
+                // Checked by `freeze_internal`
+                let hse = self.hse.unwrap();
+                // The RTC input must be 1 MHz or less
+                let rtcpre = ((hse + 999_999) / 1_000_000).clamp(2, 31);
+                #[allow(unused_unsafe)]
+                rcc.cfgr
+                    .modify(|_, w| unsafe { w.rtcpre().bits(rtcpre as u8) });
+                rcc.bdcr.modify(|_, w| w.rtcsel().hse());
+                hse / rtcpre
+            }
+        });
+        if rtcclk.is_some() {
+            rcc.bdcr.modify(|_, w| w.rtcen().set_bit());
+        }
+
+        (lse.map(|_| LSE), if lsi { Some(LSI) } else { None }, rtcclk)
+    }
+
     /// Initialises the hardware according to CFGR state returning a Clocks instance.
     /// Panics if overclocking is attempted.
     pub fn freeze(self) -> Clocks {
+        self.freeze_internal(false).unwrap()
+    }
+
+    /// Initialises the hardware according to CFGR state returning a Clocks instance.
+    ///
+    /// Returns an error instead of panicking if the requested clocks can't be generated within
+    /// the limits of the MCU. All limits are checked before the first register is written, so
+    /// the clock configuration is not changed in that case.
+    pub fn try_freeze(self) -> Result<Clocks, Error> {
         self.freeze_internal(false)
     }
 
@@ -956,10 +1605,10 @@ impl CFGR {
     /// This method does not check if the clocks are bigger or smaller than the officially
     /// recommended.
     pub unsafe fn freeze_unchecked(self) -> Clocks {
-        self.freeze_internal(true)
+        self.freeze_internal(true).unwrap()
     }
 
-    fn freeze_internal(self, unchecked: bool) -> Clocks {
+    fn freeze_internal(self, unchecked: bool) -> Result<Clocks, Error> {
         let rcc = unsafe { &*RCC::ptr() };
 
         //let (use_pll, sysclk_on_pll, sysclk, pll48clk) = self.pll_setup();
@@ -967,14 +1616,40 @@ impl CFGR {
         let sysclk = self.sysclk.unwrap_or(pllsrcclk);
         let sysclk_on_pll = sysclk != pllsrcclk;
 
-        let plls = self.pll_setup(pllsrcclk, if sysclk_on_pll { Some(sysclk) } else { None });
+        #[cfg(not(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479")))]
+        let pllr_on = false;
+        #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+        let pllr_on = self.pllr_clk.is_some();
+
+        // The PLL input must be divided down to 1 - 2 MHz
+        if (sysclk_on_pll || self.pll48clk || pllr_on)
+            && (pllsrcclk + 1_999_999) / 2_000_000 > pllsrcclk / 1_000_000
+        {
+            return Err(Error::PllInput);
+        }
+
+        if self.rtc_clock == Some(RtcClockSource::Hse) && self.hse.is_none() {
+            return Err(Error::Hse);
+        }
+
+        if !self.reset_backup_domain && self.rtc_clock_differs(rcc) {
+            return Err(Error::RtcClock);
+        }
+
+        // Only calculates the PLL configurations, they are written after all checks
+        let plls = self.pll_setup(pllsrcclk, if sysclk_on_pll { Some(sysclk) } else { None })?;
         let sysclk = if sysclk_on_pll {
             plls.pllsysclk.unwrap()
         } else {
             sysclk
         };
 
-        assert!(unchecked || !sysclk_on_pll || (SYSCLK_MIN..=SYSCLK_MAX).contains(&sysclk));
+        if !unchecked && sysclk_on_pll && !(SYSCLK_MIN..=SYSCLK_MAX).contains(&sysclk) {
+            return Err(Error::Sysclk);
+        }
+        if self.pll48clk && !Clocks::is_valid_48mhz(plls.pll48clk) {
+            return Err(Error::Pll48clk);
+        }
 
         let hclk = self.hclk.unwrap_or(sysclk);
         let (hpre_bits, hpre_div) = match (sysclk + hclk - 1) / hclk {
@@ -1008,7 +1683,9 @@ impl CFGR {
         // Calculate real APB1 clock
         let pclk1 = hclk / u32::from(ppre1);
 
-        assert!(unchecked || pclk1 <= PCLK1_MAX);
+        if !unchecked && pclk1 > PCLK1_MAX {
+            return Err(Error::Pclk1);
+        }
 
         let pclk2 = self
             .pclk2
@@ -1025,22 +1702,53 @@ impl CFGR {
         // Calculate real APB2 clock
         let pclk2 = hclk / u32::from(ppre2);
 
-        assert!(unchecked || pclk2 <= PCLK2_MAX);
+        if !unchecked && pclk2 > PCLK2_MAX {
+            return Err(Error::Pclk2);
+        }
+
+        let sscgr = match self.spread_spectrum {
+            Some((mode, modulation, depth_permille)) if plls.main_pll.use_pll => {
+                let (modper, incstep) = Self::spread_spectrum_config(
+                    pllsrcclk,
+                    &plls.main_pll,
+                    modulation,
+                    depth_permille,
+                )
+                .ok_or(Error::SpreadSpectrum)?;
+                Some((mode, modper, incstep))
+            }
+            _ => None,
+        };
+
+        // All checks passed, from here on the registers are written
+        plls.apply();
 
         Self::flash_setup(sysclk);
 
         if self.hse.is_some() {
+            // HSEBYP can only be written while the HSE is disabled, so it is set before HSEON
+            rcc.cr.modify(|_, w| w.hsebyp().bit(self.hse_bypass));
             // enable HSE and wait for it to be ready
-            rcc.cr.modify(|_, w| {
-                if self.hse_bypass {
-                    w.hsebyp().bypassed();
-                }
-                w.hseon().set_bit()
-            });
+            rcc.cr.modify(|_, w| w.hseon().set_bit());
             while rcc.cr.read().hserdy().bit_is_clear() {}
+
+            if self.css {
+                rcc.cr.modify(|_, w| w.csson().set_bit());
+            }
         }
 
-        if plls.use_pll {
+        Self::voltage_scaling_setup(rcc, hclk);
+
+        if plls.main_pll.use_pll {
+            if let Some((mode, modper, incstep)) = sscgr {
+                Self::spread_spectrum_setup(rcc, mode, modper, incstep);
+            } else {
+                // Spread spectrum may be enabled by a previous configuration
+                rcc.sscgr.reset();
+            }
+        }
+
+        if plls.main_pll.use_pll {
             // Enable PLL
             rcc.cr.modify(|_, w| w.pllon().set_bit());
 
@@ -1073,7 +1781,7 @@ impl CFGR {
         }
 
         #[cfg(not(feature = "stm32f410"))]
-        if plls.use_i2spll {
+        if plls.i2s_pll.use_pll {
             // Enable PLL.
             rcc.cr.modify(|_, w| w.plli2son().set_bit());
 
@@ -1090,7 +1798,7 @@ impl CFGR {
             feature = "stm32f469",
             feature = "stm32f479",
         ))]
-        if plls.use_saipll {
+        if plls.sai_pll.use_pll {
             // Enable PLL.
             rcc.cr.modify(|_, w| w.pllsaion().set_bit());
 
@@ -1112,6 +1820,8 @@ impl CFGR {
             feature = "stm32f479",
         ))]
         plls.sai.config_clocksel();
+        #[cfg(any(feature = "stm32f469", feature = "stm32f479"))]
+        rcc.dckcfgr.modify(|_, w| w.dsisel().bit(self.dsi_pllr));
 
         // Set scaling factors
         rcc.cfgr.modify(|_, w| unsafe {
@@ -1129,6 +1839,12 @@ impl CFGR {
 
         // Select system clock source
         rcc.cfgr.modify(|_, w| {
+            // Switch directly to the R output, the P output is not configured then.
+            // The PAC lacks the PLLR variant.
+            #[cfg(feature = "stm32f446")]
+            if sysclk_on_pll && self.sysclk_pllr {
+                return unsafe { w.sw().bits(0b11) };
+            }
             w.sw().variant(if sysclk_on_pll {
                 SW_A::Pll
             } else if self.hse.is_some() {
@@ -1138,6 +1854,8 @@ impl CFGR {
             })
         });
 
+        let (lse, lsi, rtcclk) = self.low_speed_setup(rcc);
+
         let clocks = Clocks {
             hclk: hclk.Hz(),
             pclk1: pclk1.Hz(),
@@ -1146,6 +1864,9 @@ impl CFGR {
             ppre2,
             sysclk: sysclk.Hz(),
             pll48clk: plls.pll48clk.map(Hertz::from_raw),
+            lse: lse.map(Hertz::from_raw),
+            lsi: lsi.map(Hertz::from_raw),
+            rtcclk: rtcclk.map(Hertz::from_raw),
 
             #[cfg(not(any(
                 feature = "stm32f412",
@@ -1195,22 +1916,25 @@ impl CFGR {
             sai1_clk: plls.sai.sai1_clk.map(Hertz::from_raw),
             #[cfg(feature = "stm32f446")]
             sai2_clk: plls.sai.sai2_clk.map(Hertz::from_raw),
+            #[cfg(any(
+                feature = "stm32f429",
+                feature = "stm32f439",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            lcd_clk: plls.lcd_clk.map(Hertz::from_raw),
+            #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+            pllr_clk: plls.pllr_clk.map(Hertz::from_raw),
         };
 
-        if self.pll48clk {
-            assert!(clocks.is_pll48clk_valid());
-        }
-
-        clocks
+        Ok(clocks)
     }
 }
 
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 struct PllSetup {
-    use_pll: bool,
+    main_pll: MainPll,
     #[cfg(not(feature = "stm32f410"))]
-    use_i2spll: bool,
+    i2s_pll: I2sPll,
     #[cfg(any(
         feature = "stm32f427",
         feature = "stm32f429",
@@ -1220,7 +1944,10 @@ struct PllSetup {
         feature = "stm32f469",
         feature = "stm32f479",
     ))]
-    use_saipll: bool,
+    sai_pll: SaiPll,
+    /// I2S PLL divider for the SAI clock
+    #[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+    plli2sdivr: Option<u8>,
 
     pllsysclk: Option<u32>,
     pll48clk: Option<u32>,
@@ -1239,6 +1966,41 @@ struct PllSetup {
         feature = "stm32f479",
     ))]
     sai: RealSaiClocks,
+
+    #[cfg(any(
+        feature = "stm32f429",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    lcd_clk: Option<u32>,
+    #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+    pllr_clk: Option<u32>,
+}
+
+impl PllSetup {
+    /// Writes the PLL configurations, the PLLs must be disabled.
+    fn apply(&self) {
+        self.main_pll.apply();
+        #[cfg(not(feature = "stm32f410"))]
+        self.i2s_pll.apply();
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f446",
+            feature = "stm32f469",
+            feature = "stm32f479",
+        ))]
+        self.sai_pll.apply();
+        #[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+        if let Some(div) = self.plli2sdivr {
+            unsafe { &*RCC::ptr() }
+                .dckcfgr
+                .modify(|_, w| w.plli2sdivr().bits(div));
+        }
+    }
 }
 
 #[cfg(any(
@@ -1506,6 +2268,9 @@ pub struct Clocks {
     ppre2: u8,
     sysclk: Hertz,
     pll48clk: Option<Hertz>,
+    lse: Option<Hertz>,
+    lsi: Option<Hertz>,
+    rtcclk: Option<Hertz>,
 
     #[cfg(not(any(
         feature = "stm32f412",
@@ -1555,9 +2320,88 @@ pub struct Clocks {
     sai1_clk: Option<Hertz>,
     #[cfg(feature = "stm32f446")]
     sai2_clk: Option<Hertz>,
+    #[cfg(any(
+        feature = "stm32f429",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    lcd_clk: Option<Hertz>,
+    #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+    pllr_clk: Option<Hertz>,
 }
 
 impl Clocks {
+    /// Stops the clock configuration and returns a new `CFGR` to freeze again
+    ///
+    /// The system clock is switched to the HSI and the clock security system, the HSE and all
+    /// PLLs are stopped, so that the PLLs and flash wait states can be changed safely, e.g. to
+    /// lower the system clock of battery powered devices at runtime and speed up on demand:
+    ///
+    /// ```ignore
+    /// let clocks = clocks.unfreeze().use_hse(8.MHz()).sysclk(16.MHz()).freeze();
+    /// ```
+    ///
+    /// Peripherals and delays configured with the old `Clocks` keep their dividers, so baud
+    /// rates and timer frequencies change until they are configured again with the new `Clocks`.
+    /// While reconfiguring, peripherals clocked by the I2S and SAI PLLs are stopped.
+    pub fn unfreeze(self) -> CFGR {
+        let rcc = unsafe { &*RCC::ptr() };
+
+        // Run from the HSI while the other clocks are stopped
+        rcc.cr.modify(|_, w| w.hsion().set_bit());
+        while rcc.cr.read().hsirdy().bit_is_clear() {}
+        rcc.cfgr.modify(|_, w| w.sw().hsi());
+        while !rcc.cfgr.read().sws().is_hsi() {}
+
+        // Over-drive can only be disabled while running from the HSI or HSE
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f446",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        {
+            PWR::enable(rcc);
+            unsafe { &*PWR::ptr() }
+                .cr
+                .modify(|_, w| w.oden().clear_bit().odswen().clear_bit());
+        }
+
+        // The PLL configuration can only be changed while the PLLs are disabled
+        rcc.cr.modify(|_, w| w.pllon().clear_bit());
+        while rcc.cr.read().pllrdy().bit_is_set() {}
+        #[cfg(not(feature = "stm32f410"))]
+        {
+            rcc.cr.modify(|_, w| w.plli2son().clear_bit());
+            while rcc.cr.read().plli2srdy().bit_is_set() {}
+        }
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f446",
+            feature = "stm32f469",
+            feature = "stm32f479",
+        ))]
+        {
+            rcc.cr.modify(|_, w| w.pllsaion().clear_bit());
+            while rcc.cr.read().pllsairdy().bit_is_set() {}
+        }
+
+        // HSEBYP can only be changed while the HSE is disabled, the CSS would report the
+        // stopped HSE as a failure
+        rcc.cr.modify(|_, w| w.csson().clear_bit());
+        rcc.cr.modify(|_, w| w.hseon().clear_bit());
+        while rcc.cr.read().hserdy().bit_is_set() {}
+
+        CFGR::new()
+    }
+
     /// Returns the frequency of the AHB1
     pub fn hclk(&self) -> Hertz {
         self.hclk
@@ -1573,6 +2417,22 @@ impl Clocks {
         self.pclk2
     }
 
+    /// Returns the kernel clock of the timers on APB1
+    ///
+    /// When the APB1 prescaler is not 1 the timers run at twice the `PCLK1` frequency.
+    pub fn timclk1(&self) -> Hertz {
+        let pclk_mul = if self.ppre1 == 1 { 1 } else { 2 };
+        Hertz::from_raw(self.pclk1.raw() * pclk_mul)
+    }
+
+    /// Returns the kernel clock of the timers on APB2
+    ///
+    /// When the APB2 prescaler is not 1 the timers run at twice the `PCLK2` frequency.
+    pub fn timclk2(&self) -> Hertz {
+        let pclk_mul = if self.ppre2 == 1 { 1 } else { 2 };
+        Hertz::from_raw(self.pclk2.raw() * pclk_mul)
+    }
+
     /// Returns the prescaler of the APB1
     pub fn ppre1(&self) -> u8 {
         self.ppre1
@@ -1593,12 +2453,33 @@ impl Clocks {
         self.pll48clk
     }
 
+    /// Returns the frequency of the LSE if enabled
+    pub fn lse(&self) -> Option<Hertz> {
+        self.lse
+    }
+
+    /// Returns the nominal frequency of the LSI if enabled
+    ///
+    /// The LSI also clocks the independent watchdog.
+    pub fn lsi(&self) -> Option<Hertz> {
+        self.lsi
+    }
+
+    /// Returns the frequency of the RTC clock if selected with `rtc_clock`
+    pub fn rtcclk(&self) -> Option<Hertz> {
+        self.rtcclk
+    }
+
     /// Returns true if the PLL48 clock is within USB
     /// specifications. It is required to use the USB functionality.
     pub fn is_pll48clk_valid(&self) -> bool {
+        Self::is_valid_48mhz(self.pll48clk.map(|freq| freq.raw()))
+    }
+
+    fn is_valid_48mhz(pll48clk: Option<u32>) -> bool {
         // USB specification allows +-0.25%
-        self.pll48clk
-            .map(|freq| (48_000_000 - freq.raw() as i32).abs() <= 120_000)
+        pll48clk
+            .map(|freq| (48_000_000 - freq as i32).abs() <= 120_000)
             .unwrap_or(false)
     }
 
@@ -1671,4 +2552,23 @@ impl Clocks {
     pub fn sai2_clk(&self) -> Option<Hertz> {
         self.sai2_clk
     }
+
+    /// Returns the frequency of the LCD-TFT clock.
+    #[cfg(any(
+        feature = "stm32f429",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    pub fn lcd_clk(&self) -> Option<Hertz> {
+        self.lcd_clk
+    }
+
+    /// Returns the frequency of the R output of the main PLL.
+    ///
+    /// The DSI byte lane clock if it is selected with `dsi_clk_from_pllr`.
+    #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+    pub fn pllr_clk(&self) -> Option<Hertz> {
+        self.pllr_clk
+    }
 }
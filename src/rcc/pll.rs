@@ -1,37 +1,63 @@
 use crate::pac::RCC;
 
+/// Main PLL configuration, the registers are only written by [`apply`](Self::apply)
 pub struct MainPll {
     pub use_pll: bool,
     pub pllsysclk: Option<u32>,
     pub pll48clk: Option<u32>,
     /// "M" divisor, required for the other PLLs on some MCUs.
     pub m: Option<u32>,
+    /// "N" multiplier, valid if "M" is set.
+    pub n: u32,
     /// "R" output, required for I2S on STM32F410.
     pub plli2sclk: Option<u32>,
+    use_hse: bool,
+    /// Register values of the "P", "Q" and "R" dividers.
+    p: Option<u8>,
+    q: Option<u8>,
+    #[cfg(any(
+        feature = "stm32f410",
+        feature = "stm32f446",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    r: Option<u8>,
 }
 
 impl MainPll {
+    fn unused(use_hse: bool) -> MainPll {
+        MainPll {
+            use_pll: false,
+            pllsysclk: None,
+            pll48clk: None,
+            m: None,
+            n: 0,
+            plli2sclk: None,
+            use_hse,
+            p: None,
+            q: None,
+            #[cfg(any(
+                feature = "stm32f410",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            r: None,
+        }
+    }
+
+    /// Returns `None` if the PLL can't generate `pllsysclk`.
     pub fn fast_setup(
         pllsrcclk: u32,
         use_hse: bool,
         pllsysclk: Option<u32>,
         pll48clk: bool,
-    ) -> MainPll {
+    ) -> Option<MainPll> {
         let sysclk = pllsysclk.unwrap_or(pllsrcclk);
         if pllsysclk.is_none() && !pll48clk {
             // Even if we do not use the main PLL, we still need to set the PLL source as that setting
             // applies to the I2S and SAI PLLs as well.
-            unsafe { &*RCC::ptr() }
-                .pllcfgr
-                .write(|w| w.pllsrc().bit(use_hse));
-
-            return MainPll {
-                use_pll: false,
-                pllsysclk: None,
-                pll48clk: None,
-                m: None,
-                plli2sclk: None,
-            };
+            return Some(Self::unused(use_hse));
         }
         // Input divisor from PLL source clock, must result to frequency in
         // the range from 1 to 2 MHz
@@ -40,6 +66,9 @@ impl MainPll {
 
         // Sysclk output divisor must be one of 2, 4, 6 or 8
         let sysclk_div = core::cmp::min(8, (432_000_000 / sysclk) & !1);
+        if sysclk_div == 0 {
+            return None;
+        }
 
         let target_freq = if pll48clk {
             48_000_000
@@ -49,16 +78,16 @@ impl MainPll {
 
         // Find the lowest pllm value that minimize the difference between
         // target frequency and the real vco_out frequency.
-        let pllm = (pllm_min..=pllm_max)
-            .min_by_key(|pllm| {
-                let vco_in = pllsrcclk / pllm;
-                let plln = target_freq / vco_in;
-                target_freq - vco_in * plln
-            })
-            .unwrap();
+        let pllm = (pllm_min..=pllm_max).min_by_key(|pllm| {
+            let vco_in = pllsrcclk / pllm;
+            let plln = target_freq / vco_in;
+            target_freq - vco_in * plln
+        })?;
 
         let vco_in = pllsrcclk / pllm;
-        assert!((1_000_000..=2_000_000).contains(&vco_in));
+        if !(1_000_000..=2_000_000).contains(&vco_in) {
+            return None;
+        }
 
         // Main scaler, must result in >= 100MHz (>= 192MHz for F401)
         // and <= 432MHz, min 50, max 432
@@ -77,28 +106,88 @@ impl MainPll {
         } else {
             sysclk * sysclk_div / vco_in
         };
+        if !(100_000_000..=432_000_000).contains(&(vco_in * plln)) {
+            return None;
+        }
         let pllp = (sysclk_div / 2) - 1;
 
         let pllq = (vco_in * plln + 47_999_999) / 48_000_000;
         let real_pll48clk = vco_in * plln / pllq;
 
-        unsafe { &*RCC::ptr() }.pllcfgr.write(|w| unsafe {
-            w.pllm().bits(pllm as u8);
-            w.plln().bits(plln as u16);
-            w.pllp().bits(pllp as u8);
-            w.pllq().bits(pllq as u8);
-            w.pllsrc().bit(use_hse)
-        });
-
         let real_pllsysclk = vco_in * plln / sysclk_div;
 
-        MainPll {
+        Some(MainPll {
             use_pll: true,
             pllsysclk: Some(real_pllsysclk),
             pll48clk: if pll48clk { Some(real_pll48clk) } else { None },
             m: Some(pllm),
+            n: plln,
             plli2sclk: None,
-        }
+            use_hse,
+            p: Some(pllp as u8),
+            q: Some(pllq as u8),
+            #[cfg(any(
+                feature = "stm32f410",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            r: None,
+        })
+    }
+
+    /// Writes `PLLCFGR`, the PLL must be disabled.
+    pub fn apply(&self) {
+        unsafe { &*RCC::ptr() }.pllcfgr.write(|w| unsafe {
+            if let Some(m) = self.m {
+                w.pllm().bits(m as u8);
+                w.plln().bits(self.n as u16);
+            }
+            if let Some(p) = self.p {
+                w.pllp().bits(p);
+            }
+            if let Some(q) = self.q {
+                w.pllq().bits(q);
+            }
+            #[cfg(any(
+                feature = "stm32f410",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            if let Some(r) = self.r {
+                w.pllr().bits(r);
+            }
+            w.pllsrc().bit(self.use_hse)
+        });
+    }
+
+    /// Configures the "R" output to the frequency closest to `pllr_clk`.
+    ///
+    /// If the PLL is already used for SYSCLK or the 48 MHz clock, "M" and "N" are kept and
+    /// only the divider is selected.
+    /// Returns `None` if no configuration is found.
+    #[cfg(any(feature = "stm32f446", feature = "stm32f469", feature = "stm32f479"))]
+    pub fn setup_r(&mut self, pllsrcclk: u32, target: u32) -> Option<u32> {
+        let (pllr, real_pllr_clk) = if let Some(m) = self.m {
+            let vco_out = pllsrcclk / m * self.n;
+            (2..=7)
+                .map(|r| (r, vco_out / r))
+                .min_by_key(|(_, clk)| (*clk as i32 - target as i32).unsigned_abs())
+                .unwrap()
+        } else {
+            let pllm_min = (pllsrcclk + 1_999_999) / 2_000_000;
+            let pllm_max = pllsrcclk / 1_000_000;
+            let (config, real_pllr_clk, _) = (pllm_min..=pllm_max)
+                .filter_map(|m| SingleOutputPll::optimize(pllsrcclk, m, target, 2, 7))
+                .min_by_key(|(_, _, error)| *error)?;
+            self.use_pll = true;
+            self.m = Some(config.m as u32);
+            self.n = config.n as u32;
+            (config.outdiv as u32, real_pllr_clk)
+        };
+        self.r = Some(pllr as u8);
+        Some(real_pllr_clk)
     }
 
     #[cfg(feature = "stm32f410")]
@@ -108,7 +197,7 @@ impl MainPll {
         pllsysclk: Option<u32>,
         pll48clk: bool,
         plli2sclk: u32,
-    ) -> MainPll {
+    ) -> Option<MainPll> {
         use super::{SYSCLK_MAX, SYSCLK_MIN};
 
         // Input divisor from PLL source clock, must result to frequency in
@@ -165,32 +254,23 @@ impl MainPll {
                     })
                     .min_by_key(|(_, _, _, _, _, error)| *error)
             })
-            .min_by_key(|(_, _, _, _, _, error)| *error)
-            .expect("could not find a valid main PLL configuration");
-
-        unsafe { &*RCC::ptr() }.pllcfgr.write(|w| unsafe {
-            w.pllm().bits(pllm as u8);
-            w.plln().bits(plln as u16);
-            if let Some(pllp) = pllp {
-                w.pllp().bits(pllp as u8 / 2 - 1);
-            }
-            if let Some(pllq) = pllq {
-                w.pllq().bits(pllq as u8);
-            }
-            w.pllr().bits(pllr as u8);
-            w.pllsrc().bit(use_hse)
-        });
+            .min_by_key(|(_, _, _, _, _, error)| *error)?;
 
         let real_pllsysclk = pllp.map(|pllp| pllsrcclk / pllm * plln / pllp);
         let real_pll48clk = pllq.map(|pllq| pllsrcclk / pllm * plln / pllq);
 
-        MainPll {
+        Some(MainPll {
             use_pll: true,
             pllsysclk: real_pllsysclk,
             pll48clk: real_pll48clk,
             m: Some(pllm),
+            n: plln,
             plli2sclk: None,
-        }
+            use_hse,
+            p: pllp.map(|pllp| pllp as u8 / 2 - 1),
+            q: pllq.map(|pllq| pllq as u8),
+            r: Some(pllr as u8),
+        })
     }
 
     #[cfg(feature = "stm32f410")]
@@ -222,6 +302,7 @@ impl MainPll {
     }
 }
 
+/// I2S PLL configuration, the registers are only written by [`apply`](Self::apply)
 #[cfg(not(feature = "stm32f410"))]
 pub struct I2sPll {
     pub use_pll: bool,
@@ -229,6 +310,7 @@ pub struct I2sPll {
     pub m: Option<u32>,
     /// PLL I2S clock output.
     pub plli2sclk: Option<u32>,
+    config: Option<SingleOutputPll>,
 }
 
 #[cfg(not(feature = "stm32f410"))]
@@ -238,25 +320,25 @@ impl I2sPll {
             use_pll: false,
             m: None,
             plli2sclk: None,
+            config: None,
         }
     }
 
-    pub fn setup(pllsrcclk: u32, plli2sclk: Option<u32>) -> I2sPll {
+    /// Returns `None` if no configuration is found.
+    pub fn setup(pllsrcclk: u32, plli2sclk: Option<u32>) -> Option<I2sPll> {
         let target = if let Some(clk) = plli2sclk {
             clk
         } else {
-            return Self::unused();
+            return Some(Self::unused());
         };
         // Input divisor from PLL source clock, must result to frequency in
         // the range from 1 to 2 MHz
         let pllm_min = (pllsrcclk + 1_999_999) / 2_000_000;
         let pllm_max = pllsrcclk / 1_000_000;
-        let (pll, config, _) = (pllm_min..=pllm_max)
-            .map(|m| Self::optimize_fixed_m(pllsrcclk, m, target))
-            .min_by_key(|(_, _, error)| *error)
-            .expect("no suitable I2S PLL configuration found");
-        Self::apply_config(config);
-        pll
+        let (pll, _) = (pllm_min..=pllm_max)
+            .filter_map(|m| Self::optimize_fixed_m(pllsrcclk, m, target))
+            .min_by_key(|(_, error)| *error)?;
+        Some(pll)
     }
 
     #[cfg(any(
@@ -272,7 +354,11 @@ impl I2sPll {
         feature = "stm32f469",
         feature = "stm32f479"
     ))]
-    pub fn setup_shared_m(pllsrcclk: u32, m: Option<u32>, plli2sclk: Option<u32>) -> I2sPll {
+    pub fn setup_shared_m(
+        pllsrcclk: u32,
+        m: Option<u32>,
+        plli2sclk: Option<u32>,
+    ) -> Option<I2sPll> {
         // "m" is None if the main PLL is not in use.
         let m = if let Some(m) = m {
             m
@@ -282,26 +368,31 @@ impl I2sPll {
         let target = if let Some(clk) = plli2sclk {
             clk
         } else {
-            return Self::unused();
+            return Some(Self::unused());
         };
-        let (pll, config, _) = Self::optimize_fixed_m(pllsrcclk, m, target);
-        Self::apply_config(config);
-        pll
+        let (pll, _) = Self::optimize_fixed_m(pllsrcclk, m, target)?;
+        Some(pll)
     }
 
-    fn optimize_fixed_m(pllsrcclk: u32, m: u32, plli2sclk: u32) -> (I2sPll, SingleOutputPll, u32) {
+    fn optimize_fixed_m(pllsrcclk: u32, m: u32, plli2sclk: u32) -> Option<(I2sPll, u32)> {
         let (config, real_plli2sclk, error) =
-            SingleOutputPll::optimize(pllsrcclk, m, plli2sclk, 2, 7)
-                .expect("did not find any valid I2S PLL config");
-        (
+            SingleOutputPll::optimize(pllsrcclk, m, plli2sclk, 2, 7)?;
+        Some((
             I2sPll {
                 use_pll: true,
                 m: Some(config.m as u32),
                 plli2sclk: Some(real_plli2sclk),
+                config: Some(config),
             },
-            config,
             error,
-        )
+        ))
+    }
+
+    /// Writes the PLL configuration, the PLL must be disabled.
+    pub fn apply(&self) {
+        if let Some(config) = &self.config {
+            Self::apply_config(config);
+        }
     }
 
     #[cfg(not(any(
@@ -311,7 +402,7 @@ impl I2sPll {
         feature = "stm32f423",
         feature = "stm32f446",
     )))]
-    fn apply_config(config: SingleOutputPll) {
+    fn apply_config(config: &SingleOutputPll) {
         let rcc = unsafe { &*RCC::ptr() };
         // "M" may have been written before, but the value is identical.
         rcc.pllcfgr
@@ -326,7 +417,7 @@ impl I2sPll {
         feature = "stm32f423",
         feature = "stm32f446",
     ))]
-    fn apply_config(config: SingleOutputPll) {
+    fn apply_config(config: &SingleOutputPll) {
         let rcc = unsafe { &*RCC::ptr() };
         rcc.plli2scfgr.modify(|_, w| unsafe {
             w.plli2sm()
@@ -348,10 +439,23 @@ impl I2sPll {
     feature = "stm32f469",
     feature = "stm32f479",
 ))]
+/// SAI PLL configuration, the registers are only written by [`apply`](Self::apply)
 pub struct SaiPll {
     pub use_pll: bool,
     /// SAI clock (PLL output divided by the SAI clock divider).
     pub sai_clk: Option<u32>,
+    m: u8,
+    n: u16,
+    /// "Q" divider and SAI clock divider.
+    q: Option<(u8, u32)>,
+    /// "R" divider and LCD-TFT divider.
+    #[cfg(any(
+        feature = "stm32f429",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    r: Option<(u8, u32)>,
 }
 
 #[cfg(any(
@@ -368,25 +472,34 @@ impl SaiPll {
         SaiPll {
             use_pll: false,
             sai_clk: None,
+            m: 0,
+            n: 0,
+            q: None,
+            #[cfg(any(
+                feature = "stm32f429",
+                feature = "stm32f439",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            r: None,
         }
     }
 
-    pub fn setup(pllsrcclk: u32, sai_clk: Option<u32>) -> SaiPll {
+    /// Returns `None` if no configuration is found.
+    pub fn setup(pllsrcclk: u32, sai_clk: Option<u32>) -> Option<SaiPll> {
         let target = if let Some(clk) = sai_clk {
             clk
         } else {
-            return Self::unused();
+            return Some(Self::unused());
         };
         // Input divisor from PLL source clock, must result to frequency in
         // the range from 1 to 2 MHz
         let pllm_min = (pllsrcclk + 1_999_999) / 2_000_000;
         let pllm_max = pllsrcclk / 1_000_000;
-        let (pll, config, saidiv, _) = (pllm_min..=pllm_max)
-            .map(|m| Self::optimize_fixed_m(pllsrcclk, m, target))
-            .min_by_key(|(_, _, _, error)| *error)
-            .expect("no suitable SAI PLL configuration found");
-        Self::apply_config(config, saidiv);
-        pll
+        let (pll, _) = (pllm_min..=pllm_max)
+            .filter_map(|m| Self::optimize_fixed_m(pllsrcclk, m, target))
+            .min_by_key(|(_, error)| *error)?;
+        Some(pll)
     }
 
     #[cfg(any(
@@ -397,7 +510,7 @@ impl SaiPll {
         feature = "stm32f469",
         feature = "stm32f479"
     ))]
-    pub fn setup_shared_m(pllsrcclk: u32, m: Option<u32>, sai_clk: Option<u32>) -> SaiPll {
+    pub fn setup_shared_m(pllsrcclk: u32, m: Option<u32>, sai_clk: Option<u32>) -> Option<SaiPll> {
         // "m" is None if both other PLLs are not in use.
         let m = if let Some(m) = m {
             m
@@ -407,18 +520,13 @@ impl SaiPll {
         let target = if let Some(clk) = sai_clk {
             clk
         } else {
-            return Self::unused();
+            return Some(Self::unused());
         };
-        let (pll, config, saidiv, _) = Self::optimize_fixed_m(pllsrcclk, m, target);
-        Self::apply_config(config, saidiv);
-        pll
+        let (pll, _) = Self::optimize_fixed_m(pllsrcclk, m, target)?;
+        Some(pll)
     }
 
-    fn optimize_fixed_m(
-        pllsrcclk: u32,
-        m: u32,
-        sai_clk: u32,
-    ) -> (SaiPll, SingleOutputPll, u32, u32) {
+    fn optimize_fixed_m(pllsrcclk: u32, m: u32, sai_clk: u32) -> Option<(SaiPll, u32)> {
         // NOTE: This code tests lots of configurations due to the nested loops for the two
         // dividers. A smarter approach can probably speed up the search.
         let (config, saidiv, real_sai_clk, error) = (1..=32)
@@ -428,43 +536,103 @@ impl SaiPll {
                     SingleOutputPll::optimize(pllsrcclk, m, target, 2, 15)?;
                 Some((config, saidiv, real_sai_clk, error))
             })
-            .min_by_key(|(_, _, _, error)| *error)
-            .expect("no suitable I2S PLL configuration found");
-        (
+            .min_by_key(|(_, _, _, error)| *error)?;
+        Some((
             SaiPll {
                 use_pll: true,
                 sai_clk: Some(real_sai_clk),
+                m: config.m,
+                n: config.n,
+                q: Some((config.outdiv, saidiv)),
+                #[cfg(any(
+                    feature = "stm32f429",
+                    feature = "stm32f439",
+                    feature = "stm32f469",
+                    feature = "stm32f479"
+                ))]
+                r: None,
             },
-            config,
-            saidiv,
             error,
-        )
+        ))
     }
 
-    #[cfg(not(feature = "stm32f446"))]
-    fn apply_config(config: SingleOutputPll, saidiv: u32) {
-        let rcc = unsafe { &*RCC::ptr() };
-        rcc.dckcfgr
-            .modify(|_, w| w.pllsaidivq().bits(saidiv as u8 - 1));
-        // "M" may have been written before, but the value is identical.
-        rcc.pllcfgr
-            .modify(|_, w| unsafe { w.pllm().bits(config.m) });
-        rcc.pllsaicfgr
-            .modify(|_, w| unsafe { w.pllsain().bits(config.n).pllsaiq().bits(config.outdiv) });
+    /// Configures the R output and the LCD-TFT divider for the LCD-TFT clock.
+    ///
+    /// If the PLL is already used for SAI, "M" and "N" are kept and only the dividers are
+    /// selected. Returns `None` if no configuration is found.
+    #[cfg(any(
+        feature = "stm32f429",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    pub fn setup_lcd(&mut self, pllsrcclk: u32, m: Option<u32>, target: u32) -> Option<u32> {
+        let error = |clk: u32| (clk as i32 - target as i32).unsigned_abs();
+        let (pllsair, lcddiv, real_lcd_clk) = if self.use_pll {
+            let vco_out = pllsrcclk / self.m as u32 * self.n as u32;
+            (2..=7)
+                .flat_map(|r| [2, 4, 8, 16].map(|div| (r, div, vco_out / r / div)))
+                .min_by_key(|(_, _, clk)| error(*clk))
+                .unwrap()
+        } else {
+            // "m" is None if both other PLLs are not in use.
+            let (pllm_min, pllm_max) = if let Some(m) = m {
+                (m, m)
+            } else {
+                ((pllsrcclk + 1_999_999) / 2_000_000, pllsrcclk / 1_000_000)
+            };
+            let (config, lcddiv, real_lcd_clk) = (pllm_min..=pllm_max)
+                .flat_map(|m| {
+                    [2, 4, 8, 16].into_iter().filter_map(move |div| {
+                        let (config, clk, _) =
+                            SingleOutputPll::optimize(pllsrcclk, m, target * div, 2, 7)?;
+                        Some((config, div, clk / div))
+                    })
+                })
+                .min_by_key(|(_, _, clk)| error(*clk))?;
+            self.use_pll = true;
+            self.m = config.m;
+            self.n = config.n;
+            (config.outdiv as u32, lcddiv, real_lcd_clk)
+        };
+        self.r = Some((pllsair as u8, lcddiv));
+        Some(real_lcd_clk)
     }
-    #[cfg(feature = "stm32f446")]
-    fn apply_config(config: SingleOutputPll, saidiv: u32) {
+
+    /// Writes the PLL configuration, the PLL must be disabled.
+    pub fn apply(&self) {
+        if !self.use_pll {
+            return;
+        }
         let rcc = unsafe { &*RCC::ptr() };
-        rcc.dckcfgr
-            .modify(|_, w| w.pllsaidivq().bits(saidiv as u8 - 1));
+        if let Some((_, saidiv)) = self.q {
+            rcc.dckcfgr
+                .modify(|_, w| w.pllsaidivq().bits(saidiv as u8 - 1));
+        }
+        // "M" may have been written before, but the value is identical.
+        #[cfg(not(feature = "stm32f446"))]
+        rcc.pllcfgr.modify(|_, w| unsafe { w.pllm().bits(self.m) });
         rcc.pllsaicfgr.modify(|_, w| unsafe {
-            w.pllsaim()
-                .bits(config.m)
-                .pllsain()
-                .bits(config.n)
-                .pllsaiq()
-                .bits(config.outdiv)
+            #[cfg(feature = "stm32f446")]
+            w.pllsaim().bits(self.m);
+            if let Some((q, _)) = self.q {
+                w.pllsaiq().bits(q);
+            }
+            w.pllsain().bits(self.n)
         });
+        #[cfg(any(
+            feature = "stm32f429",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        if let Some((pllsair, lcddiv)) = self.r {
+            rcc.pllsaicfgr
+                .modify(|_, w| unsafe { w.pllsair().bits(pllsair) });
+            // The LCD-TFT divider is encoded as log2(div) - 1.
+            rcc.dckcfgr
+                .modify(|_, w| w.pllsaidivr().bits(lcddiv.trailing_zeros() as u8 - 1));
+        }
     }
 }
 
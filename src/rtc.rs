@@ -43,16 +43,7 @@ impl fmt::Debug for Rtc {
     }
 }
 
-/// LSE clock mode.
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum LSEClockMode {
-    /// Enable LSE oscillator to use external crystal or ceramic resonator.
-    Oscillator,
-    /// Bypass LSE oscillator to use external clock source.
-    /// Use this if an external oscillator is used which is not connected to `OSC32_IN` such as a MEMS resonator.
-    Bypass,
-}
+pub use crate::rcc::LSEClockMode;
 
 impl Rtc<Lse> {
     /// Create and enable a new RTC with external crystal or ceramic resonator and default prescalers.
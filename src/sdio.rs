@@ -222,7 +222,16 @@ pub struct Emmc {
 
 impl<P: SdioPeripheral> Sdio<P> {
     /// Create and enable the Sdio device
+    ///
+    /// # Panics
+    ///
+    /// If the PLL48 clock, which clocks the SDIO, is not enabled with
+    /// [`require_pll48clk`](crate::rcc::CFGR::require_pll48clk).
     pub fn new<PINS: Pins>(sdio: SDIO, _pins: PINS, clocks: &Clocks) -> Self {
+        assert!(
+            clocks.pll48clk().is_some(),
+            "SDIO requires the 48 MHz clock"
+        );
         unsafe {
             //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
             let rcc = &*RCC::ptr();
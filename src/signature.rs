@@ -83,6 +83,11 @@ impl VrefCal {
     pub fn read(&self) -> u16 {
         self.0
     }
+
+    /// Calculates VDDA in millivolts from a 12 bit sample of the internal VREF channel
+    pub fn vdda_millivolts(&self, vref_sample: u16) -> u32 {
+        VDDA_CALIB * u32::from(self.read()) / u32::from(vref_sample)
+    }
 }
 
 /// A temperature reading taken at 30°C stored at the factory
@@ -110,3 +115,15 @@ impl VtempCal110 {
         self.0
     }
 }
+
+/// Converts a 12 bit sample of the temperature sensor to degrees Celsius
+///
+/// The calibration values are taken at [`VDDA_CALIB`], the sample is corrected for `vdda`
+/// in millivolts (see [`VrefCal::vdda_millivolts`]) before the interpolation between
+/// [`VtempCal30`] and [`VtempCal110`].
+pub fn temperature_celsius(sample: u16, vdda: u32) -> f32 {
+    let cal30 = f32::from(VtempCal30::get().read());
+    let cal110 = f32::from(VtempCal110::get().read());
+    let sample = f32::from(sample) * vdda as f32 / VDDA_CALIB as f32;
+    (110.0 - 30.0) * (sample - cal30) / (cal110 - cal30) + 30.0
+}
@@ -1,3 +1,150 @@
+//! Serial Peripheral Interface
+//!
+//! [`SpiExt`] configures the peripheral as master or slave, with full duplex or 1-line
+//! bidirectional transfers and 8 or 16 bit data frames. Blocking and non-blocking
+//! transfers go through the embedded-hal traits.
+//!
+//! For embedded-hal 1.0 the SPI implements `SpiBus`, which is shared by several devices
+//! with a `SpiDevice` implementation that also manages the chip select, e.g. from
+//! `embedded-hal-bus`. `flush` waits until the last frame has left the shift register,
+//! so the chip select can be released afterwards.
+//!
+//! With the `async` feature the full duplex SPI also implements the async `SpiBus` of
+//! `embedded-hal-async`. The task waits for the TXE and RXNE interrupts between frames,
+//! `on_interrupt` must be called from the SPI interrupt handler to wake it:
+//!
+//! ```ignore
+//! #[interrupt]
+//! fn SPI1() {
+//!     stm32f4xx_hal::spi::on_interrupt::<pac::SPI1>();
+//! }
+//! ```
+//!
+//! # 16 bit frames
+//!
+//! [`frame_size_16bit`](Spi::frame_size_16bit) sends each `u16` word as one frame, MSB
+//! first by default. This halves the number of register accesses and DMA transfers for
+//! 16 bit converters or RGB565 displays compared to two 8 bit frames.
+//!
+//! ```ignore
+//! let mut spi = dp.SPI1.spi((sck, NoMiso, mosi), mode, 20.MHz(), &clocks).frame_size_16bit();
+//! spi.write(&[0xf800, 0x07e0, 0x001f])?;
+//! ```
+//!
+//! # TI frame format
+//!
+//! [`frame_format`](Spi::frame_format) selects the TI synchronous serial frame format
+//! used by TI DSPs and some converters, the NSS pulse is generated by the master.
+//!
+//! ```ignore
+//! let _nss = gpioa.pa4.into_alternate::<5>();
+//! let mut spi = dp.SPI1.spi((sck, miso, mosi), mode, 4.MHz(), &clocks).frame_size_16bit();
+//! spi.frame_format(FrameFormat::Ti);
+//! ```
+//!
+//! # Half duplex and receive only
+//!
+//! In bidirectional mode ([`spi_bidi`](SpiExt::spi_bidi)) MOSI of a master or MISO of a
+//! slave is the only data line, e.g. for 3-wire sensors. The direction is switched with
+//! [`bidi_output`](Spi::bidi_output) and [`bidi_input`](Spi::bidi_input) or by the
+//! embedded-hal read and write methods. In full duplex mode
+//! [`set_receive_only`](Spi::set_receive_only) disables the output line.
+//!
+//! ```ignore
+//! let mut spi = dp.SPI1.spi_bidi((sck, NoMiso, mosi), mode, 1.MHz(), &clocks);
+//! // register address with read bit
+//! spi.write(&[0x80 | 0x0f])?;
+//! // the clock runs until the direction is switched back
+//! spi.bidi_input();
+//! let id = nb::block!(spi.read_nonblocking())?;
+//! spi.bidi_output();
+//! ```
+//!
+//! # Hardware NSS
+//!
+//! A master created by [`spi_with_nss`](SpiExt::spi_with_nss) drives the NSS pin low while
+//! the SPI is enabled, instead of a chip select managed as a GPIO.
+//!
+//! ```ignore
+//! let mut spi = dp.SPI1.spi_with_nss((sck, miso, mosi, gpioa.pa4), mode, 1.MHz(), &clocks);
+//! spi.write(&[0x9f, 0, 0, 0])?;
+//! while spi.is_busy() {}
+//! spi.enable(false);
+//! ```
+//!
+//! # Slave
+//!
+//! A slave created by [`spi_slave`](SpiExt::spi_slave) transfers when the master clocks,
+//! the blocking and non-blocking embedded-hal traits work the same as for a master.
+//! Data written is sent during the next frame, so it must be written before the master
+//! starts clocking, e.g. from the [`Event::Txe`] interrupt. The slave is always selected
+//! by default (software NSS), [`spi_slave_with_nss`](SpiExt::spi_slave_with_nss) takes
+//! the NSS pin to be selected by the master, [`set_hardware_nss`](Spi::set_hardware_nss)
+//! does the same with a pin configured separately.
+//!
+//! ```ignore
+//! let mut spi = dp.SPI1.spi_slave_with_nss((sck, miso, mosi, gpioa.pa4), mode, 1.MHz(), &clocks);
+//! spi.listen(Event::Rxne);
+//!
+//! // in the SPI1 interrupt handler
+//! if let Ok(byte) = spi.read_nonblocking() {
+//!     spi.write_nonblocking(reply(byte)).ok();
+//! }
+//! ```
+//!
+//! ## Slave with DMA
+//!
+//! A slave streams data to a host with DMA, in circular mode the buffers are sent and
+//! received repeatedly while the host clocks. The end of a frame is signalled by the
+//! rising edge of NSS, an EXTI interrupt of the NSS pin tells how many frames were
+//! received from the remaining number of transfers of the rx stream.
+//!
+//! ```ignore
+//! let mut spi = dp.SPI1.spi_slave_with_nss((sck, miso, mosi, gpioa.pa4), mode, 1.MHz(), &clocks);
+//! let nss = spi.nss_mut();
+//! nss.make_interrupt_source(&mut syscfg);
+//! nss.trigger_on_edge(&mut dp.EXTI, Edge::Rising);
+//! nss.enable_interrupt(&mut dp.EXTI);
+//!
+//! let (tx, rx) = spi.use_dma().txrx();
+//! let config = DmaConfig::default().memory_increment(true).circular_buffer(true);
+//! let mut rx = Transfer::init_peripheral_to_memory(streams.2, rx, rx_buf, None, config);
+//! let mut tx = Transfer::init_memory_to_peripheral(streams.3, tx, tx_buf, None, config);
+//! rx.start(|_| {});
+//! tx.start(|_| {});
+//!
+//! // in the EXTI4 interrupt handler
+//! exti.clear(Line::gpio(4).unwrap());
+//! let end = RX_LEN - Stream2::<pac::DMA2>::get_number_of_transfers() as usize;
+//! ```
+//!
+//! # DMA
+//!
+//! With [`use_dma`](Spi::use_dma) the SPI is split into [`Tx`] and [`Rx`] for DMA
+//! transfers that run without CPU involvement, e.g. to fill a display frame buffer or
+//! read a flash page. Each [`Transfer`](crate::dma::Transfer) owns its buffer and
+//! stream until it is complete.
+//!
+//! In full duplex mode every frame sent also receives one, a [`Tx`] only transfer
+//! leaves the overrun flag set. To read, a [`Tx`] transfer must clock out dummy bytes;
+//! start the [`Rx`] transfer first so no frame is missed.
+//!
+//! ```ignore
+//! let spi = dp.SPI1.spi((sck, miso, mosi), mode, 10.MHz(), &clocks);
+//! let (tx, rx) = spi.use_dma().txrx();
+//!
+//! let streams = StreamsTuple::new(dp.DMA2);
+//! let config = DmaConfig::default().memory_increment(true);
+//! let mut rx = Transfer::init_peripheral_to_memory(streams.0, rx, rx_buf, None, config);
+//! let mut tx = Transfer::init_memory_to_peripheral(streams.3, tx, tx_buf, None, config);
+//! rx.start(|_| {});
+//! tx.start(|_| {});
+//!
+//! // or the transfer complete interrupt of the rx stream
+//! while !Stream0::<pac::DMA2>::get_transfer_complete_flag() {}
+//! let (stream, rx, rx_buf, _) = rx.release();
+//! ```
+
 use core::marker::PhantomData;
 use core::ops::Deref;
 use core::ptr;
@@ -35,6 +182,10 @@ pub struct Mode {
 
 mod hal_02;
 mod hal_1;
+#[cfg(feature = "async")]
+mod hal_async;
+#[cfg(feature = "async")]
+pub use hal_async::{on_interrupt, SpiFuture};
 
 use crate::pac::{spi1, RCC};
 use crate::rcc;
@@ -53,6 +204,8 @@ pub enum Error {
     ModeFault,
     /// CRC error
     Crc,
+    /// NSS pulse in the middle of a frame in TI slave mode
+    FrameFormat,
 }
 
 pub struct Sck;
@@ -88,6 +241,37 @@ where
     }
 }
 
+impl<
+        SPI,
+        SCK,
+        MISO,
+        MOSI,
+        NSS,
+        const SCKA: u8,
+        const MISOA: u8,
+        const MOSIA: u8,
+        const NSSA: u8,
+    > Pins<SPI> for (SCK, MISO, MOSI, NSS)
+where
+    SCK: PinA<Sck, SPI, A = Const<SCKA>> + SetAlternate<SCKA, PushPull>,
+    MISO: PinA<Miso, SPI, A = Const<MISOA>> + SetAlternate<MISOA, PushPull>,
+    MOSI: PinA<Mosi, SPI, A = Const<MOSIA>> + SetAlternate<MOSIA, PushPull>,
+    NSS: PinA<Nss, SPI, A = Const<NSSA>> + SetAlternate<NSSA, PushPull>,
+{
+    fn set_alt_mode(&mut self) {
+        self.0.set_alt_mode();
+        self.1.set_alt_mode();
+        self.2.set_alt_mode();
+        self.3.set_alt_mode();
+    }
+    fn restore_mode(&mut self) {
+        self.0.restore_mode();
+        self.1.restore_mode();
+        self.2.restore_mode();
+        self.3.restore_mode();
+    }
+}
+
 /// A filler type for when the SCK pin is unnecessary
 pub type NoSck = NoPin;
 /// A filler type for when the Miso pin is unnecessary
@@ -152,6 +336,18 @@ pub enum BitFormat {
     MsbFirst,
 }
 
+/// The frame format of the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// Motorola SPI, NSS is low during the transfer
+    Motorola,
+    /// TI synchronous serial frame format
+    ///
+    /// A master pulses NSS for one clock cycle before each frame, clock polarity and phase
+    /// are fixed. The NSS pin must be in its alternate function mode, also for a slave.
+    Ti,
+}
+
 #[derive(Debug)]
 pub struct Spi<SPI, PINS, const BIDI: bool = false, W = u8, OPERATION = Master> {
     spi: SPI,
@@ -165,6 +361,9 @@ pub trait Instance:
 {
     #[doc(hidden)]
     fn ptr() -> *const spi1::RegisterBlock;
+    #[cfg(feature = "async")]
+    #[doc(hidden)]
+    fn waker() -> &'static hal_async::SpiWaker;
 }
 
 // Implemented by all SPI instances
@@ -177,6 +376,12 @@ macro_rules! spi {
             fn ptr() -> *const spi1::RegisterBlock {
                 <$SPI>::ptr() as *const _
             }
+
+            #[cfg(feature = "async")]
+            fn waker() -> &'static hal_async::SpiWaker {
+                static WAKER: hal_async::SpiWaker = hal_async::SpiWaker::new();
+                &WAKER
+            }
         }
     };
 }
@@ -233,6 +438,24 @@ pub trait SpiExt: Sized + Instance {
     ) -> Spi<Self, (SCK, MISO, MOSI), true, u8, Slave>
     where
         (SCK, MISO, MOSI): Pins<Self>;
+    fn spi_with_nss<SCK, MISO, MOSI, NSS>(
+        self,
+        pins: (SCK, MISO, MOSI, NSS),
+        mode: impl Into<Mode>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> Spi<Self, (SCK, MISO, MOSI, NSS), false, u8, Master>
+    where
+        (SCK, MISO, MOSI, NSS): Pins<Self>;
+    fn spi_slave_with_nss<SCK, MISO, MOSI, NSS>(
+        self,
+        pins: (SCK, MISO, MOSI, NSS),
+        mode: impl Into<Mode>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> Spi<Self, (SCK, MISO, MOSI, NSS), false, u8, Slave>
+    where
+        (SCK, MISO, MOSI, NSS): Pins<Self>;
 }
 
 impl<SPI: Instance> SpiExt for SPI {
@@ -284,6 +507,30 @@ impl<SPI: Instance> SpiExt for SPI {
     {
         Spi::new_bidi_slave(self, pins, mode, freq, clocks)
     }
+    fn spi_with_nss<SCK, MISO, MOSI, NSS>(
+        self,
+        pins: (SCK, MISO, MOSI, NSS),
+        mode: impl Into<Mode>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> Spi<Self, (SCK, MISO, MOSI, NSS), false, u8, Master>
+    where
+        (SCK, MISO, MOSI, NSS): Pins<Self>,
+    {
+        Spi::new_with_nss(self, pins, mode, freq, clocks)
+    }
+    fn spi_slave_with_nss<SCK, MISO, MOSI, NSS>(
+        self,
+        pins: (SCK, MISO, MOSI, NSS),
+        mode: impl Into<Mode>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> Spi<Self, (SCK, MISO, MOSI, NSS), false, u8, Slave>
+    where
+        (SCK, MISO, MOSI, NSS): Pins<Self>,
+    {
+        Spi::new_slave_with_nss(self, pins, mode, freq, clocks)
+    }
 }
 
 impl<SPI: Instance, PINS, const BIDI: bool, W: FrameSize, OPERATION: Ms>
@@ -310,12 +557,38 @@ impl<SPI: Instance, PINS, W: FrameSize, OPERATION: Ms> Spi<SPI, PINS, false, W,
     pub fn to_bidi_transfer_mode(self) -> Spi<SPI, PINS, true, W, OPERATION> {
         self.into_mode()
     }
+
+    /// Only receives, MOSI of a master or MISO of a slave is not driven
+    ///
+    /// A master generates the clock continuously from the moment this is enabled, frames
+    /// must be read before the next one is complete. To stop, disable the SPI after
+    /// the second to last frame was received.
+    pub fn set_receive_only(&mut self, enable: bool) {
+        self.spi.cr1.modify(|_, w| w.rxonly().bit(enable));
+    }
 }
 
 impl<SPI: Instance, PINS, W: FrameSize, OPERATION: Ms> Spi<SPI, PINS, true, W, OPERATION> {
     pub fn to_normal_transfer_mode(self) -> Spi<SPI, PINS, false, W, OPERATION> {
         self.into_mode()
     }
+
+    /// Drives the data line, written frames are transmitted
+    ///
+    /// The embedded-hal traits switch the direction on each read and write.
+    pub fn bidi_output(&mut self) {
+        self.spi.cr1.modify(|_, w| w.bidioe().set_bit());
+    }
+
+    /// Releases the data line to receive, a master starts clocking immediately
+    pub fn bidi_input(&mut self) {
+        self.spi.cr1.modify(|_, w| w.bidioe().clear_bit());
+    }
+
+    /// Returns `true` if the data line is driven
+    pub fn is_bidi_output(&self) -> bool {
+        self.spi.cr1.read().bidioe().bit_is_set()
+    }
 }
 
 impl<SPI: Instance, PINS, const BIDI: bool, W: FrameSize> Spi<SPI, PINS, BIDI, W, Master> {
@@ -328,6 +601,21 @@ impl<SPI: Instance, PINS, const BIDI: bool, W: FrameSize> Spi<SPI, PINS, BIDI, W
     pub fn to_master_operation(self) -> Spi<SPI, PINS, BIDI, W, Master> {
         self.into_mode()
     }
+
+    /// Selects the slave with the NSS pin (hardware NSS) instead of software
+    ///
+    /// The NSS pin must be in its alternate function mode. While NSS is high the slave
+    /// ignores the clock and doesn't drive MISO.
+    pub fn set_hardware_nss(&mut self, enable: bool) {
+        self.spi.cr1.modify(|_, w| w.ssm().bit(!enable));
+    }
+
+    /// Selects or deselects the slave with software NSS, the default is selected
+    ///
+    /// Has no effect with [`set_hardware_nss`](Self::set_hardware_nss).
+    pub fn set_selected(&mut self, selected: bool) {
+        self.spi.cr1.modify(|_, w| w.ssi().bit(!selected));
+    }
 }
 
 impl<SPI, PINS, const BIDI: bool, OPERATION: Ms> Spi<SPI, PINS, BIDI, u8, OPERATION>
@@ -454,7 +742,91 @@ impl<SPI: Instance, SCK, MISO, MOSI> Spi<SPI, (SCK, MISO, MOSI), true, u8, Slave
     }
 }
 
-impl<SPI, SCK, MISO, MOSI, const BIDI: bool, OPERATION> Spi<SPI, (SCK, MISO, MOSI), BIDI, OPERATION>
+impl<SPI: Instance, SCK, MISO, MOSI, NSS> Spi<SPI, (SCK, MISO, MOSI, NSS), false, u8, Master> {
+    /// Master with the NSS pin driven by the peripheral (`SSOE`)
+    ///
+    /// NSS is low while the SPI is enabled, [`enable`](Spi::enable) deselects the slave
+    /// between transactions once [`is_busy`](Spi::is_busy) is clear. Other masters can't
+    /// use the bus.
+    pub fn new_with_nss(
+        spi: SPI,
+        mut pins: (SCK, MISO, MOSI, NSS),
+        mode: impl Into<Mode>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> Self
+    where
+        (SCK, MISO, MOSI, NSS): Pins<SPI>,
+    {
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+            SPI::enable(rcc);
+            SPI::reset(rcc);
+        }
+
+        pins.set_alt_mode();
+
+        let spi = Self::_new(spi, pins).pre_init(mode.into(), freq, SPI::clock(clocks), true);
+        spi.spi.cr1.modify(|_, w| w.ssm().clear_bit());
+        spi.spi.cr2.modify(|_, w| w.ssoe().set_bit());
+        spi.init()
+    }
+}
+
+impl<SPI: Instance, SCK, MISO, MOSI, NSS> Spi<SPI, (SCK, MISO, MOSI, NSS), false, u8, Slave> {
+    /// Slave selected by the master with the NSS pin
+    pub fn new_slave_with_nss(
+        spi: SPI,
+        mut pins: (SCK, MISO, MOSI, NSS),
+        mode: impl Into<Mode>,
+        freq: Hertz,
+        clocks: &Clocks,
+    ) -> Self
+    where
+        (SCK, MISO, MOSI, NSS): Pins<SPI>,
+    {
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+            SPI::enable(rcc);
+            SPI::reset(rcc);
+        }
+
+        pins.set_alt_mode();
+
+        let spi = Self::_new(spi, pins).pre_init(mode.into(), freq, SPI::clock(clocks), false);
+        spi.spi.cr1.modify(|_, w| w.ssm().clear_bit());
+        spi.init()
+    }
+}
+
+impl<SPI, SCK, MISO, MOSI, NSS, const BIDI: bool, W, OPERATION>
+    Spi<SPI, (SCK, MISO, MOSI, NSS), BIDI, W, OPERATION>
+where
+    SPI: Instance,
+    (SCK, MISO, MOSI, NSS): Pins<SPI>,
+{
+    /// The NSS pin, e.g. to configure an EXTI interrupt on the end of a frame
+    ///
+    /// The EXTI configuration stays when the SPI is split with
+    /// [`use_dma`](Spi::use_dma).
+    pub fn nss_mut(&mut self) -> &mut NSS {
+        &mut self.pins.3
+    }
+
+    pub fn release(mut self) -> (SPI, (SCK, MISO, MOSI, NSS)) {
+        self.pins.restore_mode();
+
+        (
+            self.spi,
+            (self.pins.0, self.pins.1, self.pins.2, self.pins.3),
+        )
+    }
+}
+
+impl<SPI, SCK, MISO, MOSI, const BIDI: bool, W, OPERATION>
+    Spi<SPI, (SCK, MISO, MOSI), BIDI, W, OPERATION>
 where
     SPI: Instance,
     (SCK, MISO, MOSI): Pins<SPI>,
@@ -497,17 +869,7 @@ impl<SPI: Instance, PINS, const BIDI: bool, W, OPERATION> Spi<SPI, PINS, BIDI, W
         // disable SS output
         self.spi.cr2.write(|w| w.ssoe().clear_bit());
 
-        let br = match clock.raw() / freq.raw() {
-            0 => unreachable!(),
-            1..=2 => 0b000,
-            3..=5 => 0b001,
-            6..=11 => 0b010,
-            12..=23 => 0b011,
-            24..=47 => 0b100,
-            48..=95 => 0b101,
-            96..=191 => 0b110,
-            _ => 0b111,
-        };
+        let br = Self::compute_baud_rate(clock, freq);
 
         self.spi.cr1.write(|w| {
             w.cpha().bit(mode.phase == Phase::CaptureOnSecondTransition);
@@ -529,6 +891,61 @@ impl<SPI: Instance, PINS, const BIDI: bool, W, OPERATION> Spi<SPI, PINS, BIDI, W
         self
     }
 
+    fn compute_baud_rate(clock: Hertz, freq: Hertz) -> u8 {
+        match clock.raw() / freq.raw() {
+            0 => unreachable!(),
+            1..=2 => 0b000,
+            3..=5 => 0b001,
+            6..=11 => 0b010,
+            12..=23 => 0b011,
+            24..=47 => 0b100,
+            48..=95 => 0b101,
+            96..=191 => 0b110,
+            _ => 0b111,
+        }
+    }
+
+    /// Waits until the last frame has been sent
+    fn wait_idle(&self) {
+        // A receiving bidirectional master clocks until the direction is switched
+        if !BIDI || self.spi.cr1.read().bidioe().bit_is_set() {
+            while !self.is_tx_empty() || self.is_busy() {}
+        }
+    }
+
+    /// Modifies `CR1` with the SPI disabled, after the ongoing transfer
+    fn reconfigure(&mut self, f: impl FnOnce(&mut spi1::cr1::W) -> &mut spi1::cr1::W) {
+        self.wait_idle();
+        let enabled = self.spi.cr1.read().spe().bit_is_set();
+        self.enable(false);
+        self.spi.cr1.modify(|_, w| f(w));
+        self.enable(enabled);
+    }
+
+    /// Changes the clock frequency, e.g. from 400 kHz for SD card initialization to the
+    /// full speed
+    ///
+    /// Waits for the ongoing transfer and disables the SPI while the clock is changed.
+    /// The divider of the bus clock is selected like in the constructors.
+    pub fn set_baudrate(&mut self, freq: Hertz, clocks: &Clocks) {
+        let br = Self::compute_baud_rate(SPI::clock(clocks), freq);
+        self.reconfigure(|w| w.br().bits(br));
+    }
+
+    /// Changes the clock polarity and phase, e.g. for devices with different modes on the
+    /// same bus
+    ///
+    /// Waits for the ongoing transfer and disables the SPI while the mode is changed.
+    pub fn set_mode(&mut self, mode: impl Into<Mode>) {
+        let mode = mode.into();
+        self.reconfigure(|w| {
+            w.cpha()
+                .bit(mode.phase == Phase::CaptureOnSecondTransition)
+                .cpol()
+                .bit(mode.polarity == Polarity::IdleHigh)
+        });
+    }
+
     /// Select which frame format is used for data transfers
     pub fn bit_format(&mut self, format: BitFormat) {
         match format {
@@ -537,6 +954,18 @@ impl<SPI: Instance, PINS, const BIDI: bool, W, OPERATION> Spi<SPI, PINS, BIDI, W
         }
     }
 
+    /// Select the Motorola or TI frame format
+    ///
+    /// The SPI is disabled while the format is changed.
+    pub fn frame_format(&mut self, format: FrameFormat) {
+        let enabled = self.spi.cr1.read().spe().bit_is_set();
+        self.enable(false);
+        self.spi
+            .cr2
+            .modify(|_, w| w.frf().bit(format == FrameFormat::Ti));
+        self.enable(enabled);
+    }
+
     /// Enable interrupts for the given `event`:
     ///  - Received data ready to be read (RXNE)
     ///  - Transmit data register empty (TXE)
@@ -633,16 +1062,44 @@ where
 impl<SPI: Instance, PINS, const BIDI: bool, W: FrameSize, OPERATION>
     Spi<SPI, PINS, BIDI, W, OPERATION>
 {
+    /// Reads the received frame, `WouldBlock` until one is received
+    ///
+    /// Errors are reported and cleared on the next read, e.g. from the [`Event::Rxne`] or
+    /// [`Event::Error`] interrupt handler. In bidirectional mode the data line is switched
+    /// to input.
+    pub fn read_nonblocking(&mut self) -> nb::Result<W, Error> {
+        if BIDI {
+            self.spi.cr1.modify(|_, w| w.bidioe().clear_bit());
+        }
+        self.check_read()
+    }
+
+    /// Writes the next frame to send, `WouldBlock` until the transmit buffer is empty
+    ///
+    /// E.g. from the [`Event::Txe`] interrupt handler. In bidirectional mode the data line
+    /// is switched to output.
+    pub fn write_nonblocking(&mut self, byte: W) -> nb::Result<(), Error> {
+        if BIDI {
+            self.spi.cr1.modify(|_, w| w.bidioe().set_bit());
+        }
+        self.check_send(byte)
+    }
+
     #[inline(always)]
     fn check_read(&mut self) -> nb::Result<W, Error> {
         let sr = self.spi.sr.read();
 
         Err(if sr.ovr().bit_is_set() {
+            // Read from the DR to clear the OVR bit, the frame is lost anyway
+            let _ = self.spi.dr.read();
             Error::Overrun.into()
         } else if sr.modf().bit_is_set() {
             Error::ModeFault.into()
         } else if sr.crcerr().bit_is_set() {
             Error::Crc.into()
+        } else if sr.fre().bit_is_set() {
+            // FRE is cleared by reading SR
+            Error::FrameFormat.into()
         } else if sr.rxne().bit_is_set() {
             return Ok(self.read_data_reg());
         } else {
@@ -669,6 +1126,8 @@ impl<SPI: Instance, PINS, const BIDI: bool, W: FrameSize, OPERATION>
                 w
             });
             Error::Crc.into()
+        } else if sr.fre().bit_is_set() {
+            Error::FrameFormat.into()
         } else if sr.txe().bit_is_set() {
             self.write_data_reg(byte);
             return Ok(());
@@ -680,62 +1139,76 @@ impl<SPI: Instance, PINS, const BIDI: bool, W: FrameSize, OPERATION>
 
 // Spi DMA
 
-impl<SPI: Instance, PINS, const BIDI: bool> Spi<SPI, PINS, BIDI, u8, Master> {
-    pub fn use_dma(self) -> DmaBuilder<SPI> {
-        DmaBuilder { spi: self.spi }
+impl<SPI: Instance, PINS, const BIDI: bool, W: FrameSize, OPERATION>
+    Spi<SPI, PINS, BIDI, W, OPERATION>
+{
+    /// Hands the SPI over to DMA streams, see [`DmaBuilder`]
+    ///
+    /// The DMA buffers have the word type of the frame size, `u16` after
+    /// [`frame_size_16bit`](Spi::frame_size_16bit).
+    pub fn use_dma(self) -> DmaBuilder<SPI, W> {
+        DmaBuilder {
+            spi: self.spi,
+            _word: PhantomData,
+        }
     }
 }
 
-pub struct DmaBuilder<SPI> {
+/// Enables DMA requests for transmit, receive or both
+///
+/// [`Tx`] and [`Rx`] are used as the peripheral of a [`Transfer`](crate::dma::Transfer),
+/// which owns the buffer and the stream until the transfer is complete.
+pub struct DmaBuilder<SPI, W = u8> {
     spi: SPI,
+    _word: PhantomData<W>,
 }
 
-pub struct Tx<SPI> {
-    spi: PhantomData<SPI>,
+pub struct Tx<SPI, W = u8> {
+    spi: PhantomData<(SPI, W)>,
 }
 
-pub struct Rx<SPI> {
-    spi: PhantomData<SPI>,
+pub struct Rx<SPI, W = u8> {
+    spi: PhantomData<(SPI, W)>,
 }
 
-impl<SPI: Instance> DmaBuilder<SPI> {
-    pub fn tx(self) -> Tx<SPI> {
+impl<SPI: Instance, W> DmaBuilder<SPI, W> {
+    pub fn tx(self) -> Tx<SPI, W> {
         self.new_tx()
     }
 
-    pub fn rx(self) -> Rx<SPI> {
+    pub fn rx(self) -> Rx<SPI, W> {
         self.new_rx()
     }
 
-    pub fn txrx(self) -> (Tx<SPI>, Rx<SPI>) {
+    pub fn txrx(self) -> (Tx<SPI, W>, Rx<SPI, W>) {
         (self.new_tx(), self.new_rx())
     }
 
-    fn new_tx(&self) -> Tx<SPI> {
+    fn new_tx(&self) -> Tx<SPI, W> {
         self.spi.cr2.modify(|_, w| w.txdmaen().enabled());
         Tx { spi: PhantomData }
     }
 
-    fn new_rx(self) -> Rx<SPI> {
+    fn new_rx(self) -> Rx<SPI, W> {
         self.spi.cr2.modify(|_, w| w.rxdmaen().enabled());
         Rx { spi: PhantomData }
     }
 }
 
-unsafe impl<SPI: Instance> PeriAddress for Rx<SPI> {
+unsafe impl<SPI: Instance, W: FrameSize> PeriAddress for Rx<SPI, W> {
     #[inline(always)]
     fn address(&self) -> u32 {
         unsafe { &(*SPI::ptr()).dr as *const _ as u32 }
     }
 
-    type MemSize = u8;
+    type MemSize = W;
 }
 
-unsafe impl<SPI: Instance> PeriAddress for Tx<SPI> {
+unsafe impl<SPI: Instance, W: FrameSize> PeriAddress for Tx<SPI, W> {
     #[inline(always)]
     fn address(&self) -> u32 {
         unsafe { &(*SPI::ptr()).dr as *const _ as u32 }
     }
 
-    type MemSize = u8;
+    type MemSize = W;
 }
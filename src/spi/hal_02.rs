@@ -31,24 +31,19 @@ mod nb {
     use super::super::{Error, FrameSize, Instance, Spi};
     use embedded_hal::spi::FullDuplex;
 
-    impl<SPI, PINS, const BIDI: bool, W: FrameSize> FullDuplex<W> for Spi<SPI, PINS, BIDI, W>
+    impl<SPI, PINS, const BIDI: bool, W: FrameSize, OPERATION> FullDuplex<W>
+        for Spi<SPI, PINS, BIDI, W, OPERATION>
     where
         SPI: Instance,
     {
         type Error = Error;
 
         fn read(&mut self) -> nb::Result<W, Error> {
-            if BIDI {
-                self.spi.cr1.modify(|_, w| w.bidioe().clear_bit());
-            }
-            self.check_read()
+            self.read_nonblocking()
         }
 
         fn send(&mut self, byte: W) -> nb::Result<(), Error> {
-            if BIDI {
-                self.spi.cr1.modify(|_, w| w.bidioe().set_bit());
-            }
-            self.check_send(byte)
+            self.write_nonblocking(byte)
         }
     }
 }
@@ -58,7 +53,7 @@ mod blocking {
     use embedded_hal::blocking::spi::{Operation, Transactional, Transfer, Write, WriteIter};
     use embedded_hal::spi::FullDuplex;
 
-    impl<SPI, PINS, const BIDI: bool> Transfer<u8> for Spi<SPI, PINS, BIDI, u8>
+    impl<SPI, PINS, const BIDI: bool, OPERATION> Transfer<u8> for Spi<SPI, PINS, BIDI, u8, OPERATION>
     where
         SPI: Instance,
     {
@@ -74,7 +69,7 @@ mod blocking {
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool> Transfer<u16> for Spi<SPI, PINS, BIDI, u16>
+    impl<SPI, PINS, const BIDI: bool, OPERATION> Transfer<u16> for Spi<SPI, PINS, BIDI, u16, OPERATION>
     where
         SPI: Instance,
     {
@@ -90,7 +85,7 @@ mod blocking {
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool> Write<u8> for Spi<SPI, PINS, BIDI, u8>
+    impl<SPI, PINS, const BIDI: bool, OPERATION> Write<u8> for Spi<SPI, PINS, BIDI, u8, OPERATION>
     where
         SPI: Instance,
     {
@@ -101,7 +96,7 @@ mod blocking {
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool> WriteIter<u8> for Spi<SPI, PINS, BIDI, u8>
+    impl<SPI, PINS, const BIDI: bool, OPERATION> WriteIter<u8> for Spi<SPI, PINS, BIDI, u8, OPERATION>
     where
         SPI: Instance,
     {
@@ -122,7 +117,7 @@ mod blocking {
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool> Write<u16> for Spi<SPI, PINS, BIDI, u16>
+    impl<SPI, PINS, const BIDI: bool, OPERATION> Write<u16> for Spi<SPI, PINS, BIDI, u16, OPERATION>
     where
         SPI: Instance,
     {
@@ -133,7 +128,7 @@ mod blocking {
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool> WriteIter<u16> for Spi<SPI, PINS, BIDI, u16>
+    impl<SPI, PINS, const BIDI: bool, OPERATION> WriteIter<u16> for Spi<SPI, PINS, BIDI, u16, OPERATION>
     where
         SPI: Instance,
     {
@@ -154,7 +149,8 @@ mod blocking {
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool, W: Copy + 'static> Transactional<W> for Spi<SPI, PINS, BIDI, W>
+    impl<SPI, PINS, const BIDI: bool, W: Copy + 'static, OPERATION> Transactional<W>
+        for Spi<SPI, PINS, BIDI, W, OPERATION>
     where
         Self: Transfer<W, Error = Error> + Write<W, Error = Error>,
         SPI: Instance,
@@ -33,11 +33,14 @@ impl Error for super::Error {
             Self::Overrun => ErrorKind::Overrun,
             Self::ModeFault => ErrorKind::ModeFault,
             Self::Crc => ErrorKind::Other,
+            Self::FrameFormat => ErrorKind::FrameFormat,
         }
     }
 }
 
-impl<SPI, PINS, const BIDI: bool, W> ErrorType for super::Spi<SPI, PINS, BIDI, W> {
+impl<SPI, PINS, const BIDI: bool, W, OPERATION> ErrorType
+    for super::Spi<SPI, PINS, BIDI, W, OPERATION>
+{
     type Error = super::Error;
 }
 
@@ -45,22 +48,17 @@ mod nb {
     use super::super::{Error, FrameSize, Instance, Spi};
     use embedded_hal_one::spi::nb::FullDuplex;
 
-    impl<SPI, PINS, const BIDI: bool, W: FrameSize> FullDuplex<W> for Spi<SPI, PINS, BIDI, W>
+    impl<SPI, PINS, const BIDI: bool, W: FrameSize, OPERATION> FullDuplex<W>
+        for Spi<SPI, PINS, BIDI, W, OPERATION>
     where
         SPI: Instance,
     {
         fn read(&mut self) -> nb::Result<W, Error> {
-            if BIDI {
-                self.spi.cr1.modify(|_, w| w.bidioe().clear_bit());
-            }
-            self.check_read()
+            self.read_nonblocking()
         }
 
         fn write(&mut self, byte: W) -> nb::Result<(), Error> {
-            if BIDI {
-                self.spi.cr1.modify(|_, w| w.bidioe().set_bit());
-            }
-            self.check_send(byte)
+            self.write_nonblocking(byte)
         }
     }
 }
@@ -72,7 +70,8 @@ mod blocking {
         nb::FullDuplex,
     };
 
-    impl<SPI, PINS, const BIDI: bool, W: FrameSize + 'static> SpiBus<W> for Spi<SPI, PINS, BIDI, W>
+    impl<SPI, PINS, const BIDI: bool, W: FrameSize + 'static, OPERATION> SpiBus<W>
+        for Spi<SPI, PINS, BIDI, W, OPERATION>
     where
         SPI: Instance,
     {
@@ -86,27 +85,33 @@ mod blocking {
         }
 
         fn transfer(&mut self, buff: &mut [W], data: &[W]) -> Result<(), Self::Error> {
-            assert_eq!(data.len(), buff.len());
-
-            for (d, b) in data.iter().cloned().zip(buff.iter_mut()) {
+            // The longer buffer sets the length, missing words are sent as 0 and
+            // received words without space in `buff` are discarded
+            for i in 0..buff.len().max(data.len()) {
+                let d = data.get(i).copied().unwrap_or_default();
                 nb::block!(<Self as FullDuplex<W>>::write(self, d))?;
-                *b = nb::block!(<Self as FullDuplex<W>>::read(self))?;
+                let b = nb::block!(<Self as FullDuplex<W>>::read(self))?;
+                if let Some(word) = buff.get_mut(i) {
+                    *word = b;
+                }
             }
 
             Ok(())
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool, W> SpiBusFlush for Spi<SPI, PINS, BIDI, W>
+    impl<SPI, PINS, const BIDI: bool, W, OPERATION> SpiBusFlush for Spi<SPI, PINS, BIDI, W, OPERATION>
     where
         SPI: Instance,
     {
         fn flush(&mut self) -> Result<(), Self::Error> {
+            self.wait_idle();
             Ok(())
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool, W: FrameSize + 'static> SpiBusWrite<W> for Spi<SPI, PINS, BIDI, W>
+    impl<SPI, PINS, const BIDI: bool, W: FrameSize + 'static, OPERATION> SpiBusWrite<W>
+        for Spi<SPI, PINS, BIDI, W, OPERATION>
     where
         SPI: Instance,
     {
@@ -122,7 +127,8 @@ mod blocking {
         }
     }
 
-    impl<SPI, PINS, const BIDI: bool, W: FrameSize + 'static> SpiBusRead<W> for Spi<SPI, PINS, BIDI, W>
+    impl<SPI, PINS, const BIDI: bool, W: FrameSize + 'static, OPERATION> SpiBusRead<W>
+        for Spi<SPI, PINS, BIDI, W, OPERATION>
     where
         SPI: Instance,
     {
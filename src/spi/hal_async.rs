@@ -0,0 +1,227 @@
+use super::{Error, Event, FrameSize, Instance, Spi};
+use core::cell::RefCell;
+use core::future::{ready, Future, Ready};
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use cortex_m::interrupt::{self, Mutex};
+use embedded_hal_async::spi::{SpiBus, SpiBusFlush, SpiBusRead, SpiBusWrite};
+
+/// Task waiting for an SPI interrupt
+#[doc(hidden)]
+pub struct SpiWaker(Mutex<RefCell<Option<Waker>>>);
+
+impl SpiWaker {
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(None)))
+    }
+
+    fn register(&self, waker: &Waker) {
+        interrupt::free(|cs| {
+            let mut w = self.0.borrow(cs).borrow_mut();
+            if !matches!(&*w, Some(w) if w.will_wake(waker)) {
+                *w = Some(waker.clone());
+            }
+        })
+    }
+
+    fn wake(&self) {
+        if let Some(w) = interrupt::free(|cs| self.0.borrow(cs).borrow_mut().take()) {
+            w.wake()
+        }
+    }
+}
+
+/// Wakes the task waiting on `SPI`. Must be called from the SPI interrupt handler
+pub fn on_interrupt<SPI: Instance>() {
+    // NOTE(unsafe) only the interrupt enable bits are written
+    let spi = unsafe { &*SPI::ptr() };
+    // The flags stay set until the task continues, so mask them until the next poll
+    spi.cr2.modify(|_, w| {
+        w.txeie()
+            .clear_bit()
+            .rxneie()
+            .clear_bit()
+            .errie()
+            .clear_bit()
+    });
+    SPI::waker().wake();
+}
+
+enum Words<'a, W> {
+    Read(&'a mut [W]),
+    Write(&'a [W]),
+    Transfer(&'a mut [W], &'a [W]),
+    InPlace(&'a mut [W]),
+}
+
+impl<'a, W: FrameSize> Words<'a, W> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Read(words) | Self::InPlace(words) => words.len(),
+            Self::Write(words) => words.len(),
+            Self::Transfer(read, write) => read.len().max(write.len()),
+        }
+    }
+
+    /// Frame to send at `i`, `W::default()` past the end of the write buffer
+    fn get(&self, i: usize) -> W {
+        match self {
+            Self::Read(_) => W::default(),
+            Self::Write(words) | Self::Transfer(_, words) => {
+                words.get(i).copied().unwrap_or_default()
+            }
+            Self::InPlace(words) => words[i],
+        }
+    }
+
+    /// Stores the frame received at `i`, discarded past the end of the read buffer
+    fn set(&mut self, i: usize, word: W) {
+        match self {
+            Self::Read(words) | Self::Transfer(words, _) | Self::InPlace(words) => {
+                if let Some(w) = words.get_mut(i) {
+                    *w = word;
+                }
+            }
+            Self::Write(_) => {}
+        }
+    }
+}
+
+/// Future of an async SPI transfer
+///
+/// Sends one frame at a time and waits for the TXE and RXNE interrupts in between.
+/// Dropping it stops the transfer after the current frame.
+pub struct SpiFuture<'a, SPI: Instance, PINS, W: FrameSize, OPERATION> {
+    spi: &'a mut Spi<SPI, PINS, false, W, OPERATION>,
+    words: Words<'a, W>,
+    sent: usize,
+    received: usize,
+}
+
+impl<'a, SPI: Instance, PINS, W: FrameSize, OPERATION> SpiFuture<'a, SPI, PINS, W, OPERATION> {
+    fn new(spi: &'a mut Spi<SPI, PINS, false, W, OPERATION>, words: Words<'a, W>) -> Self {
+        Self {
+            spi,
+            words,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    fn wait(&mut self, cx: &mut Context<'_>, event: Event) -> Poll<Result<(), Error>> {
+        SPI::waker().register(cx.waker());
+        let txe = matches!(event, Event::Txe);
+        self.spi
+            .spi
+            .cr2
+            .modify(|_, w| w.errie().set_bit().txeie().bit(txe).rxneie().bit(!txe));
+        Poll::Pending
+    }
+}
+
+impl<'a, SPI: Instance, PINS, W: FrameSize, OPERATION> Future
+    for SpiFuture<'a, SPI, PINS, W, OPERATION>
+{
+    type Output = Result<(), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        while this.received < this.words.len() {
+            // Only one frame in flight, so the receive buffer can't overrun
+            if this.sent == this.received {
+                match this.spi.check_send(this.words.get(this.sent)) {
+                    Ok(()) => this.sent += 1,
+                    Err(nb::Error::WouldBlock) => return this.wait(cx, Event::Txe),
+                    Err(nb::Error::Other(e)) => return Poll::Ready(Err(e)),
+                }
+            }
+            match this.spi.check_read() {
+                Ok(word) => {
+                    this.words.set(this.received, word);
+                    this.received += 1;
+                }
+                Err(nb::Error::WouldBlock) => return this.wait(cx, Event::Rxne),
+                Err(nb::Error::Other(e)) => return Poll::Ready(Err(e)),
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, SPI: Instance, PINS, W: FrameSize, OPERATION> Drop
+    for SpiFuture<'a, SPI, PINS, W, OPERATION>
+{
+    fn drop(&mut self) {
+        self.spi.spi.cr2.modify(|_, w| {
+            w.txeie()
+                .clear_bit()
+                .rxneie()
+                .clear_bit()
+                .errie()
+                .clear_bit()
+        });
+    }
+}
+
+impl<SPI: Instance, PINS, W: FrameSize, OPERATION> SpiBusFlush
+    for Spi<SPI, PINS, false, W, OPERATION>
+{
+    type FlushFuture<'a>
+        = Ready<Result<(), Error>>
+    where
+        Self: 'a;
+
+    /// The last frame has already been received, so this only waits for `BSY` to clear
+    fn flush<'a>(&'a mut self) -> Self::FlushFuture<'a> {
+        self.wait_idle();
+        ready(Ok(()))
+    }
+}
+
+impl<SPI: Instance, PINS, W: FrameSize + 'static, OPERATION> SpiBusRead<W>
+    for Spi<SPI, PINS, false, W, OPERATION>
+{
+    type ReadFuture<'a>
+        = SpiFuture<'a, SPI, PINS, W, OPERATION>
+    where
+        Self: 'a;
+
+    fn read<'a>(&'a mut self, words: &'a mut [W]) -> Self::ReadFuture<'a> {
+        SpiFuture::new(self, Words::Read(words))
+    }
+}
+
+impl<SPI: Instance, PINS, W: FrameSize + 'static, OPERATION> SpiBusWrite<W>
+    for Spi<SPI, PINS, false, W, OPERATION>
+{
+    type WriteFuture<'a>
+        = SpiFuture<'a, SPI, PINS, W, OPERATION>
+    where
+        Self: 'a;
+
+    fn write<'a>(&'a mut self, words: &'a [W]) -> Self::WriteFuture<'a> {
+        SpiFuture::new(self, Words::Write(words))
+    }
+}
+
+impl<SPI: Instance, PINS, W: FrameSize + 'static, OPERATION> SpiBus<W>
+    for Spi<SPI, PINS, false, W, OPERATION>
+{
+    type TransferFuture<'a>
+        = SpiFuture<'a, SPI, PINS, W, OPERATION>
+    where
+        Self: 'a;
+
+    fn transfer<'a>(&'a mut self, read: &'a mut [W], write: &'a [W]) -> Self::TransferFuture<'a> {
+        SpiFuture::new(self, Words::Transfer(read, write))
+    }
+
+    type TransferInPlaceFuture<'a>
+        = SpiFuture<'a, SPI, PINS, W, OPERATION>
+    where
+        Self: 'a;
+
+    fn transfer_in_place<'a>(&'a mut self, words: &'a mut [W]) -> Self::TransferInPlaceFuture<'a> {
+        SpiFuture::new(self, Words::InPlace(words))
+    }
+}
@@ -5,6 +5,7 @@
 #![allow(non_upper_case_globals)]
 
 use core::convert::TryFrom;
+use core::marker::PhantomData;
 use cortex_m::peripheral::syst::SystClkSource;
 use cortex_m::peripheral::SYST;
 
@@ -23,12 +24,22 @@ mod pins;
 pub use pins::*;
 pub mod pwm;
 pub use pwm::*;
+pub mod capture;
+pub use capture::*;
+pub mod hall;
+pub use hall::HallSensor;
+pub mod pulse_counter;
+pub use pulse_counter::PulseCounter;
+pub mod dshot;
+pub use dshot::Dshot;
 #[cfg(not(feature = "stm32f410"))]
 pub mod pwm_input;
 #[cfg(not(feature = "stm32f410"))]
 pub use pwm_input::PwmInput;
 #[cfg(feature = "rtic")]
 pub mod monotonic;
+#[cfg(any(feature = "time-driver-tim2", feature = "time-driver-tim5"))]
+pub mod time_driver;
 #[cfg(feature = "rtic")]
 pub use monotonic::*;
 
@@ -41,7 +52,8 @@ pub struct Timer<TIM> {
     pub(crate) clk: Hertz,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+/// Timer channel, used by the multi-channel `embedded_hal::Pwm` implementations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum Channel {
@@ -60,12 +72,19 @@ pub enum SysEvent {
 }
 
 bitflags::bitflags! {
+    /// Timer interrupt events and flags of `DIER`/`SR`
+    ///
+    /// `COM` and `Break` exist only on advanced timers (TIM1, TIM8),
+    /// `Trigger` on timers with slave mode controller.
     pub struct Event: u32 {
         const Update  = 1 << 0;
         const C1 = 1 << 1;
         const C2 = 1 << 2;
         const C3 = 1 << 3;
         const C4 = 1 << 4;
+        const COM = 1 << 5;
+        const Trigger = 1 << 6;
+        const Break = 1 << 7;
     }
 }
 
@@ -98,18 +117,23 @@ pub trait TimerExt: Sized {
     fn counter_hz(self, clocks: &Clocks) -> CounterHz<Self>;
 
     /// Blocking [Delay] with custom fixed precision
+    ///
+    /// Timer clock doesn't need to be a multiple of `FREQ`, see [Delay] for the error.
     fn delay<const FREQ: u32>(self, clocks: &Clocks) -> Delay<Self, FREQ>;
     /// Blocking [Delay] with fixed precision of 1 ms (1 kHz sampling)
     ///
-    /// Can wait from 2 ms to 49 days.
+    /// Can wait from 2 ms to 49 days. On 16-bit timers delays longer than 65 sec
+    /// are split into several timer cycles.
     ///
-    /// NOTE: don't use this if your system frequency more than 65 MHz
+    /// NOTE: if your timer frequency is more than 65 MHz precision is reduced
+    /// to `65536 / clk`
     fn delay_ms(self, clocks: &Clocks) -> DelayMs<Self> {
         self.delay::<1_000>(clocks)
     }
     /// Blocking [Delay] with fixed precision of 1 μs (1 MHz sampling)
     ///
-    /// Can wait from 2 μs to 71 min.
+    /// Can wait from 2 μs to 71 min. On 16-bit timers delays longer than 65 ms
+    /// are split into several timer cycles.
     fn delay_us(self, clocks: &Clocks) -> DelayUs<Self> {
         self.delay::<1_000_000>(clocks)
     }
@@ -123,7 +147,7 @@ impl<TIM: Instance> TimerExt for TIM {
         Timer::new(self, clocks).counter_hz()
     }
     fn delay<const FREQ: u32>(self, clocks: &Clocks) -> Delay<Self, FREQ> {
-        FTimer::new(self, clocks).delay()
+        Timer::new(self, clocks).delay()
     }
 }
 
@@ -215,18 +239,135 @@ pub enum Ocm {
     PwmMode2 = 7,
 }
 
+/// Counter direction and alignment (`DIR` and `CMS` bits of `CR1`)
+///
+/// In center-aligned modes the counter counts up to the auto-reload value and back
+/// down, so the PWM period is twice as long as in edge-aligned modes for the same value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CountMode {
+    /// Edge-aligned, counting up
+    Up,
+    /// Edge-aligned, counting down
+    Down,
+    /// Center-aligned, compare interrupt flags are set while counting down
+    CenterAligned1,
+    /// Center-aligned, compare interrupt flags are set while counting up
+    CenterAligned2,
+    /// Center-aligned, compare interrupt flags are set while counting up and down
+    CenterAligned3,
+}
+
+/// Active level of an output channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
+/// Write protection of the advanced timer configuration (`LOCK` bits of `BDTR`)
+///
+/// Can be written only once after reset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum LockLevel {
+    Off = 0,
+    /// Dead time, `OISx`, break enable and polarity, `AOE` are locked
+    Level1 = 1,
+    /// Level 1 plus channel polarities and off-state selection
+    Level2 = 2,
+    /// Level 2 plus output compare mode and preload
+    Level3 = 3,
+}
+
+/// Source of the commutation event of advanced timers (`CCUS` bit of `CR2`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ComSource {
+    /// Only `COMG` set by software
+    Software,
+    /// `COMG` or a rising edge on the trigger input (`TRGI`), e.g. a hall sensor timer
+    Trigger,
+}
+
+/// Slave mode selection (`SMS` bits of `SMCR`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum SlaveMode {
+    /// Counter is clocked by the internal clock
+    Disabled = 0,
+    EncoderMode1 = 1,
+    EncoderMode2 = 2,
+    EncoderMode3 = 3,
+    /// Rising edge of the trigger reinitializes the counter
+    Reset = 4,
+    /// Counter runs while the trigger is high
+    Gated = 5,
+    /// Counter starts at rising edge of the trigger
+    Trigger = 6,
+    /// Rising edges of the trigger clock the counter
+    ExternalClock1 = 7,
+}
+
+/// Trigger selection (`TS` bits of `SMCR`)
+///
+/// Which timer is connected to `ITRx` is listed in the
+/// "TIMx internal trigger connection" table of the reference manual.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TriggerSource {
+    Itr0 = 0,
+    Itr1 = 1,
+    Itr2 = 2,
+    Itr3 = 3,
+    /// TI1 edge detector
+    Ti1FEd = 4,
+    /// Filtered timer input 1
+    Ti1Fp1 = 5,
+    /// Filtered timer input 2
+    Ti2Fp2 = 6,
+    /// External trigger input
+    Etrf = 7,
+}
+
 /// Wrapper type that indicates which register of the contained timer to use for DMA.
-pub struct CCR<T, const C: u8>(T);
+///
+/// Created from a PWM channel with [`PwmChannel::into_dma_target`], the DMA stream then
+/// writes successive duty cycles from a buffer, one per PWM period.
+pub struct CCR<T, const C: u8>(PhantomData<T>);
 pub type CCR1<T> = CCR<T, 0>;
 pub type CCR2<T> = CCR<T, 1>;
 pub type CCR3<T> = CCR<T, 2>;
 pub type CCR4<T> = CCR<T, 3>;
 
 /// Wrapper type that indicates which register of the contained timer to use for DMA.
-pub struct DMAR<T>(T);
+///
+/// Each DMA request to `DMAR` is redirected to the registers selected by
+/// [`set_dma_burst`](Timer::set_dma_burst), e.g. to update `ARR` and `CCRx` on every update event.
+/// Created with [`Timer::dmar`] or [`FTimer::dmar`], also of a running `Pwm`.
+pub struct DMAR<T>(PhantomData<T>);
+
+/// First register of a DMA burst (`DBA` bits of `DCR`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum DmaBurstBase {
+    Psc = 10,
+    Arr = 11,
+    /// Repetition counter (TIM1 and TIM8 only)
+    Rcr = 12,
+    Ccr1 = 13,
+    Ccr2 = 14,
+    Ccr3 = 15,
+    Ccr4 = 16,
+}
 
 mod sealed {
-    use super::{Channel, Event, Ocm};
+    use super::{Channel, Event, Ocm, SlaveMode, TriggerSource};
     pub trait General {
         type Width: Into<u32> + From<u16>;
         fn max_auto_reload() -> u32;
@@ -245,6 +386,7 @@ mod sealed {
         fn listen_interrupt(&mut self, event: Event, b: bool);
         fn get_interrupt_flag(&self) -> Event;
         fn read_count(&self) -> Self::Width;
+        fn write_count(&mut self, value: u32);
         fn start_one_pulse(&mut self);
         fn start_no_update(&mut self);
         fn cr1_reset(&mut self);
@@ -257,14 +399,62 @@ mod sealed {
         fn preload_output_channel_in_mode(&mut self, channel: Channel, mode: Ocm);
         fn start_pwm(&mut self);
         fn enable_channel(channel: u8, b: bool);
+        fn is_center_aligned() -> bool;
+        /// Reads `DIR`, `true` if the counter counts down
+        fn is_down_counting() -> bool;
+        /// Writes `CCxS = 01`, `ICxPSC` and `ICxF` of the capture/compare mode register
+        fn set_input_capture(&mut self, channel: u8, psc: u8, filter: u8);
+        fn set_capture_polarity(channel: u8, p: bool, np: bool);
+        /// Writes `CCxP`, `true` is active low
+        fn set_channel_polarity(channel: u8, p: bool);
+        fn is_overcapture(channel: u8) -> bool;
+        fn clear_overcapture(channel: u8);
     }
 
     pub trait MasterTimer: General {
         type Mms;
         fn master_mode(&mut self, mode: Self::Mms);
     }
+
+    pub trait WithDmar: General {
+        fn set_dma_burst(&mut self, base: u8, len: u8);
+        fn listen_dma(&mut self, event: Event, b: bool);
+        fn enable_cc_dma(channel: u8, b: bool);
+        /// Writes `CCDS`, sends `CCx` DMA requests on update events instead of compare matches
+        fn set_cc_dma_on_update(b: bool);
+    }
+
+    pub trait SlaveTimer: General {
+        fn slave_mode(&mut self, mode: SlaveMode, trigger: TriggerSource);
+        fn master_slave_mode(&mut self, b: bool);
+    }
+
+    pub trait CenterAligned: WithPwm {
+        fn set_count_mode(&mut self, cms: u8, down: bool);
+    }
+
+    pub trait Advanced: WithPwm {
+        fn set_repetition_counter(&mut self, rcr: u8);
+        fn enable_nchannel(channel: u8, b: bool);
+        /// Writes `CCxNP`, `true` is active low
+        fn set_nchannel_polarity(channel: u8, p: bool);
+        fn set_dead_time(&mut self, ckd: u8, dtg: u8);
+        fn set_main_output(&mut self, b: bool);
+        fn set_break(&mut self, enable: bool, active_high: bool);
+        fn set_auto_output_enable(&mut self, b: bool);
+        fn set_off_state(&mut self, run: bool, idle: bool);
+        fn set_lock(&mut self, level: u8);
+        /// Writes `OISx`, `true` is high
+        fn set_idle_state(channel: u8, high: bool);
+        /// Writes `OISxN`, `true` is high
+        fn set_nchannel_idle_state(channel: u8, high: bool);
+        fn set_com_preload(&mut self, enable: bool, trigger: bool);
+        fn trigger_com(&mut self);
+    }
 }
-pub(crate) use sealed::{General, MasterTimer, WithPwm};
+pub(crate) use sealed::{
+    Advanced, CenterAligned, General, MasterTimer, SlaveTimer, WithDmar, WithPwm,
+};
 
 pub trait Instance:
     crate::Sealed + rcc::Enable + rcc::Reset + rcc::BusTimerClock + General
@@ -364,6 +554,11 @@ macro_rules! hal {
                     self.cnt.read().bits() as Self::Width
                 }
                 #[inline(always)]
+                #[allow(unused_unsafe)]
+                fn write_count(&mut self, value: u32) {
+                    self.cnt.write(|w| unsafe { w.bits(value) });
+                }
+                #[inline(always)]
                 fn start_one_pulse(&mut self) {
                     self.cr1.modify(|_, w| unsafe { w.bits(1 << 3) }.cen().set_bit());
                 }
@@ -384,7 +579,8 @@ macro_rules! hal {
                 unsafe impl<const C: u8> PeriAddress for CCR<$TIM, C> {
                     #[inline(always)]
                     fn address(&self) -> u32 {
-                        &self.0.ccr[C as usize] as *const _ as u32
+                        let tim = unsafe { &*<$TIM>::ptr() };
+                        &tim.ccr[C as usize] as *const _ as u32
                     }
 
                     type MemSize = $bits;
@@ -401,16 +597,147 @@ macro_rules! hal {
     }
 }
 
+macro_rules! slave_timer {
+    ($($TIM:ty,)+) => {
+        $(
+            impl SlaveTimer for $TIM {
+                #[inline(always)]
+                fn slave_mode(&mut self, mode: SlaveMode, trigger: TriggerSource) {
+                    // Trigger must be changed while slave mode is disabled
+                    self.smcr.modify(|r, w| unsafe { w.bits(r.bits() & !0b111) });
+                    self.smcr.modify(|r, w| unsafe {
+                        w.bits((r.bits() & !0b111_0111) | ((trigger as u32) << 4) | mode as u32)
+                    });
+                }
+                #[inline(always)]
+                fn master_slave_mode(&mut self, b: bool) {
+                    self.smcr.modify(|_, w| w.msm().bit(b));
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! advanced {
+    ($($TIM:ty,)+) => {
+        $(
+            impl Advanced for $TIM {
+                #[inline(always)]
+                #[allow(unused_unsafe)]
+                fn set_repetition_counter(&mut self, rcr: u8) {
+                    self.rcr.write(|w| unsafe { w.rep().bits(rcr) });
+                }
+                #[inline(always)]
+                fn enable_nchannel(c: u8, b: bool) {
+                    let tim = unsafe { &*<$TIM>::ptr() };
+                    // Channel 4 has no complementary output
+                    if c < 3 {
+                        unsafe { bb::write(&tim.ccer, c*4 + 2, b); }
+                    }
+                }
+                #[inline(always)]
+                fn set_nchannel_polarity(c: u8, p: bool) {
+                    let tim = unsafe { &*<$TIM>::ptr() };
+                    if c < 3 {
+                        unsafe { bb::write(&tim.ccer, c*4 + 3, p); }
+                    }
+                }
+                #[inline(always)]
+                fn set_dead_time(&mut self, ckd: u8, dtg: u8) {
+                    self.cr1.modify(|_, w| unsafe { w.ckd().bits(ckd) });
+                    self.bdtr.modify(|_, w| unsafe { w.dtg().bits(dtg) });
+                }
+                #[inline(always)]
+                fn set_main_output(&mut self, b: bool) {
+                    self.bdtr.modify(|_, w| w.moe().bit(b));
+                }
+                #[inline(always)]
+                fn set_break(&mut self, enable: bool, active_high: bool) {
+                    self.bdtr.modify(|_, w| w.bke().bit(enable).bkp().bit(active_high));
+                }
+                #[inline(always)]
+                fn set_auto_output_enable(&mut self, b: bool) {
+                    self.bdtr.modify(|_, w| w.aoe().bit(b));
+                }
+                #[inline(always)]
+                fn set_off_state(&mut self, run: bool, idle: bool) {
+                    self.bdtr.modify(|_, w| w.ossr().bit(run).ossi().bit(idle));
+                }
+                #[inline(always)]
+                fn set_lock(&mut self, level: u8) {
+                    self.bdtr.modify(|_, w| unsafe { w.lock().bits(level) });
+                }
+                #[inline(always)]
+                fn set_idle_state(c: u8, high: bool) {
+                    let tim = unsafe { &*<$TIM>::ptr() };
+                    if c < 4 {
+                        unsafe { bb::write(&tim.cr2, c*2 + 8, high); }
+                    }
+                }
+                #[inline(always)]
+                fn set_nchannel_idle_state(c: u8, high: bool) {
+                    let tim = unsafe { &*<$TIM>::ptr() };
+                    if c < 3 {
+                        unsafe { bb::write(&tim.cr2, c*2 + 9, high); }
+                    }
+                }
+                #[inline(always)]
+                fn set_com_preload(&mut self, enable: bool, trigger: bool) {
+                    self.cr2.modify(|_, w| w.ccpc().bit(enable).ccus().bit(trigger));
+                }
+                #[inline(always)]
+                fn trigger_com(&mut self) {
+                    self.egr.write(|w| w.comg().set_bit());
+                }
+            }
+        )+
+    };
+}
+
 macro_rules! with_dmar {
     ($TIM:ty, $memsize:ty) => {
         unsafe impl PeriAddress for DMAR<$TIM> {
             #[inline(always)]
             fn address(&self) -> u32 {
-                &self.0.dmar as *const _ as u32
+                unsafe { &(*<$TIM>::ptr()).dmar as *const _ as u32 }
             }
 
             type MemSize = $memsize;
         }
+
+        impl WithDmar for $TIM {
+            #[inline(always)]
+            fn set_dma_burst(&mut self, base: u8, len: u8) {
+                self.dcr
+                    .write(|w| unsafe { w.dba().bits(base).dbl().bits(len - 1) });
+            }
+            #[inline(always)]
+            fn listen_dma(&mut self, event: Event, b: bool) {
+                // DMA request enable bits follow the interrupt enable bits, break has no DMA request
+                let bits = (event - Event::Break).bits() << 8;
+                if b {
+                    self.dier.modify(|r, w| unsafe { w.bits(r.bits() | bits) });
+                } else {
+                    self.dier.modify(|r, w| unsafe { w.bits(r.bits() & !bits) });
+                }
+            }
+            #[inline(always)]
+            fn enable_cc_dma(c: u8, b: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < 4 {
+                    unsafe {
+                        bb::write(&tim.dier, c + 9, b);
+                    }
+                }
+            }
+            #[inline(always)]
+            fn set_cc_dma_on_update(b: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                unsafe {
+                    bb::write(&tim.cr2, 3, b);
+                }
+            }
+        }
     };
 }
 
@@ -448,6 +775,16 @@ macro_rules! with_pwm {
                 self.cr1.modify(|_, w| w.cen().set_bit());
             }
 
+            #[inline(always)]
+            fn is_center_aligned() -> bool {
+                false
+            }
+
+            #[inline(always)]
+            fn is_down_counting() -> bool {
+                false
+            }
+
             #[inline(always)]
             fn enable_channel(c: u8, b: bool) {
                 let tim = unsafe { &*<$TIM>::ptr() };
@@ -455,6 +792,53 @@ macro_rules! with_pwm {
                     unsafe { bb::write(&tim.ccer, c*4, b); }
                 }
             }
+
+            #[inline(always)]
+            fn set_input_capture(&mut self, c: u8, psc: u8, filter: u8) {
+                let mode = 0b01 | ((psc as u32 & 0b11) << 2) | ((filter as u32 & 0xf) << 4);
+                let shift = 8 * (c as u32 % 2);
+                #[allow(unused_unsafe)]
+                match c / 2 {
+                    0 => self.ccmr1_input().modify(|r, w| unsafe {
+                        w.bits((r.bits() & !(0xff << shift)) | (mode << shift))
+                    }),
+                    _ => {}
+                }
+            }
+
+            #[inline(always)]
+            fn set_capture_polarity(c: u8, p: bool, np: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe {
+                        bb::write(&tim.ccer, c*4 + 1, p);
+                        bb::write(&tim.ccer, c*4 + 3, np);
+                    }
+                }
+            }
+
+            #[inline(always)]
+            fn set_channel_polarity(c: u8, p: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe { bb::write(&tim.ccer, c*4 + 1, p); }
+                }
+            }
+
+            #[inline(always)]
+            fn is_overcapture(c: u8) -> bool {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                c < Self::CH_NUMBER && tim.sr.read().bits() & (1 << (c + 9)) != 0
+            }
+
+            #[inline(always)]
+            fn clear_overcapture(c: u8) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    // Flags are cleared by writing 0, writing 1 has no effect
+                    tim.sr.write(|w| unsafe { w.bits(0xffff & !(1 << (c + 9))) });
+                }
+            }
         }
     };
     ($TIM:ty: CH2) => {
@@ -494,6 +878,16 @@ macro_rules! with_pwm {
                 self.cr1.modify(|_, w| w.cen().set_bit());
             }
 
+            #[inline(always)]
+            fn is_center_aligned() -> bool {
+                false
+            }
+
+            #[inline(always)]
+            fn is_down_counting() -> bool {
+                false
+            }
+
             #[inline(always)]
             fn enable_channel(c: u8, b: bool) {
                 let tim = unsafe { &*<$TIM>::ptr() };
@@ -501,9 +895,63 @@ macro_rules! with_pwm {
                     unsafe { bb::write(&tim.ccer, c*4, b); }
                 }
             }
+
+            #[inline(always)]
+            fn set_input_capture(&mut self, c: u8, psc: u8, filter: u8) {
+                let mode = 0b01 | ((psc as u32 & 0b11) << 2) | ((filter as u32 & 0xf) << 4);
+                let shift = 8 * (c as u32 % 2);
+                #[allow(unused_unsafe)]
+                match c / 2 {
+                    0 => self.ccmr1_input().modify(|r, w| unsafe {
+                        w.bits((r.bits() & !(0xff << shift)) | (mode << shift))
+                    }),
+                    _ => {}
+                }
+            }
+
+            #[inline(always)]
+            fn set_capture_polarity(c: u8, p: bool, np: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe {
+                        bb::write(&tim.ccer, c*4 + 1, p);
+                        bb::write(&tim.ccer, c*4 + 3, np);
+                    }
+                }
+            }
+
+            #[inline(always)]
+            fn set_channel_polarity(c: u8, p: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe { bb::write(&tim.ccer, c*4 + 1, p); }
+                }
+            }
+
+            #[inline(always)]
+            fn is_overcapture(c: u8) -> bool {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                c < Self::CH_NUMBER && tim.sr.read().bits() & (1 << (c + 9)) != 0
+            }
+
+            #[inline(always)]
+            fn clear_overcapture(c: u8) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    // Flags are cleared by writing 0, writing 1 has no effect
+                    tim.sr.write(|w| unsafe { w.bits(0xffff & !(1 << (c + 9))) });
+                }
+            }
         }
     };
     ($TIM:ty: CH4 $(, $aoe:ident)?) => {
+        impl CenterAligned for $TIM {
+            #[inline(always)]
+            fn set_count_mode(&mut self, cms: u8, down: bool) {
+                self.cr1.modify(|_, w| w.cms().bits(cms).dir().bit(down));
+            }
+        }
+
         impl WithPwm for $TIM {
             const CH_NUMBER: u8 = 4;
 
@@ -548,6 +996,18 @@ macro_rules! with_pwm {
                 self.cr1.modify(|_, w| w.cen().set_bit());
             }
 
+            #[inline(always)]
+            fn is_center_aligned() -> bool {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                tim.cr1.read().cms().bits() != 0
+            }
+
+            #[inline(always)]
+            fn is_down_counting() -> bool {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                tim.cr1.read().dir().bit_is_set()
+            }
+
             #[inline(always)]
             fn enable_channel(c: u8, b: bool) {
                 let tim = unsafe { &*<$TIM>::ptr() };
@@ -555,6 +1015,56 @@ macro_rules! with_pwm {
                     unsafe { bb::write(&tim.ccer, c*4, b); }
                 }
             }
+
+            #[inline(always)]
+            fn set_input_capture(&mut self, c: u8, psc: u8, filter: u8) {
+                let mode = 0b01 | ((psc as u32 & 0b11) << 2) | ((filter as u32 & 0xf) << 4);
+                let shift = 8 * (c as u32 % 2);
+                #[allow(unused_unsafe)]
+                match c / 2 {
+                    0 => self.ccmr1_input().modify(|r, w| unsafe {
+                        w.bits((r.bits() & !(0xff << shift)) | (mode << shift))
+                    }),
+                    1 => self.ccmr2_input().modify(|r, w| unsafe {
+                        w.bits((r.bits() & !(0xff << shift)) | (mode << shift))
+                    }),
+                    _ => {}
+                }
+            }
+
+            #[inline(always)]
+            fn set_capture_polarity(c: u8, p: bool, np: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe {
+                        bb::write(&tim.ccer, c*4 + 1, p);
+                        bb::write(&tim.ccer, c*4 + 3, np);
+                    }
+                }
+            }
+
+            #[inline(always)]
+            fn set_channel_polarity(c: u8, p: bool) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    unsafe { bb::write(&tim.ccer, c*4 + 1, p); }
+                }
+            }
+
+            #[inline(always)]
+            fn is_overcapture(c: u8) -> bool {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                c < Self::CH_NUMBER && tim.sr.read().bits() & (1 << (c + 9)) != 0
+            }
+
+            #[inline(always)]
+            fn clear_overcapture(c: u8) {
+                let tim = unsafe { &*<$TIM>::ptr() };
+                if c < Self::CH_NUMBER {
+                    // Flags are cleared by writing 0, writing 1 has no effect
+                    tim.sr.write(|w| unsafe { w.bits(0xffff & !(1 << (c + 9))) });
+                }
+            }
         }
     }
 }
@@ -588,6 +1098,26 @@ impl<TIM: Instance> Timer<TIM> {
         self.tim
     }
 
+    /// Stops the counter keeping its value
+    pub fn pause(&mut self) {
+        self.tim.disable_counter();
+    }
+
+    /// Continues counting after [`pause`](Self::pause)
+    pub fn resume(&mut self) {
+        self.tim.enable_counter();
+    }
+
+    /// Sets the counter value to 0
+    pub fn reset(&mut self) {
+        self.tim.reset_counter();
+    }
+
+    /// Current counter value in timer ticks
+    pub fn count(&self) -> TIM::Width {
+        self.tim.read_count()
+    }
+
     /// Starts listening for an `event`
     ///
     /// Note, you will also have to enable the TIM2 interrupt in the NVIC to start
@@ -604,6 +1134,16 @@ impl<TIM: Instance> Timer<TIM> {
         self.tim.clear_interrupt_flag(event);
     }
 
+    /// Gets the interrupt flags of pending events
+    pub fn get_interrupt(&mut self) -> Event {
+        self.tim.get_interrupt_flag()
+    }
+
+    /// Returns `true` if flag of `event` is set
+    pub fn is_pending(&self, event: Event) -> bool {
+        self.tim.get_interrupt_flag().contains(event)
+    }
+
     /// Stops listening for an `event`
     pub fn unlisten(&mut self, event: Event) {
         self.tim.listen_interrupt(event, false);
@@ -611,11 +1151,61 @@ impl<TIM: Instance> Timer<TIM> {
 }
 
 impl<TIM: Instance + MasterTimer> Timer<TIM> {
+    /// Selects the trigger output (`TRGO`) source
+    ///
+    /// E.g. basic timers TIM6/TIM7 started as [CounterHz] or [Counter] with `Mms::Update`
+    /// pace DAC and ADC conversions at the counter frequency.
     pub fn set_master_mode(&mut self, mode: TIM::Mms) {
         self.tim.master_mode(mode)
     }
 }
 
+impl<TIM: Instance + WithDmar> Timer<TIM> {
+    /// Selects `len` (1 - 18) consecutive registers starting from `base` for DMA bursts through [DMAR]
+    pub fn set_dma_burst(&mut self, base: DmaBurstBase, len: u8) {
+        assert!((1..=18).contains(&len));
+        self.tim.set_dma_burst(base as u8, len)
+    }
+
+    /// DMA target for burst transfers to the registers selected by `set_dma_burst`
+    pub fn dmar(&mut self) -> DMAR<TIM> {
+        DMAR(PhantomData)
+    }
+
+    /// Starts generating DMA requests on `event`
+    pub fn listen_dma(&mut self, event: Event) {
+        self.tim.listen_dma(event, true)
+    }
+
+    /// Stops generating DMA requests on `event`
+    pub fn unlisten_dma(&mut self, event: Event) {
+        self.tim.listen_dma(event, false)
+    }
+}
+
+impl<TIM: Instance + SlaveTimer> Timer<TIM> {
+    /// Controls the counter by `trigger`, e.g. output (`TRGO`) of another timer
+    pub fn set_slave_mode(&mut self, mode: SlaveMode, trigger: TriggerSource) {
+        self.tim.slave_mode(mode, trigger)
+    }
+
+    /// Delays the trigger output so slaves started by it are in sync with this timer
+    pub fn set_master_slave_mode(&mut self, b: bool) {
+        self.tim.master_slave_mode(b)
+    }
+}
+
+impl<TIM: Instance + Advanced> Timer<TIM> {
+    /// Generates an update event only every `rcr + 1` counter overflows
+    ///
+    /// Update interrupts and DMA requests fire every `rcr + 1` PWM periods then, in
+    /// center-aligned mode every `rcr + 1` half periods. The new value is loaded on the
+    /// next update event.
+    pub fn set_repetition_counter(&mut self, rcr: u8) {
+        self.tim.set_repetition_counter(rcr)
+    }
+}
+
 /// Timer wrapper for fixed precision timers.
 ///
 /// Uses `fugit::TimerDurationU32` for most of operations
@@ -662,7 +1252,13 @@ impl<TIM: Instance, const FREQ: u32> FTimer<TIM, FREQ> {
 
     /// Creates `Delay` that imlements [embedded_hal::blocking::delay] traits
     pub fn delay(self) -> Delay<TIM, FREQ> {
-        Delay(self)
+        Delay {
+            timer: self,
+            // counter already ticks at `FREQ`
+            clk: Hertz::from_raw(FREQ),
+            psc: 0,
+            wfe_irq: None,
+        }
     }
 
     /// Releases the TIM peripheral
@@ -670,6 +1266,26 @@ impl<TIM: Instance, const FREQ: u32> FTimer<TIM, FREQ> {
         self.tim
     }
 
+    /// Stops the counter keeping its value
+    pub fn pause(&mut self) {
+        self.tim.disable_counter();
+    }
+
+    /// Continues counting after [`pause`](Self::pause)
+    pub fn resume(&mut self) {
+        self.tim.enable_counter();
+    }
+
+    /// Sets the counter value to 0
+    pub fn reset(&mut self) {
+        self.tim.reset_counter();
+    }
+
+    /// Current counter value in timer ticks
+    pub fn count(&self) -> TIM::Width {
+        self.tim.read_count()
+    }
+
     /// Starts listening for an `event`
     ///
     /// Note, you will also have to enable the TIM2 interrupt in the NVIC to start
@@ -686,10 +1302,16 @@ impl<TIM: Instance, const FREQ: u32> FTimer<TIM, FREQ> {
         self.tim.clear_interrupt_flag(event);
     }
 
+    /// Gets the interrupt flags of pending events
     pub fn get_interrupt(&mut self) -> Event {
         self.tim.get_interrupt_flag()
     }
 
+    /// Returns `true` if flag of `event` is set
+    pub fn is_pending(&self, event: Event) -> bool {
+        self.tim.get_interrupt_flag().contains(event)
+    }
+
     /// Stops listening for an `event`
     pub fn unlisten(&mut self, event: Event) {
         self.tim.listen_interrupt(event, false);
@@ -697,11 +1319,61 @@ impl<TIM: Instance, const FREQ: u32> FTimer<TIM, FREQ> {
 }
 
 impl<TIM: Instance + MasterTimer, const FREQ: u32> FTimer<TIM, FREQ> {
+    /// Selects the trigger output (`TRGO`) source
+    ///
+    /// E.g. basic timers TIM6/TIM7 started as [CounterHz] or [Counter] with `Mms::Update`
+    /// pace DAC and ADC conversions at the counter frequency.
     pub fn set_master_mode(&mut self, mode: TIM::Mms) {
         self.tim.master_mode(mode)
     }
 }
 
+impl<TIM: Instance + WithDmar, const FREQ: u32> FTimer<TIM, FREQ> {
+    /// Selects `len` (1 - 18) consecutive registers starting from `base` for DMA bursts through [DMAR]
+    pub fn set_dma_burst(&mut self, base: DmaBurstBase, len: u8) {
+        assert!((1..=18).contains(&len));
+        self.tim.set_dma_burst(base as u8, len)
+    }
+
+    /// DMA target for burst transfers to the registers selected by `set_dma_burst`
+    pub fn dmar(&mut self) -> DMAR<TIM> {
+        DMAR(PhantomData)
+    }
+
+    /// Starts generating DMA requests on `event`
+    pub fn listen_dma(&mut self, event: Event) {
+        self.tim.listen_dma(event, true)
+    }
+
+    /// Stops generating DMA requests on `event`
+    pub fn unlisten_dma(&mut self, event: Event) {
+        self.tim.listen_dma(event, false)
+    }
+}
+
+impl<TIM: Instance + SlaveTimer, const FREQ: u32> FTimer<TIM, FREQ> {
+    /// Controls the counter by `trigger`, e.g. output (`TRGO`) of another timer
+    pub fn set_slave_mode(&mut self, mode: SlaveMode, trigger: TriggerSource) {
+        self.tim.slave_mode(mode, trigger)
+    }
+
+    /// Delays the trigger output so slaves started by it are in sync with this timer
+    pub fn set_master_slave_mode(&mut self, b: bool) {
+        self.tim.master_slave_mode(b)
+    }
+}
+
+impl<TIM: Instance + Advanced, const FREQ: u32> FTimer<TIM, FREQ> {
+    /// Generates an update event only every `rcr + 1` counter overflows
+    ///
+    /// Update interrupts and DMA requests fire every `rcr + 1` PWM periods then, in
+    /// center-aligned mode every `rcr + 1` half periods. The new value is loaded on the
+    /// next update event.
+    pub fn set_repetition_counter(&mut self, rcr: u8) {
+        self.tim.set_repetition_counter(rcr)
+    }
+}
+
 #[inline(always)]
 pub(crate) const fn compute_arr_presc(freq: u32, clock: u32) -> (u16, u32) {
     let ticks = clock / freq;
@@ -710,11 +1382,51 @@ pub(crate) const fn compute_arr_presc(freq: u32, clock: u32) -> (u16, u32) {
     (psc as u16, arr)
 }
 
+/// Clock division (`CKD`) and `DTG` value for a dead time of at least `ns`
+///
+/// Dead times longer than 4032 timer clock ticks are saturated.
+pub(crate) const fn compute_dead_time(ns: u32, clock: u32) -> (u8, u8) {
+    let ticks = (ns as u64 * clock as u64 + 999_999_999) / 1_000_000_000;
+    let mut ckd = 0;
+    while ckd < 2 && ticks > (1008 << ckd) {
+        ckd += 1;
+    }
+    // Dead time in `tDTS = tCK_INT << CKD` ticks, rounded up
+    let t = (ticks + (1 << ckd) - 1) >> ckd;
+    let dtg = if t <= 127 {
+        t as u8
+    } else if t <= 254 {
+        0b1000_0000 | ((t + 1) / 2 - 64) as u8
+    } else if t <= 504 {
+        0b1100_0000 | ((t + 7) / 8 - 32) as u8
+    } else if t <= 1008 {
+        0b1110_0000 | ((t + 15) / 16 - 32) as u8
+    } else {
+        0xff
+    };
+    (ckd, dtg)
+}
+
+/// Prescaler for delays which gives counter frequency closest to `freq`
+///
+/// Exact if `clock` is a multiple of `freq` and `clock / freq <= 65536`.
+pub(crate) const fn compute_delay_psc(freq: u32, clock: u32) -> u16 {
+    let div = clock / freq;
+    if div <= 1 {
+        0
+    } else if div > 1 << 16 {
+        u16::MAX
+    } else {
+        (div - 1) as u16
+    }
+}
+
 // All F4xx parts have these timers.
 hal!(
     pac::TIM9: [Timer9, u16, c: (CH2),],
     pac::TIM11: [Timer11, u16, c: (CH1),],
 );
+slave_timer!(pac::TIM9,);
 
 // All parts except for F410 add these timers.
 #[cfg(not(feature = "stm32f410"))]
@@ -726,6 +1438,8 @@ hal!(
     pac::TIM4: [Timer4, u16, dmar: u16, c: (CH4), m: tim3,],
     pac::TIM10: [Timer10, u16, c: (CH1),],
 );
+#[cfg(not(feature = "stm32f410"))]
+slave_timer!(pac::TIM1, pac::TIM5, pac::TIM2, pac::TIM3, pac::TIM4,);
 
 // TIM5 on F410 is 16-bit
 #[cfg(feature = "stm32f410")]
@@ -733,6 +1447,11 @@ hal!(
     pac::TIM1: [Timer1, u16, dmar: u16, c: (CH4, _aoe), m: tim1,],
     pac::TIM5: [Timer5, u16, dmar: u16, c: (CH4), m: tim5,],
 );
+#[cfg(feature = "stm32f410")]
+slave_timer!(pac::TIM1, pac::TIM5,);
+
+// Advanced-control timers: TIM1 on all parts, TIM8 below.
+advanced!(pac::TIM1,);
 
 // All parts except F401 and F411.
 #[cfg(not(any(feature = "stm32f401", feature = "stm32f411",)))]
@@ -747,3 +1466,7 @@ hal!(
     pac::TIM13: [Timer13, u16, c: (CH1),],
     pac::TIM14: [Timer14, u16, c: (CH1),],
 );
+#[cfg(not(any(feature = "stm32f401", feature = "stm32f410", feature = "stm32f411",)))]
+slave_timer!(pac::TIM8, pac::TIM12,);
+#[cfg(not(any(feature = "stm32f401", feature = "stm32f410", feature = "stm32f411",)))]
+advanced!(pac::TIM8,);
@@ -0,0 +1,205 @@
+//! Input capture
+//!
+//! Captures the counter value into `CCRx` on edges of the channel pins,
+//! e.g. to timestamp external pulses. Capture interrupts are enabled with
+//! `listen(Event::C1)` (`C2`, ...) like other timer events.
+
+use super::{Channel, FTimer, Instance, Pins, WithPwm};
+use crate::rcc::Clocks;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+/// Signal edge which triggers a capture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CaptureEdge {
+    Rising,
+    Falling,
+    /// Both rising and falling edges
+    Both,
+}
+
+/// Number of edges required for a capture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum CapturePrescaler {
+    /// Capture on every edge
+    Div1 = 0,
+    /// Capture once every 2 edges
+    Div2 = 1,
+    /// Capture once every 4 edges
+    Div4 = 2,
+    /// Capture once every 8 edges
+    Div8 = 3,
+}
+
+pub trait CaptureExt
+where
+    Self: Sized + Instance + WithPwm,
+{
+    /// Input capture with counter ticking at `FREQ`
+    fn capture<P, PINS, const FREQ: u32>(
+        self,
+        pins: PINS,
+        clocks: &Clocks,
+    ) -> Capture<Self, P, PINS, FREQ>
+    where
+        PINS: Pins<Self, P>;
+
+    /// Input capture with precision of 1 μs (1 MHz sampling)
+    fn capture_us<P, PINS>(self, pins: PINS, clocks: &Clocks) -> Capture<Self, P, PINS, 1_000_000>
+    where
+        PINS: Pins<Self, P>,
+    {
+        self.capture::<_, _, 1_000_000>(pins, clocks)
+    }
+}
+
+impl<TIM> CaptureExt for TIM
+where
+    Self: Sized + Instance + WithPwm,
+{
+    fn capture<P, PINS, const FREQ: u32>(
+        self,
+        pins: PINS,
+        clocks: &Clocks,
+    ) -> Capture<Self, P, PINS, FREQ>
+    where
+        PINS: Pins<Self, P>,
+    {
+        FTimer::<Self, FREQ>::new(self, clocks).capture(pins)
+    }
+}
+
+pub struct Capture<TIM, P, PINS, const FREQ: u32>
+where
+    TIM: Instance + WithPwm,
+    PINS: Pins<TIM, P>,
+{
+    timer: FTimer<TIM, FREQ>,
+    _pins: PhantomData<(P, PINS)>,
+}
+
+impl<TIM, P, PINS, const FREQ: u32> Capture<TIM, P, PINS, FREQ>
+where
+    TIM: Instance + WithPwm,
+    PINS: Pins<TIM, P>,
+{
+    pub fn release(mut self) -> FTimer<TIM, FREQ> {
+        // stop counter
+        self.tim.cr1_reset();
+        self.timer
+    }
+}
+
+impl<TIM, P, PINS, const FREQ: u32> Deref for Capture<TIM, P, PINS, FREQ>
+where
+    TIM: Instance + WithPwm,
+    PINS: Pins<TIM, P>,
+{
+    type Target = FTimer<TIM, FREQ>;
+    fn deref(&self) -> &Self::Target {
+        &self.timer
+    }
+}
+
+impl<TIM, P, PINS, const FREQ: u32> DerefMut for Capture<TIM, P, PINS, FREQ>
+where
+    TIM: Instance + WithPwm,
+    PINS: Pins<TIM, P>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.timer
+    }
+}
+
+impl<TIM: Instance + WithPwm, const FREQ: u32> FTimer<TIM, FREQ> {
+    /// Configures used channels as inputs capturing on rising edges
+    ///
+    /// The counter runs freely over the whole auto-reload range, so the time between
+    /// two captures is `get_capture(..).wrapping_sub(previous)` modulo `max_auto_reload() + 1`.
+    pub fn capture<P, PINS>(mut self, _pins: PINS) -> Capture<TIM, P, PINS, FREQ>
+    where
+        PINS: Pins<TIM, P>,
+    {
+        if PINS::C1 {
+            capture_rising(&mut self.tim, Channel::C1);
+        }
+        if PINS::C2 && TIM::CH_NUMBER > 1 {
+            capture_rising(&mut self.tim, Channel::C2);
+        }
+        if PINS::C3 && TIM::CH_NUMBER > 2 {
+            capture_rising(&mut self.tim, Channel::C3);
+        }
+        if PINS::C4 && TIM::CH_NUMBER > 3 {
+            capture_rising(&mut self.tim, Channel::C4);
+        }
+
+        self.tim.set_auto_reload(TIM::max_auto_reload()).unwrap();
+
+        // Trigger update event to load the registers
+        self.tim.trigger_update();
+
+        self.tim.enable_counter();
+
+        Capture {
+            timer: self,
+            _pins: PhantomData,
+        }
+    }
+}
+
+fn capture_rising<TIM: Instance + WithPwm>(tim: &mut TIM, channel: Channel) {
+    let c = channel as u8;
+    tim.set_input_capture(c, CapturePrescaler::Div1 as u8, 0);
+    TIM::set_capture_polarity(c, false, false);
+    TIM::enable_channel(c, true);
+}
+
+impl<TIM, P, PINS, const FREQ: u32> Capture<TIM, P, PINS, FREQ>
+where
+    TIM: Instance + WithPwm,
+    PINS: Pins<TIM, P>,
+{
+    pub fn enable(&mut self, channel: Channel) {
+        TIM::enable_channel(PINS::check_used(channel) as u8, true)
+    }
+
+    pub fn disable(&mut self, channel: Channel) {
+        TIM::enable_channel(PINS::check_used(channel) as u8, false)
+    }
+
+    /// Sets capture edge, prescaler and input filter of `channel`
+    ///
+    /// `filter` is the `ICxF` value (0 - 15), 0 disables the filter.
+    pub fn configure(
+        &mut self,
+        channel: Channel,
+        edge: CaptureEdge,
+        prescaler: CapturePrescaler,
+        filter: u8,
+    ) {
+        let c = PINS::check_used(channel) as u8;
+        self.tim.set_input_capture(c, prescaler as u8, filter);
+        match edge {
+            CaptureEdge::Rising => TIM::set_capture_polarity(c, false, false),
+            CaptureEdge::Falling => TIM::set_capture_polarity(c, true, false),
+            CaptureEdge::Both => TIM::set_capture_polarity(c, true, true),
+        }
+    }
+
+    /// Counter value at the last capture in `FREQ` ticks
+    pub fn get_capture(&self, channel: Channel) -> u32 {
+        TIM::read_cc_value(PINS::check_used(channel) as u8)
+    }
+
+    /// Returns `true` if a capture happened while the previous one was not read yet
+    pub fn is_overcapture(&self, channel: Channel) -> bool {
+        TIM::is_overcapture(PINS::check_used(channel) as u8)
+    }
+
+    pub fn clear_overcapture(&mut self, channel: Channel) {
+        TIM::clear_overcapture(PINS::check_used(channel) as u8)
+    }
+}
@@ -1,7 +1,7 @@
-use super::{compute_arr_presc, Error, Event, FTimer, Instance, SysEvent, Timer};
+use super::{compute_arr_presc, Error, Event, FTimer, General, Instance, SysEvent, Timer};
 use crate::pac::SYST;
 use core::ops::{Deref, DerefMut};
-use fugit::{HertzU32 as Hertz, TimerDurationU32, TimerInstantU32};
+use fugit::{HertzU32 as Hertz, TimerDurationU32, TimerInstantU32, TimerInstantU64};
 
 /// Hardware timers
 pub struct CounterHz<TIM>(pub(super) Timer<TIM>);
@@ -161,6 +161,67 @@ impl<TIM: Instance, const FREQ: u32> fugit_timer::Timer<FREQ> for Counter<TIM, F
     }
 }
 
+/// Non-wrapping 64-bit timestamp counter
+///
+/// Extends a 32-bit timer (TIM2, TIM5) with an overflow counter which is updated by
+/// [`now`](Self::now). Call it at least once per counter period (~71 minutes with 1 MHz),
+/// e.g. from the update interrupt after `listen(Event::Update)`.
+pub struct MonoTimer64<TIM, const FREQ: u32> {
+    timer: FTimer<TIM, FREQ>,
+    overflows: u32,
+}
+
+impl<T, const FREQ: u32> Deref for MonoTimer64<T, FREQ> {
+    type Target = FTimer<T, FREQ>;
+    fn deref(&self) -> &Self::Target {
+        &self.timer
+    }
+}
+
+impl<T, const FREQ: u32> DerefMut for MonoTimer64<T, FREQ> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.timer
+    }
+}
+
+/// `MonoTimer64` with precision of 1 μs (1 MHz sampling)
+pub type MonoTimer64Us<TIM> = MonoTimer64<TIM, 1_000_000>;
+
+impl<TIM: Instance + General<Width = u32>, const FREQ: u32> FTimer<TIM, FREQ> {
+    /// Creates [MonoTimer64] counting from 0
+    pub fn monotonic64(mut self) -> MonoTimer64<TIM, FREQ> {
+        self.tim.set_auto_reload(TIM::max_auto_reload()).unwrap();
+        // Trigger update event to load the registers
+        self.tim.trigger_update();
+        self.tim.clear_interrupt_flag(Event::Update);
+        self.tim.enable_counter();
+        MonoTimer64 {
+            timer: self,
+            overflows: 0,
+        }
+    }
+}
+
+impl<TIM: Instance + General<Width = u32>, const FREQ: u32> MonoTimer64<TIM, FREQ> {
+    /// Releases the TIM peripheral
+    pub fn release(mut self) -> FTimer<TIM, FREQ> {
+        // stop counter
+        self.tim.cr1_reset();
+        self.timer
+    }
+
+    pub fn now(&mut self) -> TimerInstantU64<FREQ> {
+        let mut count = self.tim.read_count();
+        if self.tim.get_interrupt_flag().contains(Event::Update) {
+            self.tim.clear_interrupt_flag(Event::Update);
+            self.overflows = self.overflows.wrapping_add(1);
+            // The counter could have been read before the overflow
+            count = self.tim.read_count();
+        }
+        TimerInstantU64::from_ticks(((self.overflows as u64) << 32) | count as u64)
+    }
+}
+
 impl Timer<SYST> {
     /// Creates [SysCounterHz] which takes [Hertz] as Duration
     pub fn counter_hz(self) -> SysCounterHz {
@@ -1,9 +1,11 @@
 //! Delays
 
-use super::{FTimer, Instance, Timer};
+use super::{compute_delay_psc, Error, Event, FTimer, Instance, Timer};
+use crate::pac::Interrupt;
+use crate::time::Hertz;
 use core::ops::{Deref, DerefMut};
-use cortex_m::peripheral::SYST;
-use fugit::{MicrosDurationU32, TimerDurationU32};
+use cortex_m::peripheral::{NVIC, SCB, SYST};
+use fugit::{ExtU32, MicrosDurationU32, TimerDurationU32};
 
 /// Timer as a delay provider (SysTick by default)
 pub struct SysDelay(Timer<SYST>);
@@ -35,42 +37,83 @@ impl Timer<SYST> {
 }
 
 impl SysDelay {
+    /// Sleep for given time
     pub fn delay(&mut self, us: MicrosDurationU32) {
+        // Use 64-bit math so long delays don't overflow and clocks that are not
+        // a whole number of MHz (e.g. `HCLK / 8`) don't lose precision
+        self.delay_ticks(us.ticks() as u64 * self.clk.raw() as u64 / 1_000_000);
+    }
+
+    /// Sleep for `ns` nanoseconds
+    ///
+    /// The delay is rounded up to the next SysTick tick.
+    pub fn delay_ns(&mut self, ns: u32) {
+        self.delay_ticks((ns as u64 * self.clk.raw() as u64 + 999_999_999) / 1_000_000_000);
+    }
+
+    fn delay_ticks(&mut self, mut total_rvr: u64) {
         // The SysTick Reload Value register supports values between 1 and 0x00FFFFFF.
         const MAX_RVR: u32 = 0x00FF_FFFF;
 
-        let mut total_rvr = us.ticks() * (self.clk.raw() / 1_000_000);
-
         while total_rvr != 0 {
-            let current_rvr = total_rvr.min(MAX_RVR);
+            let current_rvr = total_rvr.min(MAX_RVR as u64) as u32;
 
             self.tim.set_reload(current_rvr);
             self.tim.clear_current();
             self.tim.enable_counter();
 
             // Update the tracking variable while we are waiting...
-            total_rvr -= current_rvr;
+            total_rvr -= current_rvr as u64;
 
             while !self.tim.has_wrapped() {}
 
             self.tim.disable_counter();
         }
     }
+
+    /// Sleep for given number of milliseconds
+    ///
+    /// Splits the delay so it doesn't overflow when `ms * 1000` exceeds `u32::MAX`
+    pub(super) fn delay_millis(&mut self, mut ms: u32) {
+        const MAX_MS: u32 = u32::MAX / 1_000;
+
+        while ms != 0 {
+            let current_ms = ms.min(MAX_MS);
+            self.delay((current_ms * 1_000).micros());
+            ms -= current_ms;
+        }
+    }
 }
 
-/// Periodic non-blocking timer that imlements [embedded_hal::blocking::delay] traits
-pub struct Delay<TIM, const FREQ: u32>(pub(super) FTimer<TIM, FREQ>);
+/// Timer as a delay provider that imlements [embedded_hal::blocking::delay] traits
+///
+/// Generic over every timer [Instance], which provides the RCC enable/reset and
+/// the timer clock. Use [TimerExt::delay](super::TimerExt::delay) to create it.
+///
+/// If the timer clock is not a multiple of `FREQ` (e.g. 25 MHz HSE without PLL and
+/// `FREQ = 3 MHz`) or needs a prescaler bigger than 65536, the closest prescaler
+/// is used and delays are converted to timer ticks. Delays are never shorter
+/// than requested and at most one timer tick (`(PSC + 1) / clk`) longer.
+/// The error is less than `1 / FREQ` unless the prescaler had to be clamped.
+pub struct Delay<TIM, const FREQ: u32> {
+    pub(super) timer: FTimer<TIM, FREQ>,
+    /// Counter ticks at `clk / (psc + 1)`
+    pub(super) clk: Hertz,
+    pub(super) psc: u16,
+    /// Update interrupt of the timer used to wake up from `WFE`
+    pub(super) wfe_irq: Option<Interrupt>,
+}
 
 impl<T, const FREQ: u32> Deref for Delay<T, FREQ> {
     type Target = FTimer<T, FREQ>;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.timer
     }
 }
 
 impl<T, const FREQ: u32> DerefMut for Delay<T, FREQ> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.timer
     }
 }
 
@@ -79,45 +122,222 @@ pub type DelayUs<TIM> = Delay<TIM, 1_000_000>;
 
 /// `Delay` with precision of 1 ms (1 kHz sampling)
 ///
-/// NOTE: don't use this if your system frequency more than 65 MHz
+/// NOTE: if your timer frequency is more than 65 MHz precision is reduced
+/// to `65536 / clk`
 pub type DelayMs<TIM> = Delay<TIM, 1_000>;
 
+impl<TIM: Instance> Timer<TIM> {
+    /// Creates `Delay` with the prescaler closest to `FREQ`
+    ///
+    /// Unlike [FTimer::new] doesn't require timer clock to be a multiple of `FREQ`.
+    pub fn delay<const FREQ: u32>(mut self) -> Delay<TIM, FREQ> {
+        let psc = compute_delay_psc(FREQ, self.clk.raw());
+        self.tim.set_prescaler(psc);
+        Delay {
+            timer: FTimer { tim: self.tim },
+            clk: self.clk,
+            psc,
+            wfe_irq: None,
+        }
+    }
+}
+
 impl<TIM: Instance, const FREQ: u32> Delay<TIM, FREQ> {
+    /// Converts `FREQ` ticks to timer ticks rounding up
+    fn timer_ticks(&self, ticks: u32) -> u64 {
+        let div = (self.psc as u64 + 1) * FREQ as u64;
+        if self.clk.raw() as u64 == div {
+            ticks as u64
+        } else {
+            (ticks as u64 * self.clk.raw() as u64 + div - 1) / div
+        }
+    }
+
     /// Sleep for given time
+    ///
+    /// Delays longer than [`max_delay`](Self::max_delay) (e.g. on 16-bit timers)
+    /// are split into several consecutive one-pulse cycles.
     pub fn delay(&mut self, time: TimerDurationU32<FREQ>) {
-        let mut ticks = time.ticks().max(1) - 1;
-        while ticks != 0 {
-            let reload = ticks.min(TIM::max_auto_reload());
-
-            // Write Auto-Reload Register (ARR)
-            unsafe {
-                self.tim.set_auto_reload_unchecked(reload);
+        // One-pulse cycle with ARR = n lasts n + 1 ticks. ARR = 0 blocks the counter,
+        // so 1 tick is rounded up to the shortest possible cycle of 2 ticks.
+        let mut ticks = match self.timer_ticks(time.ticks()) {
+            1 => 2,
+            t => t,
+        };
+        while ticks > 1 {
+            let mut reload = (ticks - 1).min(TIM::max_auto_reload() as u64) as u32;
+            // Don't leave a single tick for the last cycle
+            if ticks - reload as u64 == 2 {
+                reload -= 1;
             }
 
-            // Trigger update event (UEV) in the event generation register (EGR)
-            // in order to immediately apply the config
-            self.tim.trigger_update();
-
-            // Configure the counter in one-pulse mode (counter stops counting at
-            // the next updateevent, clearing the CEN bit) and enable the counter.
-            self.tim.start_one_pulse();
+            self.start_cycle(reload);
 
             // Update the tracking variable while we are waiting...
-            ticks -= reload;
+            ticks -= reload as u64 + 1;
             // Wait for CEN bit to clear
-            while self.tim.is_counter_enabled() { /* wait */ }
+            if self.wfe_irq.is_some() {
+                while self.tim.is_counter_enabled() {
+                    cortex_m::asm::wfe();
+                }
+            } else {
+                while self.tim.is_counter_enabled() { /* wait */ }
+            }
+        }
+        self.clear_wfe_irq();
+    }
+
+    /// Sleep for `ns` nanoseconds
+    ///
+    /// The delay is rounded up to the next timer tick, so use a high `FREQ` for short delays.
+    pub fn delay_ns(&mut self, ns: u32) {
+        let ticks = (ns as u64 * FREQ as u64 + 999_999_999) / 1_000_000_000;
+        self.delay(TimerDurationU32::from_ticks(ticks as u32));
+    }
+
+    /// Starts a delay without blocking
+    ///
+    /// Use [`wait`](Self::wait) to poll for completion. Unlike [`delay`](Self::delay)
+    /// `time` can't be longer than [`max_delay`](Self::max_delay).
+    pub fn start(&mut self, time: TimerDurationU32<FREQ>) -> Result<(), Error> {
+        let reload = self.timer_ticks(time.ticks()).saturating_sub(1);
+        if reload == 0 || reload > TIM::max_auto_reload() as u64 {
+            return Err(Error::WrongAutoReload);
+        }
+        let reload = reload as u32;
+
+        // pause
+        self.tim.disable_counter();
+
+        self.start_cycle(reload);
+
+        Ok(())
+    }
+
+    /// Checks if the delay started with [`start`](Self::start) has elapsed
+    pub fn wait(&mut self) -> nb::Result<(), Error> {
+        if self.tim.is_counter_enabled() {
+            Err(nb::Error::WouldBlock)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Aborts the delay started with [`start`](Self::start)
+    ///
+    /// Stops the counter and clears the pending update flag.
+    pub fn cancel(&mut self) -> Result<(), Error> {
+        if !self.tim.is_counter_enabled() {
+            return Err(Error::Disabled);
+        }
+
+        // disable counter
+        self.tim.disable_counter();
+        self.tim.clear_interrupt_flag(Event::Update);
+        self.clear_wfe_irq();
+        Ok(())
+    }
+
+    /// Sleeps with `WFE` instead of busy-waiting during blocking delays
+    ///
+    /// `irq` must be the interrupt which is raised on update events of this timer
+    /// (e.g. `Interrupt::TIM2` or `Interrupt::TIM1_UP_TIM10`). It is used only as a
+    /// wake-up event, so keep it masked in the NVIC or make its handler ignore
+    /// update events. This also sets `SEVONPEND` in the System Control Register.
+    pub fn enable_low_power(&mut self, irq: Interrupt) {
+        // Wake up from `WFE` when an interrupt becomes pending, even if it is masked
+        const SCB_SCR_SEVONPEND: u32 = 1 << 4;
+        unsafe {
+            (*SCB::PTR).scr.modify(|scr| scr | SCB_SCR_SEVONPEND);
+        }
+        self.tim.listen_interrupt(Event::Update, true);
+        self.wfe_irq = Some(irq);
+        self.clear_wfe_irq();
+    }
+
+    /// Goes back to busy-waiting during blocking delays
+    pub fn disable_low_power(&mut self) {
+        self.clear_wfe_irq();
+        self.tim.listen_interrupt(Event::Update, false);
+        self.wfe_irq = None;
+    }
+
+    fn clear_wfe_irq(&mut self) {
+        if let Some(irq) = self.wfe_irq {
+            // Pending interrupt must be cleared or next `WFE` will not wake up
+            self.tim.clear_interrupt_flag(Event::Update);
+            NVIC::unpend(irq);
+        }
+    }
+
+    fn start_cycle(&mut self, reload: u32) {
+        self.clear_wfe_irq();
+
+        // Write Auto-Reload Register (ARR)
+        unsafe {
+            self.tim.set_auto_reload_unchecked(reload);
         }
+
+        // Trigger update event (UEV) in the event generation register (EGR)
+        // in order to immediately apply the config
+        self.tim.trigger_update();
+
+        // Configure the counter in one-pulse mode (counter stops counting at
+        // the next updateevent, clearing the CEN bit) and enable the counter.
+        self.tim.start_one_pulse();
     }
 
     pub fn max_delay(&self) -> TimerDurationU32<FREQ> {
-        TimerDurationU32::from_ticks(TIM::max_auto_reload())
+        let ticks = TIM::max_auto_reload() as u64 * (self.psc as u64 + 1) * FREQ as u64
+            / self.clk.raw() as u64;
+        TimerDurationU32::from_ticks(ticks.min(u32::MAX as u64) as u32)
     }
 
     /// Releases the TIM peripheral
+    ///
+    /// If the delay was created with [`Timer::delay`] the prescaler is set back to
+    /// `clk / FREQ - 1`, like [`FTimer::configure`] this panics if the timer clock
+    /// is not a multiple of `FREQ`.
     pub fn release(mut self) -> FTimer<TIM, FREQ> {
         // stop counter
         self.tim.cr1_reset();
-        self.0
+        if self.wfe_irq.is_some() {
+            self.disable_low_power();
+        }
+        // The delay prescaler may be rounded or clamped
+        if self.clk.raw() != FREQ {
+            assert!(self.clk.raw() % FREQ == 0);
+            let psc = self.clk.raw() / FREQ;
+            self.tim.set_prescaler(u16::try_from(psc - 1).unwrap());
+        }
+        self.timer
+    }
+}
+
+/// Delay provider that feeds a watchdog during long delays
+///
+/// Splits delays into chunks of `chunk_ms` milliseconds and calls
+/// [Watchdog::feed](embedded_hal::watchdog::Watchdog::feed) between them,
+/// so `chunk_ms` must be less than the watchdog period.
+pub struct WatchdogDelay<D, W> {
+    pub(super) delay: D,
+    pub(super) watchdog: W,
+    pub(super) chunk_ms: u32,
+}
+
+impl<D, W> WatchdogDelay<D, W> {
+    pub fn new(delay: D, watchdog: W, chunk_ms: u32) -> Self {
+        assert!(chunk_ms > 0);
+        Self {
+            delay,
+            watchdog,
+            chunk_ms,
+        }
+    }
+
+    /// Releases the delay provider and the watchdog
+    pub fn release(self) -> (D, W) {
+        (self.delay, self.watchdog)
     }
 }
 
@@ -0,0 +1,150 @@
+//! DSHOT ESC protocol
+//!
+//! Every DSHOT bit is one PWM period, `1` is high for 75% and `0` for 37.5% of the period.
+//! A frame consists of 11 bits of throttle, a telemetry request bit and a 4-bit checksum.
+//! [`Dshot`] sends frames on a PWM channel with DMA updating the duty cycle every period.
+//!
+//! ```ignore
+//! let pa8 = gpioa.pa8.into_alternate();
+//! let ch1 = dp.TIM1.pwm_hz(pa8, DshotSpeed::Dshot600.bitrate(), &clocks).split();
+//! let buf = cortex_m::singleton!(: [u16; DSHOT_BUF_LEN] = [0; DSHOT_BUF_LEN]).unwrap();
+//! // TIM1_CH1 is DMA2 stream 1 channel 6
+//! let mut dshot: Dshot<Stream1<pac::DMA2>, 6, pac::TIM1, 0, u16> =
+//!     Dshot::new(StreamsTuple::new(dp.DMA2).1, ch1, buf);
+//! // retry after the previous frame is sent
+//! while dshot.send(1000, false).is_err() {}
+//! ```
+
+use super::{Instance, PwmChannel, WithDmar, WithPwm, CCR};
+use crate::dma::traits::{Channel, DMASet, PeriAddress, Stream};
+use crate::dma::{config::DmaConfig, ChannelX, DMAError, MemoryToPeripheral, Transfer};
+use embedded_dma::Word;
+use fugit::HertzU32 as Hertz;
+
+/// Length of the duty cycle buffer: 16 bits and 2 low periods ending the frame
+pub const DSHOT_BUF_LEN: usize = 18;
+
+/// DSHOT bit rate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DshotSpeed {
+    Dshot150,
+    Dshot300,
+    Dshot600,
+}
+
+impl DshotSpeed {
+    /// PWM frequency to use for this speed
+    pub const fn bitrate(self) -> Hertz {
+        match self {
+            Self::Dshot150 => Hertz::from_raw(150_000),
+            Self::Dshot300 => Hertz::from_raw(300_000),
+            Self::Dshot600 => Hertz::from_raw(600_000),
+        }
+    }
+}
+
+/// Builds a DSHOT frame with checksum
+///
+/// `throttle` values 1 - 47 are commands, 48 - 2047 throttle and 0 disarms.
+///
+/// # Panics
+///
+/// If `throttle` is greater than 2047.
+pub const fn frame(throttle: u16, telemetry: bool) -> u16 {
+    assert!(throttle < 2048);
+    let value = (throttle << 1) | telemetry as u16;
+    let crc = (value ^ (value >> 4) ^ (value >> 8)) & 0xf;
+    (value << 4) | crc
+}
+
+/// Encodes `frame` into duty cycles for a PWM with `max_duty`, most significant bit first
+pub fn encode<W: From<u16>>(frame: u16, max_duty: u16, buf: &mut [W; DSHOT_BUF_LEN]) {
+    let one = (max_duty as u32 * 3 / 4) as u16;
+    let zero = (max_duty as u32 * 3 / 8) as u16;
+    for (i, duty) in buf.iter_mut().enumerate() {
+        *duty = W::from(if i >= 16 {
+            0
+        } else if frame & (0x8000 >> i) != 0 {
+            one
+        } else {
+            zero
+        });
+    }
+}
+
+/// DSHOT output on a PWM channel driven by a DMA stream
+///
+/// The PWM frequency must be [`DshotSpeed::bitrate`] and `STREAM`/`CHANNEL` the DMA
+/// request of the timer channel.
+pub struct Dshot<STREAM, const CHANNEL: u8, TIM, const C: u8, W>
+where
+    STREAM: Stream,
+    ChannelX<CHANNEL>: Channel,
+    TIM: Instance + WithPwm + WithDmar,
+    CCR<TIM, C>: PeriAddress<MemSize = W> + DMASet<STREAM, CHANNEL, MemoryToPeripheral>,
+    W: Word + From<u16> + 'static,
+{
+    transfer:
+        Transfer<STREAM, CHANNEL, CCR<TIM, C>, MemoryToPeripheral, &'static mut [W; DSHOT_BUF_LEN]>,
+    max_duty: u16,
+}
+
+impl<STREAM, const CHANNEL: u8, TIM, const C: u8, W> Dshot<STREAM, CHANNEL, TIM, C, W>
+where
+    STREAM: Stream,
+    ChannelX<CHANNEL>: Channel,
+    TIM: Instance + WithPwm + WithDmar,
+    CCR<TIM, C>: PeriAddress<MemSize = W> + DMASet<STREAM, CHANNEL, MemoryToPeripheral>,
+    W: Word + From<u16> + 'static,
+{
+    /// Takes the PWM channel and starts sending a disarm frame
+    pub fn new(
+        stream: STREAM,
+        mut channel: PwmChannel<TIM, C>,
+        buf: &'static mut [W; DSHOT_BUF_LEN],
+    ) -> Self {
+        let max_duty = channel.get_max_duty();
+        channel.set_duty(0);
+        channel.enable();
+        encode(frame(0, false), max_duty, buf);
+
+        let config = DmaConfig::default().memory_increment(true);
+        let mut transfer = Transfer::init_memory_to_peripheral(
+            stream,
+            channel.into_dma_target(),
+            buf,
+            None,
+            config,
+        );
+        transfer.start(|_| {});
+
+        Self { transfer, max_duty }
+    }
+
+    /// Sends a frame
+    ///
+    /// Returns [`DMAError::NotReady`] while the previous frame is still being sent,
+    /// restarting the transfer then would corrupt it.
+    pub fn send(&mut self, throttle: u16, telemetry: bool) -> Result<(), DMAError<()>> {
+        // The stream is disabled by hardware at the end of the frame or on an error
+        if STREAM::is_enabled() {
+            return Err(DMAError::NotReady(()));
+        }
+        let frame = frame(throttle, telemetry);
+        let max_duty = self.max_duty;
+        // NOTE(unsafe) double buffering is not used
+        unsafe {
+            self.transfer.next_transfer_with(|buf, _| {
+                encode(frame, max_duty, buf);
+                (buf, ())
+            })
+        }
+    }
+
+    /// Stops the transfer and releases the stream, PWM channel and buffer
+    pub fn release(self) -> (STREAM, PwmChannel<TIM, C>, &'static mut [W; DSHOT_BUF_LEN]) {
+        let (stream, ccr, buf, _) = self.transfer.release();
+        (stream, ccr.release(), buf)
+    }
+}
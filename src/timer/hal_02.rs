@@ -1,18 +1,18 @@
-//! Delay implementation based on general-purpose 32 bit timers and System timer (SysTick).
+//! `embedded-hal` 0.2 traits for timers and System timer (SysTick).
 //!
-//! TIM2 and TIM5 are a general purpose 32-bit auto-reload up/downcounter with
-//! a 16-bit prescaler.
+//! Implemented for every timer [Instance](super::Instance), both 16-bit and 32-bit.
 
 use embedded_hal::{
     blocking::delay::{DelayMs, DelayUs},
     timer::{Cancel, CountDown, Periodic},
+    watchdog::Watchdog,
 };
 use fugit::{ExtU32, HertzU32 as Hertz, TimerDurationU32};
 use void::Void;
 
 use super::{
     Channel, Counter, CounterHz, Delay, Error, Instance, Pins, Pwm, PwmChannel, PwmHz, SysCounter,
-    SysCounterHz, SysDelay, WithPwm,
+    SysCounterHz, SysDelay, WatchdogDelay, WithPwm,
 };
 
 impl DelayUs<u32> for SysDelay {
@@ -23,7 +23,7 @@ impl DelayUs<u32> for SysDelay {
 
 impl DelayMs<u32> for SysDelay {
     fn delay_ms(&mut self, ms: u32) {
-        self.delay_us(ms * 1_000);
+        self.delay_millis(ms);
     }
 }
 
@@ -235,6 +235,56 @@ impl<TIM: Instance, const FREQ: u32> DelayMs<u8> for Delay<TIM, FREQ> {
     }
 }
 
+impl<D: DelayMs<u32>, W: Watchdog> DelayMs<u32> for WatchdogDelay<D, W> {
+    fn delay_ms(&mut self, mut ms: u32) {
+        self.watchdog.feed();
+        while ms != 0 {
+            let current_ms = ms.min(self.chunk_ms);
+            self.delay.delay_ms(current_ms);
+            self.watchdog.feed();
+            ms -= current_ms;
+        }
+    }
+}
+
+impl<D: DelayMs<u32>, W: Watchdog> DelayMs<u16> for WatchdogDelay<D, W> {
+    fn delay_ms(&mut self, ms: u16) {
+        DelayMs::<u32>::delay_ms(self, ms as u32)
+    }
+}
+
+impl<D: DelayMs<u32>, W: Watchdog> DelayMs<u8> for WatchdogDelay<D, W> {
+    fn delay_ms(&mut self, ms: u8) {
+        DelayMs::<u32>::delay_ms(self, ms as u32)
+    }
+}
+
+impl<TIM: Instance, const FREQ: u32> CountDown for Delay<TIM, FREQ> {
+    type Time = TimerDurationU32<FREQ>;
+
+    fn start<T>(&mut self, timeout: T)
+    where
+        T: Into<Self::Time>,
+    {
+        self.start(timeout.into()).unwrap()
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        match self.wait() {
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<TIM: Instance, const FREQ: u32> Cancel for Delay<TIM, FREQ> {
+    type Error = Error;
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.cancel()
+    }
+}
+
 impl<TIM: Instance, const FREQ: u32> Periodic for Counter<TIM, FREQ> {}
 
 impl<TIM: Instance, const FREQ: u32> CountDown for Counter<TIM, FREQ> {
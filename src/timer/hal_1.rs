@@ -1,7 +1,6 @@
-//! Delay implementation based on general-purpose 32 bit timers and System timer (SysTick).
+//! `embedded-hal` 1.0 traits for timers and System timer (SysTick).
 //!
-//! TIM2 and TIM5 are a general purpose 32-bit auto-reload up/downcounter with
-//! a 16-bit prescaler.
+//! Implemented for every timer [Instance](super::Instance), both 16-bit and 32-bit.
 
 use core::convert::Infallible;
 use embedded_hal_one::delay::blocking::DelayUs;
@@ -19,7 +18,9 @@ impl DelayUs for SysDelay {
     }
 
     fn delay_ms(&mut self, ms: u32) -> Result<(), Self::Error> {
-        self.delay_us(ms * 1_000)
+        self.delay_millis(ms);
+
+        Ok(())
     }
 }
 
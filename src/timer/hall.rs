@@ -0,0 +1,157 @@
+//! Hall sensor interface
+//!
+//! The three hall sensor outputs are connected to CH1, CH2 and CH3 and XORed into TI1.
+//! Every commutation (edge on any of the inputs) captures the counter into CCR1 and
+//! resets it, so CCR1 holds the time between the last two commutations.
+
+use super::{CPin, Event, FTimer, General, Instance};
+use core::ops::{Deref, DerefMut};
+use fugit::TimerDurationU32;
+
+pub trait Pins<TIM> {}
+
+impl<TIM, PC1, PC2, PC3> Pins<TIM> for (PC1, PC2, PC3)
+where
+    PC1: CPin<TIM, 0>,
+    PC2: CPin<TIM, 1>,
+    PC3: CPin<TIM, 2>,
+{
+}
+
+/// Timer configured as hall sensor interface
+///
+/// Listen for `Event::C1` to get an interrupt on every commutation.
+/// An example interrupt handler:
+/// ```ignore
+/// fn tim3(hall: &mut HallSensor<TIM3, PINS, 1_000_000>) {
+///     if let Some(period) = hall.commutation() {
+///         // time between the last two commutations in μs
+///         let _ = period.ticks();
+///     }
+/// }
+/// ```
+pub struct HallSensor<TIM, PINS, const FREQ: u32>
+where
+    TIM: Instance,
+    PINS: Pins<TIM>,
+{
+    timer: FTimer<TIM, FREQ>,
+    pins: PINS,
+}
+
+impl<TIM, PINS, const FREQ: u32> Deref for HallSensor<TIM, PINS, FREQ>
+where
+    TIM: Instance,
+    PINS: Pins<TIM>,
+{
+    type Target = FTimer<TIM, FREQ>;
+    fn deref(&self) -> &Self::Target {
+        &self.timer
+    }
+}
+
+impl<TIM, PINS, const FREQ: u32> DerefMut for HallSensor<TIM, PINS, FREQ>
+where
+    TIM: Instance,
+    PINS: Pins<TIM>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.timer
+    }
+}
+
+impl<TIM, PINS, const FREQ: u32> HallSensor<TIM, PINS, FREQ>
+where
+    TIM: Instance,
+    PINS: Pins<TIM>,
+{
+    /// Releases the timer and the pins
+    pub fn release(mut self) -> (FTimer<TIM, FREQ>, PINS) {
+        self.tim.cr1_reset();
+        (self.timer, self.pins)
+    }
+}
+
+macro_rules! hal {
+    ($($TIM:ident,)+) => {
+        $(
+        impl<const FREQ: u32> FTimer<crate::pac::$TIM, FREQ> {
+            /// Configures this timer as hall sensor interface
+            ///
+            /// The counter ticks at `FREQ`, so the slowest commutation period which can be
+            /// measured is `max_auto_reload()` ticks. Longer periods are truncated.
+            #[allow(unused_unsafe)] //for some chips the operations are considered safe.
+            pub fn hall_sensor<PINS>(mut self, pins: PINS) -> HallSensor<crate::pac::$TIM, PINS, FREQ>
+            where
+                PINS: Pins<crate::pac::$TIM>,
+            {
+                let max = <crate::pac::$TIM as General>::max_auto_reload();
+                self.tim.set_auto_reload(max).unwrap();
+                self.tim.trigger_update();
+
+                // XOR CH1, CH2 and CH3 into TI1
+                self.tim.cr2.modify(|_, w| w.ti1s().set_bit());
+
+                // Capture on TRC (TI1F_ED), any edge of the XORed input
+                self.tim
+                    .ccmr1_input()
+                    .modify(|_, w| unsafe { w.cc1s().bits(0b11).ic1f().bits(0) });
+                self.tim
+                    .ccer
+                    .modify(|_, w| w.cc1p().clear_bit().cc1np().clear_bit().cc1e().set_bit());
+
+                // Select TI1F_ED as trigger input and reset the counter on every commutation
+                self.tim.smcr.modify(|_, w| unsafe { w.ts().bits(0b100) });
+                self.tim.smcr.modify(|_, w| unsafe { w.sms().bits(0b100) });
+
+                self.tim.enable_counter();
+
+                HallSensor { timer: self, pins }
+            }
+        }
+
+        impl<PINS, const FREQ: u32> HallSensor<crate::pac::$TIM, PINS, FREQ>
+        where
+            PINS: Pins<crate::pac::$TIM>,
+        {
+            /// Time between the last two commutations
+            pub fn commutation_period(&self) -> TimerDurationU32<FREQ> {
+                TimerDurationU32::from_ticks(self.tim.ccr1().read().bits())
+            }
+
+            /// Returns the commutation period if a commutation happened since the last call
+            ///
+            /// Clears the capture flag, so it can be called from the `Event::C1` interrupt handler.
+            pub fn commutation(&mut self) -> Option<TimerDurationU32<FREQ>> {
+                if self.tim.get_interrupt_flag().contains(Event::C1) {
+                    self.tim.clear_interrupt_flag(Event::C1);
+                    Some(self.commutation_period())
+                } else {
+                    None
+                }
+            }
+
+            /// Sets the digital filter of the XORed input
+            ///
+            /// `filter` is the `IC1F` value (0 - 15), 0 disables the filter.
+            #[allow(unused_unsafe)]
+            pub fn set_filter(&mut self, filter: u8) {
+                self.tim
+                    .ccmr1_input()
+                    .modify(|_, w| unsafe { w.ic1f().bits(filter & 0xf) });
+            }
+        }
+        )+
+    };
+}
+
+hal! {
+    TIM5,
+}
+
+#[cfg(feature = "tim2")]
+hal! {
+    TIM2,
+    TIM3,
+    TIM4,
+}
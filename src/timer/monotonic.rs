@@ -1,4 +1,7 @@
-// RTIC Monotonic impl for the 32-bit timers
+//! RTIC Monotonic impl for the 32-bit timers (TIM2 and TIM5)
+//!
+//! Enabled with the `rtic` feature. Uses channel 1 compare for scheduling, so
+//! the timer interrupt must be bound as the monotonic interrupt.
 use super::{Channel, Event, FTimer, General, Instance, WithPwm};
 use crate::rcc::Clocks;
 use core::ops::{Deref, DerefMut};
@@ -6,6 +9,11 @@ pub use fugit::{self, ExtU32};
 use rtic_monotonic::Monotonic;
 use systick_monotonic::Systick;
 
+/// [Monotonic] timer counting at `FREQ` over the full 32-bit range
+///
+/// With `MonoTimerUs` the counter wraps around after ~71 minutes, which is handled
+/// by `fugit` instant comparison as long as scheduled tasks are less than
+/// half of that into the future.
 pub struct MonoTimer<TIM, const FREQ: u32>(FTimer<TIM, FREQ>);
 
 impl<TIM, const FREQ: u32> Deref for MonoTimer<TIM, FREQ> {
@@ -34,6 +42,7 @@ impl<TIM: Instance, const FREQ: u32> MonoTimer<TIM, FREQ> {
 }
 
 pub trait MonoTimerExt: Sized {
+    /// Creates [MonoTimer] with custom precision
     fn monotonic<const FREQ: u32>(self, clocks: &Clocks) -> MonoTimer<Self, FREQ>;
     fn monotonic_us(self, clocks: &Clocks) -> MonoTimer<Self, 1_000_000> {
         self.monotonic::<1_000_000>(clocks)
@@ -8,6 +8,11 @@ pub const C2: u8 = 1;
 pub const C3: u8 = 2;
 pub const C4: u8 = 3;
 
+// Complementary output channels markers (advanced timers)
+pub trait NCPin<TIM, const C: u8> {}
+// Break input marker (advanced timers)
+pub trait BkinPin<TIM> {}
+
 macro_rules! channel_impl {
     ( $( $TIM:ident, $C:ident, $PINX:ident, $AF:literal; )+ ) => {
         $(
@@ -16,6 +21,22 @@ macro_rules! channel_impl {
     };
 }
 
+macro_rules! nchannel_impl {
+    ( $( $TIM:ident, $C:ident, $PINX:ident, $AF:literal; )+ ) => {
+        $(
+            impl<Otype> NCPin<crate::pac::$TIM, $C> for gpio::$PINX<Alternate<$AF, Otype>> { }
+        )+
+    };
+}
+
+macro_rules! bkin_impl {
+    ( $( $TIM:ident, $PINX:ident, $AF:literal; )+ ) => {
+        $(
+            impl<Otype> BkinPin<crate::pac::$TIM> for gpio::$PINX<Alternate<$AF, Otype>> { }
+        )+
+    };
+}
+
 // The approach to PWM channel implementation is to group parts with
 // common pins, starting with groupings of the largest number of parts
 // and moving to smaller and smaller groupings.  Last, we have individual
@@ -218,3 +239,142 @@ channel_impl!(
 
     TIM2, C4, PB2, 1;
 );
+
+// Complementary outputs and break inputs
+nchannel_impl!(
+    TIM1, C1, PA7, 1;
+    TIM1, C1, PB13, 1;
+    TIM1, C2, PB0, 1;
+    TIM1, C2, PB14, 1;
+    TIM1, C3, PB1, 1;
+    TIM1, C3, PB15, 1;
+);
+bkin_impl!(
+    TIM1, PA6, 1;
+    TIM1, PB12, 1;
+);
+
+// All parts except F410.
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nchannel_impl!(
+    TIM1, C1, PE8, 1;
+    TIM1, C2, PE10, 1;
+    TIM1, C3, PE12, 1;
+);
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+bkin_impl!(
+    TIM1, PE15, 1;
+);
+
+// All parts except F401, F410, and F411.
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nchannel_impl!(
+    TIM8, C1, PA5, 3;
+    TIM8, C1, PA7, 3;
+    TIM8, C2, PB0, 3;
+    TIM8, C2, PB14, 3;
+    TIM8, C3, PB1, 3;
+    TIM8, C3, PB15, 3;
+);
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+bkin_impl!(
+    TIM8, PA6, 3;
+);
+
+// STM's "advanced and foundation" lines except F446.
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nchannel_impl!(
+    TIM8, C1, PH13, 3;
+    TIM8, C2, PH14, 3;
+    TIM8, C3, PH15, 3;
+);
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+bkin_impl!(
+    TIM8, PI4, 3;
+);
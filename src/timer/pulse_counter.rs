@@ -0,0 +1,80 @@
+//! External pulse counter
+//!
+//! Counts edges on a CH1 or CH2 pin with the timer in external clock mode 1,
+//! e.g. flow-meter pulses, without interrupts.
+
+use super::{CPin, CaptureEdge, Instance, SlaveMode, SlaveTimer, Timer, TriggerSource, WithPwm};
+use core::ops::{Deref, DerefMut};
+
+/// Timer clocked by edges on an input pin
+///
+/// Use [`count`](Timer::count) and [`reset`](Timer::reset) to read and clear the number of pulses.
+pub struct PulseCounter<TIM, PIN> {
+    timer: Timer<TIM>,
+    pin: PIN,
+}
+
+impl<TIM, PIN> Deref for PulseCounter<TIM, PIN> {
+    type Target = Timer<TIM>;
+    fn deref(&self) -> &Self::Target {
+        &self.timer
+    }
+}
+
+impl<TIM, PIN> DerefMut for PulseCounter<TIM, PIN> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.timer
+    }
+}
+
+impl<TIM: Instance + WithPwm + SlaveTimer> Timer<TIM> {
+    /// Counts `edge`s on `pin` connected to channel 1 or 2
+    ///
+    /// `filter` is the `ICxF` value (0 - 15), 0 disables the filter.
+    /// The count wraps around at `max_auto_reload()`.
+    pub fn pulse_counter<PIN, const C: u8>(
+        mut self,
+        pin: PIN,
+        edge: CaptureEdge,
+        filter: u8,
+    ) -> PulseCounter<TIM, PIN>
+    where
+        PIN: CPin<TIM, C>,
+    {
+        assert!(C < 2 && C < TIM::CH_NUMBER);
+
+        self.tim.disable_counter();
+        self.tim.set_input_capture(C, 0, filter);
+        match edge {
+            CaptureEdge::Rising => TIM::set_capture_polarity(C, false, false),
+            CaptureEdge::Falling => TIM::set_capture_polarity(C, true, false),
+            CaptureEdge::Both => TIM::set_capture_polarity(C, true, true),
+        }
+        let trigger = if C == 0 {
+            TriggerSource::Ti1Fp1
+        } else {
+            TriggerSource::Ti2Fp2
+        };
+        self.tim.slave_mode(SlaveMode::ExternalClock1, trigger);
+
+        self.tim.set_prescaler(0);
+        self.tim.set_auto_reload(TIM::max_auto_reload()).unwrap();
+        // Trigger update event to load the registers
+        self.tim.trigger_update();
+        self.tim.reset_counter();
+
+        self.tim.enable_counter();
+
+        PulseCounter { timer: self, pin }
+    }
+}
+
+impl<TIM: Instance + WithPwm + SlaveTimer, PIN> PulseCounter<TIM, PIN> {
+    /// Releases the timer and the pin
+    pub fn release(mut self) -> (Timer<TIM>, PIN) {
+        self.tim.cr1_reset();
+        self.tim
+            .slave_mode(SlaveMode::Disabled, TriggerSource::Itr0);
+        (self.timer, self.pin)
+    }
+}
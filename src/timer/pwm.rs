@@ -1,4 +1,71 @@
-use super::{compute_arr_presc, Channel, FTimer, Instance, Ocm, Timer, WithPwm};
+//! Pulse width modulation
+//!
+//! `TIMx.pwm_hz(pins, freq, &clocks)` (or `pwm`/`pwm_us` with fixed counter precision)
+//! configures the timer and returns a [`PwmHz`] ([`Pwm`]) that controls all used channels.
+//! `split()` turns it into one [`PwmChannel`] per pin implementing `embedded_hal::PwmPin`.
+//! Unsplit, [`PwmHz`] and [`Pwm`] implement the multi-channel `embedded_hal::Pwm` trait
+//! with [`Channel`] selecting the channel, for drivers that take a whole PWM peripheral.
+//! Using a channel without a pin panics.
+//!
+//! Pins are checked at compile time: each pin must implement [`CPin`] for the timer and
+//! channel, so only pins with the right alternate function are accepted. Several pins
+//! can be given for one channel as a tuple.
+//!
+//! Complementary outputs of advanced timers (TIM1, TIM8) are enabled with
+//! `with_complementary(pin)`, where the pin must implement [`NCPin`], and
+//! separated from the main output by `set_dead_time_ns`. `with_break_pin` lets a
+//! [`BkinPin`] (e.g. an overcurrent comparator) disable the outputs in hardware.
+//! For six-step commutation `set_commutation_preload` buffers channel enables and modes
+//! until the next commutation event.
+//!
+//! Timers with 4 channels can also count down or run in center-aligned modes,
+//! see `set_count_mode`.
+//!
+//! ```ignore
+//! let gpioa = dp.GPIOA.split();
+//! let pins = (gpioa.pa8.into_alternate(), gpioa.pa9.into_alternate());
+//! let (mut ch1, _ch2) = dp.TIM1.pwm_hz(pins, 20.kHz(), &clocks).split();
+//! ch1.set_duty(ch1.get_max_duty() / 2);
+//! ch1.enable();
+//! ```
+//!
+//! ## Phase-shifted PWM
+//!
+//! Timers running at the same frequency can start together with fixed phase offsets,
+//! e.g. for interleaved converters. The master timer outputs its counter enable on `TRGO`
+//! and is started after all slaves wait for it with `set_phase_shift`:
+//!
+//! ```ignore
+//! let mut master = dp.TIM1.pwm_hz(pa8, 100.kHz(), &clocks);
+//! master.pause();
+//! master.reset();
+//! master.set_master_mode(pac::tim1::cr2::MMS_A::Enable);
+//! // TIM1 TRGO is ITR0 of TIM2 and TIM3, see the internal trigger connection table
+//! let mut phase2 = dp.TIM2.pwm_hz(pa0, 100.kHz(), &clocks);
+//! phase2.set_phase_shift(TriggerSource::Itr0, 120);
+//! let mut phase3 = dp.TIM3.pwm_hz(pa6, 100.kHz(), &clocks);
+//! phase3.set_phase_shift(TriggerSource::Itr0, 240);
+//! master.resume();
+//! ```
+//!
+//! All timers must run at exactly the same PWM frequency.
+//!
+//! ## Asymmetric PWM
+//!
+//! The combined and asymmetric PWM modes of newer STM32 families (4-bit `OCxM`) don't
+//! exist on STM32F4. Independent placement of rising and falling edges is still possible
+//! in a center-aligned mode with a new duty cycle for every half period: with
+//! `set_repetition_counter(0)` (advanced timers) an update happens at both the top and the
+//! bottom of the count, and a DMA stream writing to the channel
+//! ([`PwmChannel::into_dma_target`]) loads alternating compare values for the up- and
+//! down-counting halves.
+
+use super::{
+    compute_arr_presc, compute_dead_time, Advanced, CenterAligned, Channel, ComSource, CountMode,
+    FTimer, Instance, LockLevel, Ocm, Polarity, SlaveMode, SlaveTimer, Timer, TriggerSource,
+    WithDmar, WithPwm, CCR,
+};
+use crate::gpio::PinState;
 use crate::rcc::Clocks;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
@@ -25,7 +92,7 @@ pub trait Pins<TIM, P> {
 
     fn split() -> Self::Channels;
 }
-pub use super::{CPin, Ch, C1, C2, C3, C4};
+pub use super::{BkinPin, CPin, Ch, NCPin, C1, C2, C3, C4};
 
 pub struct PwmChannel<TIM, const C: u8> {
     pub(super) _tim: PhantomData<TIM>,
@@ -171,7 +238,7 @@ impl<TIM: Instance + WithPwm, const C: u8> PwmChannel<TIM, C> {
     /// If `0` returned means max_duty is 2^16
     #[inline]
     pub fn get_max_duty(&self) -> u16 {
-        (TIM::read_auto_reload() as u16).wrapping_add(1)
+        max_duty::<TIM>()
     }
 
     #[inline]
@@ -180,6 +247,35 @@ impl<TIM: Instance + WithPwm, const C: u8> PwmChannel<TIM, C> {
     }
 }
 
+impl<TIM: Instance + WithPwm + WithDmar, const C: u8> PwmChannel<TIM, C> {
+    /// Turns the channel into a DMA target for its duty cycle register (`CCRx`)
+    ///
+    /// The channel DMA request is generated on every update event, so a memory to peripheral
+    /// transfer on the stream of this channel (`TIMx_CHy`) writes one duty value from the
+    /// buffer per PWM period, e.g. for WS2812 LEDs. Watch out: `CCDS` is shared by all
+    /// channels of the timer.
+    ///
+    /// ```ignore
+    /// let (ch1, ..) = dp.TIM1.pwm_hz(pins, 800.kHz(), &clocks).split();
+    /// let ccr = ch1.into_dma_target();
+    /// let mut transfer = Transfer::init_memory_to_peripheral(stream, ccr, buf, None, config);
+    /// transfer.start(|_| {});
+    /// ```
+    pub fn into_dma_target(self) -> CCR<TIM, C> {
+        TIM::set_cc_dma_on_update(true);
+        TIM::enable_cc_dma(C, true);
+        CCR(PhantomData)
+    }
+}
+
+impl<TIM: Instance + WithPwm + WithDmar, const C: u8> CCR<TIM, C> {
+    /// Disables the channel DMA request and returns the PWM channel
+    pub fn release(self) -> PwmChannel<TIM, C> {
+        TIM::enable_cc_dma(C, false);
+        PwmChannel::new()
+    }
+}
+
 pub struct PwmHz<TIM, P, PINS>
 where
     TIM: Instance + WithPwm,
@@ -282,6 +378,13 @@ where
         TIM::enable_channel(PINS::check_used(channel) as u8, false)
     }
 
+    /// Sets the output compare mode of `channel`, e.g. [`Ocm::ForceInactive`] to hold
+    /// the output at its inactive level
+    pub fn set_channel_mode(&mut self, channel: Channel, mode: Ocm) {
+        self.tim
+            .preload_output_channel_in_mode(PINS::check_used(channel), mode);
+    }
+
     pub fn get_duty(&self, channel: Channel) -> u16 {
         TIM::read_cc_value(PINS::check_used(channel) as u8) as u16
     }
@@ -292,7 +395,7 @@ where
 
     /// If `0` returned means max_duty is 2^16
     pub fn get_max_duty(&self) -> u16 {
-        (TIM::read_auto_reload() as u16).wrapping_add(1)
+        max_duty::<TIM>()
     }
 
     pub fn get_period(&self) -> Hertz {
@@ -301,16 +404,192 @@ where
         let arr = TIM::read_auto_reload();
 
         // Length in ms of an internal clock pulse
-        clk / ((psc + 1) * (arr + 1))
+        if TIM::is_center_aligned() {
+            clk / ((psc + 1) * 2 * arr)
+        } else {
+            clk / ((psc + 1) * (arr + 1))
+        }
     }
 
     pub fn set_period(&mut self, period: Hertz) {
         let clk = self.clk;
 
-        let (psc, arr) = compute_arr_presc(period.raw(), clk.raw());
+        let (psc, arr) = if TIM::is_center_aligned() {
+            // Counter counts up to ARR and back down
+            let ticks = clk.raw() / period.raw() / 2;
+            let psc = (ticks - 1) / 0xffff;
+            (psc as u16, ticks / (psc + 1))
+        } else {
+            compute_arr_presc(period.raw(), clk.raw())
+        };
         self.tim.set_prescaler(psc);
         self.tim.set_auto_reload(arr).unwrap();
     }
+
+    /// Sets the active level of `channel` output, e.g. for gate drivers with inverted inputs
+    pub fn with_polarity(mut self, channel: Channel, p: Polarity) -> Self {
+        self.set_polarity(channel, p);
+        self
+    }
+
+    /// Sets the active level of `channel` output
+    pub fn set_polarity(&mut self, channel: Channel, p: Polarity) {
+        TIM::set_channel_polarity(PINS::check_used(channel) as u8, p == Polarity::ActiveLow)
+    }
+}
+
+impl<TIM, P, PINS> PwmHz<TIM, P, PINS>
+where
+    TIM: Instance + CenterAligned,
+    PINS: Pins<TIM, P>,
+{
+    /// Changes counting direction and alignment keeping the PWM period
+    ///
+    /// The maximum duty changes, so duty cycles should be set again afterwards.
+    pub fn set_count_mode(&mut self, mode: CountMode) {
+        let period = self.get_period();
+        set_count_mode(&mut self.tim, mode);
+        self.set_period(period);
+        self.tim.trigger_update();
+        self.tim.enable_counter();
+    }
+}
+
+impl<TIM, P, PINS> PwmHz<TIM, P, PINS>
+where
+    TIM: Instance + WithPwm + SlaveTimer,
+    PINS: Pins<TIM, P>,
+{
+    /// Stops the counter and restarts it on the next rising edge of `trigger` lagging
+    /// `degrees` (0 - 359) behind it
+    ///
+    /// See [phase-shifted PWM](self#phase-shifted-pwm). Panics in center-aligned modes.
+    pub fn set_phase_shift(&mut self, trigger: TriggerSource, degrees: u16) {
+        set_phase_shift(&mut self.tim, trigger, degrees);
+    }
+}
+
+impl<TIM, P, PINS> PwmHz<TIM, P, PINS>
+where
+    TIM: Instance + Advanced,
+    PINS: Pins<TIM, P>,
+{
+    /// Enables the complementary output `CHxN` of channel `C` on `npin`
+    pub fn with_complementary<NPIN, const C: u8>(mut self, _npin: NPIN) -> Self
+    where
+        NPIN: NCPin<TIM, C>,
+    {
+        self.enable_complementary(channel(C));
+        self
+    }
+
+    pub fn enable_complementary(&mut self, channel: Channel) {
+        TIM::enable_nchannel(PINS::check_used(channel) as u8, true)
+    }
+
+    pub fn disable_complementary(&mut self, channel: Channel) {
+        TIM::enable_nchannel(PINS::check_used(channel) as u8, false)
+    }
+
+    /// Sets the active level of `channel` complementary output
+    pub fn set_complementary_polarity(&mut self, channel: Channel, p: Polarity) {
+        TIM::set_nchannel_polarity(PINS::check_used(channel) as u8, p == Polarity::ActiveLow)
+    }
+
+    /// Sets the idle level of `channel` output, see [`set_idle_state`](Self::set_idle_state)
+    pub fn with_idle_state(mut self, channel: Channel, state: PinState) -> Self {
+        self.set_idle_state(channel, state);
+        self
+    }
+
+    /// Sets the level of `channel` output while all outputs are disabled (`MOE = 0`)
+    ///
+    /// Applied after the dead time, if off-state selection for idle mode (`OSSI`) is set,
+    /// see [`set_off_state`](Self::set_off_state). The output and its complementary
+    /// output must not both be active at idle. Locked from [`LockLevel::Level1`].
+    pub fn set_idle_state(&mut self, channel: Channel, state: PinState) {
+        TIM::set_idle_state(PINS::check_used(channel) as u8, state == PinState::High)
+    }
+
+    /// Sets the level of `channel` complementary output while all outputs are disabled
+    pub fn set_complementary_idle_state(&mut self, channel: Channel, state: PinState) {
+        TIM::set_nchannel_idle_state(PINS::check_used(channel) as u8, state == PinState::High)
+    }
+
+    /// Inserts a dead time of at least `ns` nanoseconds between a channel output and its
+    /// complementary output
+    ///
+    /// Changes the clock division of the timer, which is also used by the input filters.
+    pub fn set_dead_time_ns(&mut self, ns: u32) {
+        let (ckd, dtg) = compute_dead_time(ns, self.clk.raw());
+        self.tim.set_dead_time(ckd, dtg);
+    }
+
+    /// Enables all outputs (`MOE`)
+    pub fn enable_outputs(&mut self) {
+        self.tim.set_main_output(true);
+    }
+
+    /// Disables all outputs (`MOE`), they go into their idle state
+    ///
+    /// With [`set_auto_output_enable`](Self::set_auto_output_enable) (the default) the
+    /// outputs are enabled again on the next update event, disable it first to keep them off.
+    pub fn disable_outputs(&mut self) {
+        self.tim.set_main_output(false);
+    }
+
+    /// Enables the break input on `pin`
+    ///
+    /// When `pin` goes to its active level the hardware disables all outputs (clears
+    /// `MOE`). Listen for `Event::Break` to get an interrupt on break.
+    pub fn with_break_pin<BKIN: BkinPin<TIM>>(mut self, _pin: BKIN, polarity: Polarity) -> Self {
+        self.tim.set_break(true, polarity == Polarity::ActiveHigh);
+        self
+    }
+
+    pub fn disable_break(&mut self) {
+        self.tim.set_break(false, false);
+    }
+
+    /// Re-enables the outputs automatically on the next update event after a break
+    ///
+    /// Enabled by default, if disabled `enable_outputs` has to be called after a break.
+    pub fn set_auto_output_enable(&mut self, b: bool) {
+        self.tim.set_auto_output_enable(b);
+    }
+
+    /// Selects off-states of enabled outputs
+    ///
+    /// `run` (`OSSR`) applies while outputs are enabled (`MOE = 1`) and a channel is
+    /// disabled, `idle` (`OSSI`) while outputs are disabled. If `true` the outputs are
+    /// driven to their inactive (idle) level, otherwise they are released.
+    pub fn set_off_state(&mut self, run: bool, idle: bool) {
+        self.tim.set_off_state(run, idle);
+    }
+
+    /// Write-protects the configuration up to the next reset
+    ///
+    /// Must be called after dead time, break and off-state configuration.
+    pub fn lock(&mut self, level: LockLevel) {
+        self.tim.set_lock(level as u8);
+    }
+
+    /// Preloads channel enables (`CCxE`, `CCxNE`) and output compare modes (`OCxM`)
+    ///
+    /// While enabled, changes made by `enable`, `disable`, `enable_complementary`,
+    /// `disable_complementary` and `set_channel_mode` take effect all at once on the next
+    /// commutation event generated by [`commutate`](Self::commutate) or, with
+    /// [`ComSource::Trigger`], by the trigger input. This is used for six-step (BLDC)
+    /// commutation. Listen for `Event::COM` to prepare the next step.
+    pub fn set_commutation_preload(&mut self, enable: bool, source: ComSource) {
+        self.tim
+            .set_com_preload(enable, source == ComSource::Trigger);
+    }
+
+    /// Generates a commutation event, applying the preloaded channel configuration
+    pub fn commutate(&mut self) {
+        self.tim.trigger_com();
+    }
 }
 
 pub struct Pwm<TIM, P, PINS, const FREQ: u32>
@@ -417,6 +696,13 @@ where
         TIM::enable_channel(PINS::check_used(channel) as u8, false)
     }
 
+    /// Sets the output compare mode of `channel`, e.g. [`Ocm::ForceInactive`] to hold
+    /// the output at its inactive level
+    pub fn set_channel_mode(&mut self, channel: Channel, mode: Ocm) {
+        self.tim
+            .preload_output_channel_in_mode(PINS::check_used(channel), mode);
+    }
+
     pub fn get_duty(&self, channel: Channel) -> u16 {
         TIM::read_cc_value(PINS::check_used(channel) as u8) as u16
     }
@@ -435,14 +721,238 @@ where
 
     /// If `0` returned means max_duty is 2^16
     pub fn get_max_duty(&self) -> u16 {
-        (TIM::read_auto_reload() as u16).wrapping_add(1)
+        max_duty::<TIM>()
     }
 
     pub fn get_period(&self) -> TimerDurationU32<FREQ> {
-        TimerDurationU32::from_ticks(TIM::read_auto_reload() + 1)
+        if TIM::is_center_aligned() {
+            TimerDurationU32::from_ticks(TIM::read_auto_reload() * 2)
+        } else {
+            TimerDurationU32::from_ticks(TIM::read_auto_reload() + 1)
+        }
     }
 
     pub fn set_period(&mut self, period: TimerDurationU32<FREQ>) {
-        self.tim.set_auto_reload(period.ticks() - 1).unwrap();
+        if TIM::is_center_aligned() {
+            // Counter counts up to ARR and back down
+            self.tim.set_auto_reload(period.ticks() / 2).unwrap();
+        } else {
+            self.tim.set_auto_reload(period.ticks() - 1).unwrap();
+        }
+    }
+
+    /// Sets the active level of `channel` output, e.g. for gate drivers with inverted inputs
+    pub fn with_polarity(mut self, channel: Channel, p: Polarity) -> Self {
+        self.set_polarity(channel, p);
+        self
+    }
+
+    /// Sets the active level of `channel` output
+    pub fn set_polarity(&mut self, channel: Channel, p: Polarity) {
+        TIM::set_channel_polarity(PINS::check_used(channel) as u8, p == Polarity::ActiveLow)
+    }
+}
+
+impl<TIM, P, PINS, const FREQ: u32> Pwm<TIM, P, PINS, FREQ>
+where
+    TIM: Instance + CenterAligned,
+    PINS: Pins<TIM, P>,
+{
+    /// Changes counting direction and alignment keeping the PWM period
+    ///
+    /// The maximum duty changes, so duty cycles should be set again afterwards.
+    pub fn set_count_mode(&mut self, mode: CountMode) {
+        let period = self.get_period();
+        set_count_mode(&mut self.tim, mode);
+        self.set_period(period);
+        self.tim.trigger_update();
+        self.tim.enable_counter();
+    }
+}
+
+impl<TIM, P, PINS, const FREQ: u32> Pwm<TIM, P, PINS, FREQ>
+where
+    TIM: Instance + WithPwm + SlaveTimer,
+    PINS: Pins<TIM, P>,
+{
+    /// Stops the counter and restarts it on the next rising edge of `trigger` lagging
+    /// `degrees` (0 - 359) behind it
+    ///
+    /// See [phase-shifted PWM](self#phase-shifted-pwm). Panics in center-aligned modes.
+    pub fn set_phase_shift(&mut self, trigger: TriggerSource, degrees: u16) {
+        set_phase_shift(&mut self.tim, trigger, degrees);
+    }
+}
+
+impl<TIM, P, PINS, const FREQ: u32> Pwm<TIM, P, PINS, FREQ>
+where
+    TIM: Instance + Advanced,
+    PINS: Pins<TIM, P>,
+{
+    /// Enables the complementary output `CHxN` of channel `C` on `npin`
+    pub fn with_complementary<NPIN, const C: u8>(mut self, _npin: NPIN) -> Self
+    where
+        NPIN: NCPin<TIM, C>,
+    {
+        self.enable_complementary(channel(C));
+        self
+    }
+
+    pub fn enable_complementary(&mut self, channel: Channel) {
+        TIM::enable_nchannel(PINS::check_used(channel) as u8, true)
+    }
+
+    pub fn disable_complementary(&mut self, channel: Channel) {
+        TIM::enable_nchannel(PINS::check_used(channel) as u8, false)
+    }
+
+    /// Sets the active level of `channel` complementary output
+    pub fn set_complementary_polarity(&mut self, channel: Channel, p: Polarity) {
+        TIM::set_nchannel_polarity(PINS::check_used(channel) as u8, p == Polarity::ActiveLow)
+    }
+
+    /// Sets the idle level of `channel` output, see [`set_idle_state`](Self::set_idle_state)
+    pub fn with_idle_state(mut self, channel: Channel, state: PinState) -> Self {
+        self.set_idle_state(channel, state);
+        self
+    }
+
+    /// Sets the level of `channel` output while all outputs are disabled (`MOE = 0`)
+    ///
+    /// Applied after the dead time, if off-state selection for idle mode (`OSSI`) is set,
+    /// see [`set_off_state`](Self::set_off_state). The output and its complementary
+    /// output must not both be active at idle. Locked from [`LockLevel::Level1`].
+    pub fn set_idle_state(&mut self, channel: Channel, state: PinState) {
+        TIM::set_idle_state(PINS::check_used(channel) as u8, state == PinState::High)
+    }
+
+    /// Sets the level of `channel` complementary output while all outputs are disabled
+    pub fn set_complementary_idle_state(&mut self, channel: Channel, state: PinState) {
+        TIM::set_nchannel_idle_state(PINS::check_used(channel) as u8, state == PinState::High)
+    }
+
+    /// Inserts a dead time of at least `ns` nanoseconds between a channel output and its
+    /// complementary output
+    ///
+    /// Changes the clock division of the timer, which is also used by the input filters.
+    pub fn set_dead_time_ns(&mut self, ns: u32) {
+        // Timer clock is a multiple of `FREQ`
+        let clk = FREQ * (self.tim.read_prescaler() as u32 + 1);
+        let (ckd, dtg) = compute_dead_time(ns, clk);
+        self.tim.set_dead_time(ckd, dtg);
+    }
+
+    /// Enables all outputs (`MOE`)
+    pub fn enable_outputs(&mut self) {
+        self.tim.set_main_output(true);
+    }
+
+    /// Disables all outputs (`MOE`), they go into their idle state
+    ///
+    /// With [`set_auto_output_enable`](Self::set_auto_output_enable) (the default) the
+    /// outputs are enabled again on the next update event, disable it first to keep them off.
+    pub fn disable_outputs(&mut self) {
+        self.tim.set_main_output(false);
+    }
+
+    /// Enables the break input on `pin`
+    ///
+    /// When `pin` goes to its active level the hardware disables all outputs (clears
+    /// `MOE`). Listen for `Event::Break` to get an interrupt on break.
+    pub fn with_break_pin<BKIN: BkinPin<TIM>>(mut self, _pin: BKIN, polarity: Polarity) -> Self {
+        self.tim.set_break(true, polarity == Polarity::ActiveHigh);
+        self
+    }
+
+    pub fn disable_break(&mut self) {
+        self.tim.set_break(false, false);
+    }
+
+    /// Re-enables the outputs automatically on the next update event after a break
+    ///
+    /// Enabled by default, if disabled `enable_outputs` has to be called after a break.
+    pub fn set_auto_output_enable(&mut self, b: bool) {
+        self.tim.set_auto_output_enable(b);
+    }
+
+    /// Selects off-states of enabled outputs
+    ///
+    /// `run` (`OSSR`) applies while outputs are enabled (`MOE = 1`) and a channel is
+    /// disabled, `idle` (`OSSI`) while outputs are disabled. If `true` the outputs are
+    /// driven to their inactive (idle) level, otherwise they are released.
+    pub fn set_off_state(&mut self, run: bool, idle: bool) {
+        self.tim.set_off_state(run, idle);
+    }
+
+    /// Write-protects the configuration up to the next reset
+    ///
+    /// Must be called after dead time, break and off-state configuration.
+    pub fn lock(&mut self, level: LockLevel) {
+        self.tim.set_lock(level as u8);
+    }
+
+    /// Preloads channel enables (`CCxE`, `CCxNE`) and output compare modes (`OCxM`)
+    ///
+    /// While enabled, changes made by `enable`, `disable`, `enable_complementary`,
+    /// `disable_complementary` and `set_channel_mode` take effect all at once on the next
+    /// commutation event generated by [`commutate`](Self::commutate) or, with
+    /// [`ComSource::Trigger`], by the trigger input. This is used for six-step (BLDC)
+    /// commutation. Listen for `Event::COM` to prepare the next step.
+    pub fn set_commutation_preload(&mut self, enable: bool, source: ComSource) {
+        self.tim
+            .set_com_preload(enable, source == ComSource::Trigger);
+    }
+
+    /// Generates a commutation event, applying the preloaded channel configuration
+    pub fn commutate(&mut self) {
+        self.tim.trigger_com();
+    }
+}
+
+/// In center-aligned modes the counter counts up to `ARR` and back down
+fn max_duty<TIM: WithPwm>() -> u16 {
+    let arr = TIM::read_auto_reload() as u16;
+    if TIM::is_center_aligned() {
+        arr
+    } else {
+        arr.wrapping_add(1)
+    }
+}
+
+fn set_count_mode<TIM: CenterAligned>(tim: &mut TIM, mode: CountMode) {
+    let (cms, down) = match mode {
+        CountMode::Up => (0, false),
+        CountMode::Down => (0, true),
+        CountMode::CenterAligned1 => (1, false),
+        CountMode::CenterAligned2 => (2, false),
+        CountMode::CenterAligned3 => (3, false),
+    };
+    // Alignment can't be changed while the counter is enabled
+    tim.disable_counter();
+    tim.set_count_mode(cms, down);
+}
+
+fn set_phase_shift<TIM: WithPwm + SlaveTimer>(tim: &mut TIM, trigger: TriggerSource, degrees: u16) {
+    assert!(degrees < 360 && !TIM::is_center_aligned());
+    let period = TIM::read_auto_reload() as u64 + 1;
+    let lag = period * degrees as u64 / 360;
+    // A lagging counter starts `lag` ticks before the end of the period, which is 0 when
+    // counting down
+    let offset = if TIM::is_down_counting() {
+        lag
+    } else {
+        (period - lag) % period
+    };
+    tim.disable_counter();
+    tim.write_count(offset as u32);
+    tim.slave_mode(SlaveMode::Trigger, trigger);
+}
+
+const fn channel(c: u8) -> Channel {
+    match c {
+        0 => Channel::C1,
+        1 => Channel::C2,
+        2 => Channel::C3,
+        _ => Channel::C4,
     }
 }
@@ -188,6 +188,32 @@ macro_rules! hal {
             pub fn get_duty_cycle_clocks(&self) -> <$TIM as General>::Width {
                 self.tim.ccr2().read().ccr().bits()
             }
+            /// Period and duty cycle in terms of clock cycles
+            ///
+            /// Both are read between the same two period captures, the reads are repeated if
+            /// a new period was captured in between (`CC1IF` set again).
+            pub fn get_period_and_duty_cycle_clocks(
+                &self,
+            ) -> (<$TIM as General>::Width, <$TIM as General>::Width) {
+                loop {
+                    // Reading CCR1 clears CC1IF
+                    let period = self.get_period_clocks();
+                    let duty = self.get_duty_cycle_clocks();
+                    if self.tim.sr.read().cc1if().bit_is_clear() {
+                        return (period, duty);
+                    }
+                }
+            }
+            /// Frequency of PWM signal, or 0 Hz if no period was captured yet
+            pub fn get_frequency(&self) -> Hertz {
+                let period_clocks: u32 = self.get_period_clocks().into();
+                if period_clocks == 0 {
+                    return Hertz::from_raw(0);
+                }
+                let psc = self.tim.read_prescaler() as u64;
+                // 64-bit math, the divisor overflows u32 with large prescalers and periods
+                Hertz::from_raw((self.clk.raw() as u64 / ((psc + 1) * period_clocks as u64)) as u32)
+            }
             /// Observed duty cycle as a float in range [0.00, 1.00]
             pub fn get_duty_cycle(&self) -> f32 {
                 let period_clocks = self.get_period_clocks();
@@ -0,0 +1,219 @@
+//! `embassy-time` driver on TIM2 or TIM5
+//!
+//! Enabled with the `time-driver-tim2` or `time-driver-tim5` feature. The 32-bit counter
+//! is extended to 64 bits by a period counter which is incremented on overflow and
+//! at half of the range (CC2), channel 1 compare is used for the alarm.
+//!
+//! Call [init] after clocks are frozen and [on_interrupt] from the timer interrupt:
+//! ```ignore
+//! #[interrupt]
+//! fn TIM2() {
+//!     stm32f4xx_hal::timer::time_driver::on_interrupt();
+//! }
+//! ```
+
+use crate::pac;
+use crate::rcc::{BusTimerClock, Clocks, Enable, Reset};
+use core::cell::Cell;
+use core::sync::atomic::{compiler_fence, AtomicBool, AtomicU32, Ordering};
+use cortex_m::interrupt::{self, CriticalSection, Mutex};
+use embassy_time_driver::{AlarmHandle, Driver, TICK_HZ};
+
+#[cfg(all(feature = "time-driver-tim2", feature = "time-driver-tim5"))]
+compile_error!("Only one of `time-driver-tim2` and `time-driver-tim5` can be enabled");
+
+#[cfg(all(feature = "time-driver-tim2", not(feature = "tim2")))]
+compile_error!("TIM2 is not available on this MCU");
+
+#[cfg(all(feature = "time-driver-tim5", feature = "stm32f410"))]
+compile_error!("TIM5 on F410 is 16-bit and can't be used as time driver");
+
+#[cfg(all(feature = "time-driver-tim2", feature = "tim2"))]
+type Tim = pac::TIM2;
+#[cfg(all(feature = "time-driver-tim2", feature = "tim2"))]
+const INTERRUPT: pac::Interrupt = pac::Interrupt::TIM2;
+
+#[cfg(all(feature = "time-driver-tim5", not(feature = "time-driver-tim2")))]
+type Tim = pac::TIM5;
+#[cfg(all(feature = "time-driver-tim5", not(feature = "time-driver-tim2")))]
+const INTERRUPT: pac::Interrupt = pac::Interrupt::TIM5;
+
+const HALF: u32 = 1 << 31;
+
+type Callback = (fn(*mut ()), *mut ());
+
+struct AlarmState {
+    timestamp: Cell<u64>,
+    callback: Cell<Option<Callback>>,
+}
+
+// NOTE(unsafe) only accessed inside of critical sections
+unsafe impl Send for AlarmState {}
+
+/// `embassy-time` driver state
+pub struct TimerDriver {
+    /// Number of half-overflows of the counter
+    period: AtomicU32,
+    alarm_allocated: AtomicBool,
+    alarm: Mutex<AlarmState>,
+}
+
+embassy_time_driver::time_driver_impl!(static DRIVER: TimerDriver = TimerDriver {
+    period: AtomicU32::new(0),
+    alarm_allocated: AtomicBool::new(false),
+    alarm: Mutex::new(AlarmState {
+        timestamp: Cell::new(u64::MAX),
+        callback: Cell::new(None),
+    }),
+});
+
+/// Starts the timer counting at `TICK_HZ` and enables its interrupt in the NVIC
+///
+/// Timer clock must be a multiple of `TICK_HZ`.
+pub fn init(tim: Tim, clocks: &Clocks) {
+    unsafe {
+        //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
+        let rcc = &(*pac::RCC::ptr());
+        // Enable and reset the timer peripheral
+        Tim::enable(rcc);
+        Tim::reset(rcc);
+    }
+
+    let clk = Tim::timer_clock(clocks).raw() as u64;
+    assert!(clk % TICK_HZ == 0);
+    let psc = u16::try_from(clk / TICK_HZ - 1).unwrap();
+
+    #[allow(unused_unsafe)]
+    tim.psc.write(|w| unsafe { w.psc().bits(psc) });
+    tim.arr.write(|w| w.bits(u32::MAX));
+    // Half-overflow of the counter
+    tim.ccr[1].write(|w| w.bits(HALF));
+
+    // Load the registers without generating an update interrupt
+    tim.cr1.modify(|_, w| w.urs().set_bit());
+    tim.egr.write(|w| w.ug().set_bit());
+    tim.cr1.modify(|_, w| w.urs().clear_bit());
+
+    tim.sr.write(|w| unsafe { w.bits(0) });
+    tim.dier.write(|w| w.uie().set_bit().cc2ie().set_bit());
+    tim.cr1.modify(|_, w| w.cen().set_bit());
+
+    unsafe {
+        cortex_m::peripheral::NVIC::unmask(INTERRUPT);
+    }
+}
+
+/// Handles timer events. Must be called from the TIM2 (TIM5) interrupt handler
+pub fn on_interrupt() {
+    DRIVER.on_interrupt()
+}
+
+#[inline(always)]
+fn regs() -> &'static <Tim as core::ops::Deref>::Target {
+    unsafe { &*Tim::ptr() }
+}
+
+/// Combines period and counter, the counter `MSB` has to match the period parity
+fn calc_now(period: u32, counter: u32) -> u64 {
+    ((period as u64) << 31) + (counter ^ ((period & 1) << 31)) as u64
+}
+
+impl TimerDriver {
+    fn on_interrupt(&self) {
+        interrupt::free(|cs| {
+            let tim = regs();
+            let sr = tim.sr.read();
+            let dier = tim.dier.read();
+
+            // Flags are cleared by writing 0, so clear only the ones being handled
+            tim.sr.write(|w| unsafe { w.bits(0xffff & !sr.bits()) });
+
+            if sr.uif().bit_is_set() {
+                self.next_period(cs);
+            }
+            if sr.cc2if().bit_is_set() {
+                self.next_period(cs);
+            }
+            if sr.cc1if().bit_is_set() && dier.cc1ie().bit_is_set() {
+                self.trigger_alarm(cs);
+            }
+        })
+    }
+
+    fn next_period(&self, cs: &CriticalSection) {
+        let period = self.period.load(Ordering::Relaxed).wrapping_add(1);
+        self.period.store(period, Ordering::Relaxed);
+        let t = (period as u64) << 31;
+
+        // Enable compare if the alarm happens before the channel matches a second time
+        let at = self.alarm.borrow(cs).timestamp.get();
+        if at < t + 0xC000_0000 {
+            regs().dier.modify(|_, w| w.cc1ie().set_bit());
+        }
+    }
+
+    fn trigger_alarm(&self, cs: &CriticalSection) {
+        regs().dier.modify(|_, w| w.cc1ie().clear_bit());
+
+        let alarm = self.alarm.borrow(cs);
+        alarm.timestamp.set(u64::MAX);
+        if let Some((f, ctx)) = alarm.callback.get() {
+            f(ctx);
+        }
+    }
+}
+
+impl Driver for TimerDriver {
+    fn now(&self) -> u64 {
+        let period = self.period.load(Ordering::Relaxed);
+        compiler_fence(Ordering::Acquire);
+        let counter = regs().cnt.read().bits();
+        calc_now(period, counter)
+    }
+
+    unsafe fn allocate_alarm(&self) -> Option<AlarmHandle> {
+        // Only one alarm (CC1) is available
+        if self.alarm_allocated.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(AlarmHandle::new(0))
+        }
+    }
+
+    fn set_alarm_callback(&self, _alarm: AlarmHandle, callback: fn(*mut ()), ctx: *mut ()) {
+        interrupt::free(|cs| {
+            self.alarm.borrow(cs).callback.set(Some((callback, ctx)));
+        })
+    }
+
+    fn set_alarm(&self, _alarm: AlarmHandle, timestamp: u64) -> bool {
+        interrupt::free(|cs| {
+            let tim = regs();
+            let alarm = self.alarm.borrow(cs);
+            alarm.timestamp.set(timestamp);
+
+            let t = self.now();
+            if timestamp <= t {
+                // Alarm has already passed
+                tim.dier.modify(|_, w| w.cc1ie().clear_bit());
+                alarm.timestamp.set(u64::MAX);
+                return false;
+            }
+
+            // The lower 32 bits of `now` are the counter value
+            tim.ccr[0].write(|w| w.bits(timestamp as u32));
+
+            let diff = timestamp - t;
+            tim.dier.modify(|_, w| w.cc1ie().bit(diff < 0xC000_0000));
+
+            // The counter could have passed the compare value while it was being set
+            if timestamp <= self.now() {
+                tim.dier.modify(|_, w| w.cc1ie().clear_bit());
+                alarm.timestamp.set(u64::MAX);
+                return false;
+            }
+
+            true
+        })
+    }
+}